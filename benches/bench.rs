@@ -0,0 +1,60 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use rust_i18n::t;
+
+rust_i18n::i18n!("tests/locales", fallback = "en-US");
+
+fn bench_t(c: &mut Criterion) {
+    rust_i18n::set_locale("en-US");
+
+    c.bench_function("t", |b| b.iter(|| t!("hello")));
+
+    c.bench_function("t_with_locale", |b| {
+        b.iter(|| t!("hello", locale = "tr-TR"))
+    });
+
+    c.bench_function("t_with_fallback", |b| {
+        b.iter(|| t!("hello", locale = "en-GB"))
+    });
+
+    c.bench_function("t_with_args", |b| {
+        b.iter(|| t!("messages.hello", name = "Jason"))
+    });
+
+    c.bench_function("t_with_count", |b| b.iter(|| t!("inbox", count = 2)));
+}
+
+/// Reads from many threads at once, with and without a concurrent `t_add!` writer
+fn bench_t_concurrent(c: &mut Criterion) {
+    const THREADS: usize = 8;
+    const READS: usize = 1000;
+
+    fn read_in_threads() {
+        std::thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..READS {
+                        std::hint::black_box(t!("hello", locale = "en-US"));
+                    }
+                });
+            }
+        });
+    }
+
+    c.bench_function("t_concurrent", |b| b.iter(read_in_threads));
+
+    c.bench_function("t_concurrent_with_add", |b| {
+        b.iter(|| {
+            std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    for i in 0..10 {
+                        rust_i18n::t_add!("en-US", &format!("bench.added.{}", i), "Added");
+                    }
+                });
+                read_in_threads();
+            });
+        })
+    });
+}
+
+criterion_group!(benches, bench_t, bench_t_concurrent);
+criterion_main!(benches);
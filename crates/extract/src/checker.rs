@@ -0,0 +1,503 @@
+use crate::extractor::{Location, Message};
+use crate::generator::{is_todo_file, relative_path};
+use crate::pruner::KeyPatterns;
+use crate::updater::get_key;
+use anyhow::Error;
+use rust_i18n_support::plural::PluralCategory;
+use rust_i18n_support::{load_files, try_load_locales, Layout};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+const PLURAL_CATEGORIES: [PluralCategory; 6] = [
+    PluralCategory::Zero,
+    PluralCategory::One,
+    PluralCategory::Two,
+    PluralCategory::Few,
+    PluralCategory::Many,
+    PluralCategory::Other,
+];
+
+/// The kind of a problem of a key in a locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IssueKind {
+    /// The key is used in the source code, but the locale doesn't have it.
+    Missing,
+    /// The key of the locale is not used in the source code.
+    Unused,
+    /// The key of the default locale is not translated in the locale.
+    Untranslated,
+}
+
+impl IssueKind {
+    /// The rule id in the reports, e.g. `missing-key`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            IssueKind::Missing => "missing-key",
+            IssueKind::Unused => "unused-key",
+            IssueKind::Untranslated => "untranslated-key",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            IssueKind::Missing => {
+                "The key is used in the source code, but not defined in the locale."
+            }
+            IssueKind::Unused => {
+                "The key is defined in the locale, but not used in the source code."
+            }
+            IssueKind::Untranslated => {
+                "The key is defined in the default locale, but not translated in the locale."
+            }
+        }
+    }
+
+    /// The SARIF level, the missing keys are errors.
+    fn level(&self) -> &'static str {
+        match self {
+            IssueKind::Missing => "error",
+            IssueKind::Unused | IssueKind::Untranslated => "warning",
+        }
+    }
+}
+
+/// A problem of a key in a locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub locale: String,
+    pub key: String,
+    /// The source locations of a missing key.
+    pub locations: Vec<Location>,
+    /// The locale file of an unused key, or the file of the default locale of an untranslated key.
+    pub file: Option<PathBuf>,
+}
+
+impl Issue {
+    fn message(&self, default_locale: &str) -> String {
+        match self.kind {
+            IssueKind::Missing => format!("`{}` is missing in {}", self.key, self.locale),
+            IssueKind::Unused => format!("`{}` of {} is not used", self.key, self.locale),
+            IssueKind::Untranslated => format!(
+                "`{}` of {} is not translated in {}",
+                self.key, default_locale, self.locale
+            ),
+        }
+    }
+
+    /// The source locations, or the locale file, relative to `source_root`.
+    fn places(&self, source_root: &Path) -> Vec<(String, Option<usize>)> {
+        let mut places = self
+            .locations
+            .iter()
+            .map(|l| (relative_path(source_root, &l.file), Some(l.line)))
+            .collect::<Vec<_>>();
+        if let Some(file) = &self.file {
+            places.push((relative_path(source_root, file), None));
+        }
+        places
+    }
+}
+
+/// The issues of the keys of the locales, in the order of the locales, then by kind and key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub default_locale: String,
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    /// Whether there are the issues of the kind.
+    pub fn has(&self, kind: IssueKind) -> bool {
+        self.issues.iter().any(|issue| issue.kind == kind)
+    }
+
+    fn count(&self, kind: IssueKind) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.kind == kind)
+            .count()
+    }
+
+    /// The human readable report, a line per issue and a summary.
+    pub fn to_text(&self, source_root: &Path) -> String {
+        let mut content = String::new();
+        for issue in &self.issues {
+            let places = issue
+                .places(source_root)
+                .into_iter()
+                .map(|(file, line)| match line {
+                    Some(line) => format!("{}:{}", file, line),
+                    None => file,
+                })
+                .collect::<Vec<_>>();
+            content.push_str(&format!(
+                "[{}] {}: {}",
+                issue.locale,
+                issue.kind.id(),
+                issue.message(&self.default_locale)
+            ));
+            if !places.is_empty() {
+                content.push_str(&format!(", at {}", places.join(", ")));
+            }
+            content.push('\n');
+        }
+
+        if self.issues.is_empty() {
+            content.push_str("All keys are translated and used.\n");
+        } else {
+            content.push_str(&format!(
+                "Found {} missing, {} unused and {} untranslated keys.\n",
+                self.count(IssueKind::Missing),
+                self.count(IssueKind::Unused),
+                self.count(IssueKind::Untranslated)
+            ));
+        }
+        content
+    }
+
+    /// The JSON report, with the issues and their counts.
+    pub fn to_json(&self, source_root: &Path) -> Result<String, Error> {
+        let issues = self
+            .issues
+            .iter()
+            .map(|issue| {
+                let mut value = json!({
+                    "kind": issue.kind.id(),
+                    "locale": issue.locale,
+                    "key": issue.key,
+                    "message": issue.message(&self.default_locale),
+                    "locations": issue
+                        .locations
+                        .iter()
+                        .map(|l| json!({
+                            "file": relative_path(source_root, &l.file),
+                            "line": l.line,
+                        }))
+                        .collect::<Vec<_>>(),
+                });
+                if let Some(file) = &issue.file {
+                    value["file"] = relative_path(source_root, file).into();
+                }
+                value
+            })
+            .collect::<Vec<_>>();
+
+        let report = json!({
+            "default_locale": self.default_locale,
+            "missing": self.count(IssueKind::Missing),
+            "unused": self.count(IssueKind::Unused),
+            "untranslated": self.count(IssueKind::Untranslated),
+            "issues": issues,
+        });
+        Ok(format!("{}\n", serde_json::to_string_pretty(&report)?))
+    }
+
+    /// The SARIF 2.1.0 report, for the code scanning of CI.
+    pub fn to_sarif(&self, source_root: &Path) -> Result<String, Error> {
+        let kinds = [
+            IssueKind::Missing,
+            IssueKind::Unused,
+            IssueKind::Untranslated,
+        ];
+        let rules = kinds
+            .iter()
+            .map(|kind| {
+                json!({
+                    "id": kind.id(),
+                    "shortDescription": { "text": kind.description() },
+                    "defaultConfiguration": { "level": kind.level() },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let results = self
+            .issues
+            .iter()
+            .map(|issue| {
+                let locations = issue
+                    .places(source_root)
+                    .into_iter()
+                    .map(|(file, line)| {
+                        let mut location = json!({ "artifactLocation": { "uri": file } });
+                        if let Some(line) = line {
+                            location["region"] = json!({ "startLine": line });
+                        }
+                        json!({ "physicalLocation": location })
+                    })
+                    .collect::<Vec<_>>();
+
+                json!({
+                    "ruleId": issue.kind.id(),
+                    "ruleIndex": kinds.iter().position(|kind| *kind == issue.kind),
+                    "level": issue.kind.level(),
+                    "message": { "text": issue.message(&self.default_locale) },
+                    "locations": locations,
+                    "properties": { "locale": issue.locale, "key": issue.key },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let sarif = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "cargo-i18n",
+                        "informationUri": "https://github.com/longbridgeapp/rust-i18n",
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        });
+        Ok(format!("{}\n", serde_json::to_string_pretty(&sarif)?))
+    }
+}
+
+/// Check the keys of the locales against the keys used in the source code, and the keys of the default locale.
+///
+/// The plural forms and the items of a key, like `inbox.one` or `days.0`, are the translations of the key,
+/// and a plural form of the default locale is translated if the locale has any plural form of the key.
+/// The `TODO.<locale>.yml` files are not translations, so their keys are missing, and the keys matched by `keep`
+/// are never unused.
+pub fn check<'a>(
+    locales_path: &Path,
+    layout: Layout,
+    locales: &[String],
+    default_locale: &str,
+    keep: &KeyPatterns,
+    messages: impl IntoIterator<Item = &'a Message>,
+) -> Result<Report, Error> {
+    let path = locales_path.display().to_string();
+    let translations = try_load_locales(&path, layout, is_todo_file)?;
+    let files = load_files(&path, layout, is_todo_file)?;
+    let file_of = |locale: &str, key: &str| {
+        files
+            .iter()
+            .find(|(_, trs)| trs.get(locale).and_then(|v| get_key(v, key)).is_some())
+            .map(|(path, _)| path.clone())
+    };
+
+    let messages = messages
+        .into_iter()
+        .map(|m| (m.key.as_str(), m))
+        .collect::<HashMap<_, _>>();
+    let empty = HashMap::new();
+    let default_keys = key_set(translations.get(default_locale).unwrap_or(&empty));
+
+    let mut report = Report {
+        default_locale: default_locale.to_string(),
+        issues: vec![],
+    };
+    for locale in locales {
+        let keys = key_set(translations.get(locale).unwrap_or(&empty));
+        let mut issues = vec![];
+
+        for (key, m) in &messages {
+            if !has_translation(&keys, key) {
+                issues.push(Issue {
+                    kind: IssueKind::Missing,
+                    locale: locale.clone(),
+                    key: key.to_string(),
+                    locations: m.locations.clone(),
+                    file: None,
+                });
+            }
+        }
+
+        for key in &keys {
+            if !is_used(&messages, key) && !keep.matches(key) {
+                issues.push(Issue {
+                    kind: IssueKind::Unused,
+                    locale: locale.clone(),
+                    key: key.to_string(),
+                    locations: vec![],
+                    file: file_of(locale, key),
+                });
+            }
+        }
+
+        if locale != default_locale {
+            for key in &default_keys {
+                if !keys.contains(key) && !has_plural_form(&keys, key) {
+                    issues.push(Issue {
+                        kind: IssueKind::Untranslated,
+                        locale: locale.clone(),
+                        key: key.to_string(),
+                        locations: vec![],
+                        file: file_of(default_locale, key),
+                    });
+                }
+            }
+        }
+
+        issues.sort_by(|a, b| (a.kind, &a.key).cmp(&(b.kind, &b.key)));
+        report.issues.extend(issues);
+    }
+
+    Ok(report)
+}
+
+fn key_set(trs: &HashMap<String, String>) -> BTreeSet<&str> {
+    trs.keys().map(|key| key.as_str()).collect()
+}
+
+/// Whether the locale has the key, or the keys nested in it like the plural forms.
+fn has_translation(keys: &BTreeSet<&str>, key: &str) -> bool {
+    keys.contains(key) || keys.iter().any(|k| is_child(k, key))
+}
+
+/// Whether the key of a locale is used, or it's nested in a used key.
+fn is_used(messages: &HashMap<&str, &Message>, key: &str) -> bool {
+    messages.contains_key(key) || messages.keys().any(|used| is_child(key, used))
+}
+
+/// Whether the key is a plural form, and the locale has any plural form of its key.
+fn has_plural_form(keys: &BTreeSet<&str>, key: &str) -> bool {
+    let Some((parent, form)) = key.rsplit_once('.') else {
+        return false;
+    };
+    let is_plural = |form: &str| PLURAL_CATEGORIES.iter().any(|c| c.as_str() == form);
+
+    is_plural(form)
+        && keys.iter().any(|k| {
+            k.strip_prefix(parent)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .is_some_and(is_plural)
+        })
+}
+
+fn is_child(key: &str, parent: &str) -> bool {
+    key.strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn parse(content: &str) -> Value {
+        serde_json::from_str(content).unwrap()
+    }
+
+    fn message(key: &str, line: usize) -> Message {
+        Message {
+            key: key.into(),
+            index: 0,
+            locations: vec![Location {
+                file: PathBuf::from("./src/main.rs"),
+                line,
+            }],
+            notes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-checker-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.yml"),
+            "hello: Hello\nold: Old\ninbox:\n  one: One\n  other: Many\ndays: [Sun, Mon]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("ru.yml"),
+            "hello: Привет\ninbox:\n  one: Один\n  few: Несколько\n  many: Много\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("TODO.ru.yml"), "bye: bye\n").unwrap();
+
+        let messages = [
+            message("hello", 1),
+            message("inbox", 2),
+            message("bye", 3),
+            message("days", 4),
+        ];
+        let locales = ["en".to_string(), "ru".to_string()];
+        let keep = KeyPatterns::default();
+        let report = check(&dir, Layout::default(), &locales, "en", &keep, &messages).unwrap();
+
+        let issues = report
+            .issues
+            .iter()
+            .map(|issue| (issue.locale.as_str(), issue.kind, issue.key.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            vec![
+                ("en", IssueKind::Missing, "bye"),
+                ("en", IssueKind::Unused, "old"),
+                ("ru", IssueKind::Missing, "bye"),
+                ("ru", IssueKind::Missing, "days"),
+                ("ru", IssueKind::Untranslated, "days.0"),
+                ("ru", IssueKind::Untranslated, "days.1"),
+                ("ru", IssueKind::Untranslated, "old"),
+            ]
+        );
+        assert!(report.has(IssueKind::Missing));
+        assert_eq!(report.issues[1].file, Some(dir.join("en.yml")));
+        assert_eq!(report.issues[6].file, Some(dir.join("en.yml")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reports() {
+        let report = Report {
+            default_locale: "en".into(),
+            issues: vec![
+                Issue {
+                    kind: IssueKind::Missing,
+                    locale: "de".into(),
+                    key: "hello".into(),
+                    locations: message("hello", 10).locations,
+                    file: None,
+                },
+                Issue {
+                    kind: IssueKind::Untranslated,
+                    locale: "de".into(),
+                    key: "bye".into(),
+                    locations: vec![],
+                    file: Some(PathBuf::from("./locales/en.yml")),
+                },
+            ],
+        };
+        let root = Path::new("./");
+
+        assert_eq!(
+            report.to_text(root),
+            "[de] missing-key: `hello` is missing in de, at src/main.rs:10\n\
+             [de] untranslated-key: `bye` of en is not translated in de, at locales/en.yml\n\
+             Found 1 missing, 0 unused and 1 untranslated keys.\n"
+        );
+        assert_eq!(
+            Report::default().to_text(root),
+            "All keys are translated and used.\n"
+        );
+
+        let json = parse(&report.to_json(root).unwrap());
+        assert_eq!(json["missing"], 1);
+        assert_eq!(json["issues"][0]["locations"][0]["file"], "src/main.rs");
+        assert_eq!(json["issues"][1]["file"], "locales/en.yml");
+
+        let sarif = parse(&report.to_sarif(root).unwrap());
+        assert_eq!(sarif["version"], "2.1.0");
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(results[0]["ruleId"], "missing-key");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            10
+        );
+        assert_eq!(results[1]["ruleIndex"], 2);
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "locales/en.yml"
+        );
+    }
+}
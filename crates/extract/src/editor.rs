@@ -0,0 +1,501 @@
+use crate::updater::{format_content, parse_content};
+use anyhow::Error;
+use rust_i18n_support::FileFormat;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The new content of a locale file, see [`edit_content`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Edit {
+    pub content: String,
+    /// Whether the file is written as a whole, which loses its comments and formatting.
+    pub rewritten: bool,
+}
+
+/// Edit the content of a YAML or TOML file to the new value, only the changed keys are written again,
+/// the other lines keep their comments, quoting and order.
+///
+/// The file is written as a whole if it can't be edited so, like with the YAML anchors, and a JSON file
+/// always, then `rewritten` tells whether any comments or formatting are lost.
+pub(crate) fn edit_content(format: FileFormat, text: &str, value: &Value) -> Result<Edit, Error> {
+    let old = parse_content(format, text)?;
+    let edited = match (format, &old, value) {
+        (FileFormat::Yaml, Value::Object(old), Value::Object(new)) => {
+            let lines = text.lines().map(String::from).collect::<Vec<_>>();
+            edit_mapping(&lines, 0, old, new).map(|lines| join_lines(&lines))
+        }
+        (FileFormat::Toml, Value::Object(old), Value::Object(new)) => edit_toml(text, old, new),
+        _ => None,
+    };
+
+    // The edited content is only used if it reads as the new value
+    if let Some(content) =
+        edited.filter(|content| parse_content(format, content).is_ok_and(|edited| &edited == value))
+    {
+        return Ok(Edit {
+            content,
+            rewritten: false,
+        });
+    }
+    Ok(Edit {
+        content: format_content(format, value)?,
+        rewritten: format_content(format, &old)? != text,
+    })
+}
+
+fn join_lines(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// A part of the lines of a YAML block mapping.
+enum Part {
+    /// The blank and comment lines between the entries.
+    Lines(Vec<String>),
+    /// An entry with the comments right above its key, and the nested lines of its value.
+    Entry {
+        key: String,
+        comments: Vec<String>,
+        lines: Vec<String>,
+    },
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn is_sequence_item(line: &str) -> bool {
+    let line = line.trim_start_matches(' ');
+    line == "-" || line.starts_with("- ")
+}
+
+/// The key of a YAML mapping entry like `key: value`, `"key": value` or `'key':`, with the text after the `:`.
+fn parse_key(line: &str) -> Option<(String, &str)> {
+    let bytes = line.as_bytes();
+    let end = match bytes.first()? {
+        b'"' => {
+            let mut i = 1;
+            loop {
+                match bytes.get(i)? {
+                    b'\\' => i += 2,
+                    b'"' => break i + 1,
+                    _ => i += 1,
+                }
+            }
+        }
+        b'\'' => {
+            let mut i = 1;
+            loop {
+                match bytes.get(i)? {
+                    b'\'' if bytes.get(i + 1) == Some(&b'\'') => i += 2,
+                    b'\'' => break i + 1,
+                    _ => i += 1,
+                }
+            }
+        }
+        b'?' | b'{' | b'[' | b'&' | b'*' | b'!' | b'|' | b'>' | b'#' => return None,
+        _ if is_sequence_item(line) => return None,
+        _ => line
+            .match_indices(':')
+            .map(|(i, _)| i)
+            .find(|&i| matches!(bytes.get(i + 1), None | Some(b' ')))?,
+    };
+
+    let token = line[..end].trim_end();
+    let rest = line[end..].trim_start().strip_prefix(':')?;
+    let key = if token.starts_with(['"', '\'']) {
+        serde_yaml::from_str(token).ok()?
+    } else {
+        token.to_string()
+    };
+    Some((key, rest))
+}
+
+/// Split the lines of a YAML block mapping at the indent into its parts, `None` if they are not a block mapping.
+fn split_mapping(lines: &[String], indent: usize) -> Option<Vec<Part>> {
+    let mut parts = vec![];
+    let mut pending = vec![];
+    for line in lines {
+        if is_blank_or_comment(line) || (indent == 0 && line.starts_with("---")) {
+            pending.push(line.clone());
+            continue;
+        }
+
+        let line_indent = indent_of(line);
+        let is_nested = line_indent > indent || (line_indent == indent && is_sequence_item(line));
+        if is_nested {
+            let Some(Part::Entry { lines, .. }) = parts.last_mut() else {
+                return None;
+            };
+            lines.append(&mut pending);
+            lines.push(line.clone());
+            continue;
+        }
+        if line_indent < indent {
+            return None;
+        }
+
+        let (key, _) = parse_key(&line[indent..])?;
+        // The comments right above the key are of its entry, the nested comments before are of the last entry
+        let start = pending
+            .iter()
+            .rposition(|line| !line.trim_start().starts_with('#') || indent_of(line) != indent)
+            .map_or(0, |i| i + 1);
+        let comments = pending.split_off(start);
+        if let Some(Part::Entry { lines, .. }) = parts.last_mut() {
+            let nested = pending
+                .iter()
+                .take_while(|line| !line.trim().is_empty() && indent_of(line) > indent)
+                .count();
+            lines.extend(pending.drain(..nested));
+        }
+        if !pending.is_empty() {
+            parts.push(Part::Lines(std::mem::take(&mut pending)));
+        }
+        parts.push(Part::Entry {
+            key,
+            comments,
+            lines: vec![line.clone()],
+        });
+    }
+    if !pending.is_empty() {
+        parts.push(Part::Lines(pending));
+    }
+    Some(parts)
+}
+
+/// Edit the lines of a YAML block mapping from the old object to the new one, the removed entries are removed
+/// with their comments, and the new ones added after the last entry.
+fn edit_mapping(
+    lines: &[String],
+    indent: usize,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+) -> Option<Vec<String>> {
+    let mut parts = split_mapping(lines, indent)?;
+    let keys = parts
+        .iter()
+        .filter_map(|part| match part {
+            Part::Entry { key, .. } => Some(key),
+            Part::Lines(_) => None,
+        })
+        .collect::<Vec<_>>();
+    if keys.len() != old.len() || keys.iter().any(|key| !old.contains_key(*key)) {
+        return None;
+    }
+
+    let trailing = match parts.last() {
+        Some(Part::Lines(_)) => parts.pop(),
+        _ => None,
+    };
+    let mut result = vec![];
+    for part in parts {
+        match part {
+            Part::Lines(lines) => result.extend(lines),
+            Part::Entry {
+                key,
+                comments,
+                lines,
+            } => {
+                let Some(value) = new.get(&key) else {
+                    continue;
+                };
+                result.extend(comments);
+                if &old[&key] == value {
+                    result.extend(lines);
+                } else {
+                    result.extend(edit_entry(&lines, indent, &key, &old[&key], value)?);
+                }
+            }
+        }
+    }
+    for (key, value) in new {
+        if !old.contains_key(key) {
+            result.extend(yaml_lines(
+                indent,
+                &Map::from_iter([(key.clone(), value.clone())]),
+            )?);
+        }
+    }
+    if let Some(Part::Lines(lines)) = trailing {
+        result.extend(lines);
+    }
+    Some(result)
+}
+
+/// Edit the lines of a changed YAML entry, its nested mapping or sequence is edited in place,
+/// and the other values are written again.
+fn edit_entry(
+    lines: &[String],
+    indent: usize,
+    key: &str,
+    old: &Value,
+    new: &Value,
+) -> Option<Vec<String>> {
+    let (_, rest) = parse_key(&lines[0][indent..])?;
+    let rest = rest.trim();
+    let nested = &lines[1..];
+    let nested_indent = nested
+        .iter()
+        .find(|line| !is_blank_or_comment(line))
+        .map(|line| indent_of(line));
+
+    let edited = match (old, new, nested_indent) {
+        _ if !rest.is_empty() && !rest.starts_with('#') => None,
+        (Value::Object(old), Value::Object(new), Some(nested_indent)) if nested_indent > indent => {
+            edit_mapping(nested, nested_indent, old, new)
+        }
+        (Value::Array(old), Value::Array(new), Some(nested_indent)) => {
+            edit_sequence(nested, nested_indent, old, new)
+        }
+        _ => None,
+    };
+    match edited {
+        Some(nested) => Some([lines[0].clone()].into_iter().chain(nested).collect()),
+        None => yaml_lines(indent, &Map::from_iter([(key.to_string(), new.clone())])),
+    }
+}
+
+/// Edit the lines of a YAML block sequence from the old array to the new one of the same length.
+fn edit_sequence(
+    lines: &[String],
+    indent: usize,
+    old: &[Value],
+    new: &[Value],
+) -> Option<Vec<String>> {
+    // The lines before the first item, then the items with their nested lines
+    let mut items = vec![vec![]];
+    for line in lines {
+        if !is_blank_or_comment(line) {
+            if indent_of(line) == indent && is_sequence_item(line) {
+                items.push(vec![]);
+            } else if indent_of(line) <= indent {
+                return None;
+            }
+        }
+        items.last_mut()?.push(line.clone());
+    }
+    let mut result = items.remove(0);
+    if items.len() != old.len() || old.len() != new.len() {
+        return None;
+    }
+
+    for ((mut lines, old), new) in items.into_iter().zip(old).zip(new) {
+        let tail = lines.len()
+            - lines
+                .iter()
+                .rev()
+                .take_while(|line| is_blank_or_comment(line))
+                .count();
+        let trailing = lines.split_off(tail);
+        if old == new {
+            result.extend(lines);
+        } else {
+            let edited = match (old, new) {
+                // The mapping of an item like `- key: value` is at the indent after the `- `
+                (Value::Object(old), Value::Object(new)) => {
+                    lines[0].replace_range(indent..indent + 1, " ");
+                    edit_mapping(&lines, indent + 2, old, new)
+                        .filter(|lines| {
+                            lines
+                                .first()
+                                .is_some_and(|line| indent_of(line) == indent + 2)
+                        })
+                        .map(|mut lines| {
+                            lines[0].replace_range(indent..indent + 1, "-");
+                            lines
+                        })
+                }
+                _ => None,
+            };
+            match edited {
+                Some(lines) => result.extend(lines),
+                None => result.extend(yaml_lines(indent, &[new])?),
+            }
+        }
+        result.extend(trailing);
+    }
+    Some(result)
+}
+
+/// The lines of a value written by serde_yaml, at the indent.
+fn yaml_lines<T: Serialize + ?Sized>(indent: usize, value: &T) -> Option<Vec<String>> {
+    let text = serde_yaml::to_string(value).ok()?;
+    let lines = text.lines().map(|line| match line {
+        "" => String::new(),
+        line => format!("{:indent$}{}", "", line),
+    });
+    Some(lines.collect())
+}
+
+/// Edit a TOML document with `toml_edit`, which keeps the comments and formatting of the other keys.
+fn edit_toml(text: &str, old: &Map<String, Value>, new: &Map<String, Value>) -> Option<String> {
+    let mut document = text.parse::<toml_edit::DocumentMut>().ok()?;
+    edit_table(document.as_table_mut(), old, new)?;
+    Some(document.to_string())
+}
+
+fn edit_table(
+    table: &mut dyn toml_edit::TableLike,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+) -> Option<()> {
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            table.remove(key)?;
+        }
+    }
+
+    for (key, value) in new {
+        let Some(old) = old.get(key) else {
+            table.insert(key, toml_item(value)?);
+            continue;
+        };
+        if old == value {
+            continue;
+        }
+        match (old, value, table.get_mut(key)?) {
+            (Value::Object(old), Value::Object(new), item) => {
+                edit_table(item.as_table_like_mut()?, old, new)?
+            }
+            // The comment after the value is kept
+            (_, _, toml_edit::Item::Value(item)) => {
+                let decor = item.decor().clone();
+                *item = toml_value(value)?;
+                *item.decor_mut() = decor;
+            }
+            (_, _, item) => *item = toml_item(value)?,
+        }
+    }
+    Some(())
+}
+
+fn toml_item(value: &Value) -> Option<toml_edit::Item> {
+    match value {
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| Some((key.as_str(), toml_item(value)?)))
+            .collect::<Option<toml_edit::Table>>()
+            .map(toml_edit::Item::Table),
+        value => toml_value(value).map(toml_edit::Item::Value),
+    }
+}
+
+fn toml_value(value: &Value) -> Option<toml_edit::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(value) => (*value).into(),
+        Value::Number(number) => match number.as_i64() {
+            Some(number) => number.into(),
+            None => number.as_f64()?.into(),
+        },
+        Value::String(text) => text.as_str().into(),
+        Value::Array(items) => items
+            .iter()
+            .map(toml_value)
+            .collect::<Option<toml_edit::Array>>()?
+            .into(),
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| Some((key.as_str(), toml_value(value)?)))
+            .collect::<Option<toml_edit::InlineTable>>()?
+            .into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit(format: FileFormat, text: &str, value: Value) -> (String, bool) {
+        let edit = edit_content(format, text, &value).unwrap();
+        (edit.content, edit.rewritten)
+    }
+
+    #[test]
+    fn test_edit_yaml() {
+        let text = "# Greetings\nhello: 'Hello'  # The title\n\n# Old\nold: Old\nmenu:\n  # File menu\n  file: \"File\"\n  edit: Edit\n\
+                    days:\n- Sun\n# Monday\n- Mon\nmessage: |\n  Line 1\n\n  Line 2\n# End\n";
+        let value = json!({
+            "hello": "Hello",
+            "menu": { "file": "Datei", "new": "Neu" },
+            "days": ["Sun", "Montag"],
+            "message": "Line 1\n\nLine 2\n",
+            "bye": "Bye",
+        });
+        assert_eq!(
+            edit(FileFormat::Yaml, text, value),
+            (
+                "# Greetings\nhello: 'Hello'  # The title\n\nmenu:\n  # File menu\n  file: Datei\n  new: Neu\n\
+                 days:\n- Sun\n# Monday\n- Montag\nmessage: |\n  Line 1\n\n  Line 2\nbye: Bye\n# End\n"
+                    .to_string(),
+                false
+            )
+        );
+
+        // The nested mapping of the items
+        let text = "items:\n  - label: File  # Label\n    icon: file\n";
+        let value = json!({ "items": [{ "label": "Datei", "icon": "file" }] });
+        assert_eq!(
+            edit(FileFormat::Yaml, text, value),
+            (
+                "items:\n  - label: Datei\n    icon: file\n".to_string(),
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_edit_yaml_rewritten() {
+        // The alias of a removed anchor can't be kept
+        let text = "# Greetings\nbase: &base Hello\nhello: *base\n";
+        assert_eq!(
+            edit(FileFormat::Yaml, text, json!({ "hello": "Hello" })),
+            ("hello: Hello\n".to_string(), true)
+        );
+        // Nothing is lost from a file as written by serde_yaml
+        assert_eq!(
+            edit(FileFormat::Yaml, "{}\n", json!({ "hello": "Hello" })),
+            ("hello: Hello\n".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn test_edit_toml() {
+        let text = "# Greetings\nhello = 'Hello' # The title\nold = \"Old\"\n\n# Menu\n[menu]\nfile = \"File\"\nedit = \"Edit\"\n";
+        let value = json!({
+            "hello": "Hallo",
+            "menu": { "file": "File", "new": "Neu" },
+            "bye": { "title": "Bye" },
+        });
+        assert_eq!(
+            edit(FileFormat::Toml, text, value),
+            (
+                "# Greetings\nhello = \"Hallo\" # The title\n\n# Menu\n[menu]\nfile = \"File\"\nnew = \"Neu\"\n\n[bye]\ntitle = \"Bye\"\n"
+                    .to_string(),
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_edit_json() {
+        assert_eq!(
+            edit(
+                FileFormat::Json,
+                "{\n  \"a\": \"A\"\n}\n",
+                json!({ "b": "B" })
+            ),
+            ("{\n  \"b\": \"B\"\n}\n".to_string(), false)
+        );
+        assert_eq!(
+            edit(FileFormat::Json, "{\"a\": \"A\"}", json!({ "b": "B" })),
+            ("{\n  \"b\": \"B\"\n}\n".to_string(), true)
+        );
+    }
+}
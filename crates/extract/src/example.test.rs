@@ -18,5 +18,19 @@ and support mutiple YAML files merging."##);
         t!("The table below describes some of those behaviours.");
         // Will remove spaces for avoid duplication.
         t!("The table     below describes some     of those behaviours.");
+        // TRANSLATORS: Shown on the sign in page,
+        //   keep it short.
+        t!("Sign in");
+        let x = rust_i18n::t!("Qualified");
+        let x = ::rust_i18n::t!("views.qualified", locale = "en");
+        let field = Field { title: t!("Field title") };
+        tr!("Wrapped");
+        // TRANSLATORS: Bold on the home page.
+        ui::t_html!(
+            "<b>Html</b>",
+        );
+        t_add!("en", "runtime.key", "Runtime");
+        t_add!("runtime.other", "Other");
+        other::t_html!("Not extracted");
     }
 }
@@ -1,8 +1,10 @@
-use anyhow::Error;
-use proc_macro2::{TokenStream, TokenTree};
+use anyhow::{anyhow, Error};
+use proc_macro2::{Spacing, TokenStream, TokenTree};
 use quote::ToTokens;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use syn::ext::IdentExt;
+use syn::parse::Parser;
 
 pub type Results = HashMap<String, Message>;
 
@@ -17,6 +19,8 @@ pub struct Message {
     pub key: String,
     pub index: usize,
     pub locations: Vec<Location>,
+    /// The `// TRANSLATORS:` comments right above the calls, for the translators.
+    pub notes: Vec<String>,
 }
 
 impl Message {
@@ -25,15 +29,96 @@ impl Message {
             key: key.to_owned(),
             index,
             locations: vec![],
+            notes: vec![],
         }
     }
 }
 
 static METHOD_NAME: &str = "t";
+/// The macros of rust-i18n with the key as the first argument.
+static BUILTIN_MACRO_NAMES: &[&str] = &[METHOD_NAME, "t_list", "t_map"];
+static NOTE_TAG: &str = "TRANSLATORS:";
+
+/// A macro to extract the keys from, like `t!`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroName {
+    /// The last segments of the macro path, `tr` matches `tr!` and `ui::tr!`, `ui::tr` matches only the latter.
+    pub path: Vec<String>,
+    /// The index of the key argument, a negative index counts from the end, e.g. `-2` for the key of `t_add!`.
+    pub key_index: isize,
+}
+
+impl MacroName {
+    /// Create with the macro path like `tr` or `ui::tr`, returns an error if the path is invalid.
+    pub fn new(path: &str, key_index: isize) -> Result<Self, Error> {
+        let segments = path
+            .trim_start_matches("::")
+            .split("::")
+            .map(|segment| segment.trim().to_string())
+            .collect::<Vec<_>>();
+        if segments
+            .iter()
+            .any(|segment| syn::Ident::parse_any.parse_str(segment).is_err())
+        {
+            return Err(anyhow!("invalid macro name `{}`", path));
+        }
+
+        Ok(Self {
+            path: segments,
+            key_index,
+        })
+    }
+
+    /// The macros of rust-i18n, `t!`, and `t_list!` and `t_map!` of the arrays and the nested keys.
+    pub fn builtin() -> Vec<Self> {
+        BUILTIN_MACRO_NAMES
+            .iter()
+            .map(|name| Self {
+                path: vec![name.to_string()],
+                key_index: 0,
+            })
+            .collect()
+    }
+
+    /// Whether the macro path of a call ends with the path.
+    fn matches(&self, path: &[String]) -> bool {
+        path.ends_with(&self.path)
+    }
+
+    /// The key argument of the arguments.
+    fn key_arg<'b>(&self, args: &'b [Vec<TokenTree>]) -> Option<&'b [TokenTree]> {
+        let index = if self.key_index < 0 {
+            args.len().checked_sub(self.key_index.unsigned_abs())?
+        } else {
+            self.key_index as usize
+        };
+        args.get(index).map(|arg| arg.as_slice())
+    }
+}
+
+impl Default for MacroName {
+    /// The `t!` macro, with the key as the first argument.
+    fn default() -> Self {
+        Self {
+            path: vec![METHOD_NAME.to_string()],
+            key_index: 0,
+        }
+    }
+}
 
 #[allow(clippy::ptr_arg)]
-pub fn extract(results: &mut Results, path: &PathBuf, source: &str) -> Result<(), Error> {
-    let mut ex = Extractor { results, path };
+pub fn extract(
+    results: &mut Results,
+    path: &PathBuf,
+    source: &str,
+    macros: &[MacroName],
+) -> Result<(), Error> {
+    let mut ex = Extractor {
+        results,
+        path,
+        lines: source.lines().collect(),
+        macros,
+    };
 
     let file = syn::parse_file(source)
         .unwrap_or_else(|_| panic!("Failed to parse file, file: {}", path.display()));
@@ -45,6 +130,8 @@ pub fn extract(results: &mut Results, path: &PathBuf, source: &str) -> Result<()
 struct Extractor<'a> {
     results: &'a mut Results,
     path: &'a PathBuf,
+    lines: Vec<&'a str>,
+    macros: &'a [MacroName],
 }
 
 impl<'a> Extractor<'a> {
@@ -55,17 +142,37 @@ impl<'a> Extractor<'a> {
             match token {
                 TokenTree::Group(group) => self.invoke(group.stream())?,
                 TokenTree::Ident(ident) => {
+                    // The macro path, like `t` or `rust_i18n::t`
+                    let line = ident.span().start().line;
+                    let mut path = vec![ident.to_string()];
+                    while let Some(TokenTree::Punct(punct)) = token_iter.peek() {
+                        if punct.as_char() != ':' || punct.spacing() != Spacing::Joint {
+                            break;
+                        }
+                        token_iter.next();
+                        token_iter.next();
+                        match token_iter.peek() {
+                            Some(TokenTree::Ident(ident)) => path.push(ident.to_string()),
+                            _ => break,
+                        }
+                        token_iter.next();
+                    }
+
                     let mut is_macro = false;
                     if let Some(TokenTree::Punct(punct)) = token_iter.peek() {
-                        if punct.to_string() == "!" {
+                        if punct.as_char() == '!' {
                             is_macro = true;
                             token_iter.next();
                         }
                     }
 
-                    if ident == METHOD_NAME && is_macro {
+                    let name = self.macros.iter().find(|name| name.matches(&path));
+                    if let (Some(name), true) = (name, is_macro) {
                         if let Some(TokenTree::Group(group)) = token_iter.peek() {
-                            self.take_message(group.stream());
+                            let args = split_args(group.stream());
+                            if let Some(arg) = name.key_arg(&args) {
+                                self.take_message(arg, line);
+                            }
                         }
                     }
                 }
@@ -76,11 +183,10 @@ impl<'a> Extractor<'a> {
         Ok(())
     }
 
-    fn take_message(&mut self, stream: TokenStream) {
-        let mut token_iter = stream.into_iter().peekable();
-
-        let literal = if let Some(TokenTree::Literal(literal)) = token_iter.next() {
-            literal
+    /// Take the key of a macro call at the line, the `// TRANSLATORS:` note is above the call, not the key.
+    fn take_message(&mut self, arg: &[TokenTree], call_line: usize) {
+        let literal = if let Some(TokenTree::Literal(literal)) = arg.first() {
+            literal.clone()
         } else {
             return;
         };
@@ -105,11 +211,59 @@ impl<'a> Extractor<'a> {
                         line,
                     });
                 }
+
+                if let Some(note) = note_above(&self.lines, call_line) {
+                    if !message.notes.contains(&note) {
+                        message.notes.push(note);
+                    }
+                }
             }
         }
     }
 }
 
+/// Split the arguments of a macro call by the top-level commas.
+fn split_args(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut args = vec![vec![]];
+    for token in stream {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => args.push(vec![]),
+            token => args.last_mut().unwrap().push(token),
+        }
+    }
+
+    // A trailing comma
+    if args.last().is_some_and(|arg| arg.is_empty()) {
+        args.pop();
+    }
+    args
+}
+
+/// The note of the `// TRANSLATORS:` comment in the comment lines right above the line (1-based),
+/// the comment lines after it are the rest of the note.
+fn note_above(lines: &[&str], line: usize) -> Option<String> {
+    let comments = lines[..line.saturating_sub(1).min(lines.len())]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with("//"))
+        .map(|line| line.trim_start_matches('/').trim())
+        .collect::<Vec<_>>();
+
+    let start = comments
+        .iter()
+        .rposition(|line| line.starts_with(NOTE_TAG))?;
+    let note = comments[..=start]
+        .iter()
+        .rev()
+        .map(|line| line.strip_prefix(NOTE_TAG).unwrap_or(line).trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    (!note.is_empty()).then_some(note)
+}
+
 fn literal_to_string(lit: &proc_macro2::Literal) -> Option<String> {
     match syn::parse_str::<syn::LitStr>(&lit.to_string()) {
         Ok(lit) => Some(lit.value()),
@@ -143,6 +297,7 @@ mod tests {
                         )+
                     ],
                     index: 0,
+                    notes: vec![],
                 };
                 results.push(message);
             )+
@@ -188,9 +343,8 @@ mod tests {
     #[test]
     fn test_extract() {
         let source = include_str!("example.test.rs");
-        let stream = proc_macro2::TokenStream::from_str(source).unwrap();
 
-        let expected = build_messages![
+        let mut expected = build_messages![
             ("hello", 4),
             ("views.message.title", 5),
             ("views.message.description", 7),
@@ -203,27 +357,90 @@ mod tests {
                 "The table below describes some of those behaviours.",
                 18,
                 20
-            )
+            ),
+            ("Sign in", 23),
+            ("Qualified", 24),
+            ("views.qualified", 25),
+            ("Field title", 26)
         ];
+        expected[5].notes = vec!["Shown on the sign in page, keep it short.".to_owned()];
 
+        let messages = extract_messages(source, &[MacroName::default()]);
+        assert_eq!(expected, messages);
+    }
+
+    #[test]
+    fn test_extract_with_macros() {
+        let source = include_str!("example.test.rs");
+        let macros = [
+            MacroName::default(),
+            MacroName::new("tr", 0).unwrap(),
+            MacroName::new("ui::t_html", 0).unwrap(),
+            MacroName::new("t_add", -2).unwrap(),
+        ];
+
+        let messages = extract_messages(source, &macros);
+        let keys = messages
+            .iter()
+            .skip(9)
+            .map(|m| (m.key.as_str(), m.locations[0].line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                ("Wrapped", 27),
+                ("<b>Html</b>", 30),
+                ("runtime.key", 32),
+                ("runtime.other", 33)
+            ]
+        );
+        // The note above a call of multiple lines
+        assert_eq!(messages[10].notes, vec!["Bold on the home page."]);
+    }
+
+    #[test]
+    fn test_macro_name() {
+        let name = MacroName::new("::rust_i18n::t", 0).unwrap();
+        assert_eq!(name.path, vec!["rust_i18n", "t"]);
+        assert!(name.matches(&["rust_i18n".into(), "t".into()]));
+        assert!(!name.matches(&["t".into()]));
+        assert!(MacroName::new("crate::tr", 0).is_ok());
+        assert!(MacroName::new("t!", 0).is_err());
+        assert!(MacroName::new("ui::", 0).is_err());
+
+        let args =
+            split_args(proc_macro2::TokenStream::from_str(r#""en", "key", "value","#).unwrap());
+        assert_eq!(args.len(), 3);
+        let key = |index| {
+            MacroName::new("t_add", index)
+                .unwrap()
+                .key_arg(&args)
+                .map(|arg| arg[0].to_string())
+        };
+        assert_eq!(key(-2).as_deref(), Some(r#""key""#));
+        assert_eq!(key(0).as_deref(), Some(r#""en""#));
+        assert_eq!(key(-4), None);
+        assert_eq!(key(3), None);
+    }
+
+    /// Extract the messages of the source, in order, without the indexes.
+    fn extract_messages(source: &str, macros: &[MacroName]) -> Vec<Message> {
+        let stream = proc_macro2::TokenStream::from_str(source).unwrap();
         let mut results = HashMap::new();
 
         let mut ex = Extractor {
             results: &mut results,
             path: &"hello.rs".to_owned().into(),
+            lines: source.lines().collect(),
+            macros,
         };
-
         ex.invoke(stream).unwrap();
 
-        let mut messages: Vec<_> = ex.results.values().collect();
+        let mut messages: Vec<_> = results.into_values().collect();
         messages.sort_by_key(|m| m.index);
-        assert_eq!(expected.len(), messages.len());
-
-        for (expected_message, actually_message) in expected.iter().zip(messages) {
-            let mut actually_message = actually_message.clone();
-            actually_message.index = 0;
-
-            assert_eq!(*expected_message, actually_message);
+        for message in &mut messages {
+            message.index = 0;
         }
+        messages
     }
 }
@@ -1,13 +1,20 @@
 use crate::extractor::Message;
-use rust_i18n_support::load_locales;
-use std::collections::HashMap;
+use rust_i18n_support::gettext;
+use rust_i18n_support::{try_load_locales, Layout};
+use std::collections::{BTreeMap, HashMap};
 use std::io::prelude::*;
 use std::io::Result;
 use std::path::Path;
 
+/// Write the keys missing in the locale to `TODO.<locale>.yml`, sorted, with their source locations
+/// relative to `source_root` and the notes as comments, returns an error if there are any.
+///
+/// If no key is missing, an empty `{}` TODO file is written, to clear the keys of the last run.
 pub fn generate<'a, P: AsRef<Path>>(
     output: P,
+    source_root: &Path,
     locale: &str,
+    layout: Layout,
     messages: impl IntoIterator<Item = &'a Message>,
 ) -> Result<()> {
     println!("Checking [{}] and generating untranslated texts...", locale);
@@ -18,31 +25,26 @@ pub fn generate<'a, P: AsRef<Path>>(
     let output_path = output.as_ref().display().to_string();
 
     let ignore_file = |fname: &str| fname.ends_with(&filename);
-    let data = load_locales(&output_path, ignore_file);
+    let data = try_load_locales(&output_path, layout, ignore_file)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
 
-    let mut new_values: HashMap<String, String> = HashMap::new();
+    let mut new_values: BTreeMap<&str, &Message> = BTreeMap::new();
 
     for m in messages {
-        if !m.locations.is_empty() {
-            for _l in &m.locations {
-                // TODO: write file and line as YAML comment
-            }
-        }
-
         if let Some(trs) = data.get(locale) {
-            if trs.get(&m.key).is_some() {
+            // The key, or the keys nested in it like the items of `t_list!`
+            let prefix = format!("{}.", m.key);
+            if trs.contains_key(&m.key) || trs.keys().any(|key| key.starts_with(&prefix)) {
                 continue;
             }
         }
 
-        let value = m.key.split('.').last().unwrap_or_default();
-
-        new_values
-            .entry(m.key.clone())
-            .or_insert_with(|| value.into());
+        new_values.entry(m.key.as_str()).or_insert(m);
     }
 
-    write_file(&output, &filename, &new_values)?;
+    let content = todo_content(source_root, &new_values)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    write_file(&output, &filename, &content)?;
 
     if new_values.is_empty() {
         println!("All thing done.\n");
@@ -54,24 +56,220 @@ pub fn generate<'a, P: AsRef<Path>>(
     eprintln!("----------------------------------------");
     eprintln!("Writing to {}\n", filename);
 
-    write_file(&output, &filename, &new_values)?;
-
     // Finally, return error for let CI fail
     let err = std::io::Error::new(std::io::ErrorKind::Other, "");
     Err(err)
 }
 
-fn write_file<P: AsRef<Path>>(
-    output: &P,
-    filename: &str,
-    translations: &HashMap<String, String>,
-) -> Result<()> {
+fn write_file<P: AsRef<Path>>(output: &P, filename: &str, content: &str) -> Result<()> {
     let output_file = std::path::Path::new(output.as_ref()).join(String::from(filename));
     let mut output = ::std::fs::File::create(&output_file)
         .unwrap_or_else(|_| panic!("Unable to create {} file", &output_file.display()));
 
-    writeln!(output, "{}", serde_yaml::to_string(&translations).unwrap())
-        .expect("Write YAML file error");
+    write!(output, "{}", content).expect("Write YAML file error");
 
     Ok(())
 }
+
+/// The YAML of the untranslated keys in order, the value is the last part of the key,
+/// and the `# TRANSLATORS:` notes and the `# file:line` locations are the comments above it.
+fn todo_content(
+    source_root: &Path,
+    messages: &BTreeMap<&str, &Message>,
+) -> std::result::Result<String, serde_yaml::Error> {
+    if messages.is_empty() {
+        return Ok("{}\n".to_string());
+    }
+
+    let mut content = String::new();
+    for (key, m) in messages {
+        for note in &m.notes {
+            content.push_str(&format!("# TRANSLATORS: {}\n", note));
+        }
+        for location in &m.locations {
+            let file = relative_path(source_root, &location.file);
+            content.push_str(&format!("# {}:{}\n", file, location.line));
+        }
+
+        let value = key.split('.').next_back().unwrap_or_default();
+        content.push_str(&serde_yaml::to_string(&BTreeMap::from([(key, value)]))?);
+    }
+
+    Ok(content)
+}
+
+/// Write a gettext `.pot` template of the messages, with the `#:` source references relative to `source_root`.
+///
+/// The messages of the same key, like the keys shared by the crates of a workspace, are one entry
+/// with the references of all of them.
+pub fn generate_pot<'a, P: AsRef<Path>>(
+    output_file: P,
+    source_root: &Path,
+    messages: impl IntoIterator<Item = &'a Message>,
+) -> Result<()> {
+    let output_file = output_file.as_ref();
+    println!("Writing gettext template to {}", output_file.display());
+
+    std::fs::write(output_file, pot_content(source_root, messages))
+}
+
+fn pot_content<'a>(source_root: &Path, messages: impl IntoIterator<Item = &'a Message>) -> String {
+    let mut content = String::new();
+    content.push_str("msgid \"\"\n");
+    content.push_str("msgstr \"\"\n");
+    content.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    content.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    content.push_str("\"X-Generator: rust-i18n\\n\"\n");
+
+    let mut entries: Vec<(&str, Vec<_>)> = vec![];
+    let mut indexes = HashMap::new();
+    for m in messages {
+        let index = *indexes.entry(m.key.as_str()).or_insert_with(|| {
+            entries.push((m.key.as_str(), vec![]));
+            entries.len() - 1
+        });
+        entries[index].1.extend(&m.locations);
+    }
+
+    for (key, locations) in entries {
+        content.push('\n');
+        for location in locations {
+            let file = relative_path(source_root, &location.file);
+            content.push_str(&format!("#: {}:{}\n", file, location.line));
+        }
+        content.push_str(&format!("msgid {}\n", gettext::quote(key)));
+        content.push_str("msgstr \"\"\n");
+    }
+
+    content
+}
+
+/// Whether the file is a `TODO.<locale>.yml` written by `generate`, which is not translated yet.
+pub fn is_todo_file(path: &str) -> bool {
+    Path::new(path)
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("TODO."))
+}
+
+/// The path of a source file relative to `source_root`, with `/` on all platforms.
+pub(crate) fn relative_path(source_root: &Path, file: &Path) -> String {
+    let file = file.strip_prefix(source_root).unwrap_or(file);
+    file.display().to_string().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extractor::Location;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_todo_content() {
+        let hello = Message {
+            key: "hello".into(),
+            index: 1,
+            locations: vec![
+                Location {
+                    file: PathBuf::from("./src/main.rs"),
+                    line: 10,
+                },
+                Location {
+                    file: PathBuf::from("./src/lib.rs"),
+                    line: 2,
+                },
+            ],
+            notes: vec!["The greeting".into()],
+        };
+        let title = Message {
+            key: "views.title".into(),
+            index: 0,
+            locations: vec![Location {
+                file: PathBuf::from("./src/views.rs"),
+                line: 5,
+            }],
+            notes: vec![],
+        };
+        let quoted = Message {
+            key: "Yes: no".into(),
+            index: 2,
+            locations: vec![],
+            notes: vec![],
+        };
+        let messages = BTreeMap::from([
+            (title.key.as_str(), &title),
+            (hello.key.as_str(), &hello),
+            (quoted.key.as_str(), &quoted),
+        ]);
+
+        assert_eq!(
+            todo_content(Path::new("./"), &messages).unwrap(),
+            "'Yes: no': 'Yes: no'\n\
+             # TRANSLATORS: The greeting\n\
+             # src/main.rs:10\n\
+             # src/lib.rs:2\n\
+             hello: hello\n\
+             # src/views.rs:5\n\
+             views.title: title\n"
+        );
+        assert_eq!(
+            todo_content(Path::new("./"), &BTreeMap::new()).unwrap(),
+            "{}\n"
+        );
+    }
+
+    #[test]
+    fn test_pot_content() {
+        let messages = vec![
+            Message {
+                key: "hello".into(),
+                index: 0,
+                locations: vec![
+                    Location {
+                        file: PathBuf::from("./src/main.rs"),
+                        line: 10,
+                    },
+                    Location {
+                        file: PathBuf::from("./src/lib.rs"),
+                        line: 2,
+                    },
+                ],
+                notes: vec![],
+            },
+            Message {
+                key: "Say \"hi\"".into(),
+                index: 1,
+                locations: vec![],
+                notes: vec![],
+            },
+            // The same key of another crate
+            Message {
+                key: "hello".into(),
+                index: 0,
+                locations: vec![Location {
+                    file: PathBuf::from("./app/src/main.rs"),
+                    line: 3,
+                }],
+                notes: vec![],
+            },
+        ];
+
+        assert_eq!(
+            pot_content(Path::new("./"), &messages),
+            r#"msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Content-Transfer-Encoding: 8bit\n"
+"X-Generator: rust-i18n\n"
+
+#: src/main.rs:10
+#: src/lib.rs:2
+#: app/src/main.rs:3
+msgid "hello"
+msgstr ""
+
+msgid "Say \"hi\""
+msgstr ""
+"#
+        );
+    }
+}
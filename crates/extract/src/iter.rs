@@ -3,6 +3,9 @@ use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 
+/// Call back with the path and source of all `.rs` files of the crate.
+///
+/// The directories with their own `Cargo.toml`, like the workspace members, are other crates and skipped.
 pub fn iter_crate<F>(src_path: &str, mut callback: F) -> Result<(), Error>
 where
     F: FnMut(&PathBuf, &str) -> Result<(), Error>,
@@ -14,7 +17,12 @@ where
         .skip_stdout(true)
         .parents(true)
         .git_ignore(true)
-        .follow_links(false);
+        .follow_links(false)
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_some_and(|t| t.is_dir())
+                || !entry.path().join("Cargo.toml").is_file()
+        });
 
     for result in walker.build() {
         match result {
@@ -1,3 +1,10 @@
+pub mod checker;
+mod editor;
 pub mod extractor;
 pub mod generator;
 pub mod iter;
+pub mod mobile;
+pub mod pruner;
+pub mod spreadsheet;
+pub mod updater;
+pub mod xliff;
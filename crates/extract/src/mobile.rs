@@ -0,0 +1,459 @@
+use quick_xml::escape::{escape, partial_escape};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+/// The CLDR plural categories, the sub-keys of a plural key like `inbox.one`.
+const PLURAL_CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// A text, or the plural forms of a key by the CLDR category.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry<'a> {
+    Text(&'a str),
+    Plural(BTreeMap<&'a str, &'a str>),
+}
+
+/// Group the translations of a locale by key, the sub-keys of a CLDR category are the forms of
+/// a plural key if it has the `other` form.
+fn entries(translations: &HashMap<String, String>) -> BTreeMap<&str, Entry<'_>> {
+    let mut plurals: BTreeMap<&str, BTreeMap<&str, &str>> = BTreeMap::new();
+    for (key, text) in translations {
+        if let Some((key, category)) = key.rsplit_once('.') {
+            if PLURAL_CATEGORIES.contains(&category) {
+                plurals.entry(key).or_default().insert(category, text);
+            }
+        }
+    }
+    plurals.retain(|_, forms| forms.contains_key("other"));
+
+    let mut entries = BTreeMap::new();
+    for (key, text) in translations {
+        let is_form = key
+            .rsplit_once('.')
+            .is_some_and(|(key, _)| plurals.contains_key(key));
+        if !is_form {
+            entries.insert(key.as_str(), Entry::Text(text));
+        }
+    }
+    for (key, forms) in plurals {
+        // The plural forms replace the text of the same key
+        entries.insert(key, Entry::Plural(forms));
+    }
+
+    entries
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Platform {
+    Android,
+    Apple,
+}
+
+/// Convert the `%{name}` placeholders to the positional format arguments of the platform, like
+/// `%1$s` on Android and `%1$@` on Apple, `%{count}` is `%d` and the first argument of a plural.
+///
+/// Returns whether the text has arguments, a literal `%` is escaped as `%%` only if it has.
+fn format_arguments(text: &str, platform: Platform, plural: bool) -> (String, bool) {
+    let mut names: Vec<&str> = if plural { vec!["count"] } else { vec![] };
+    let mut parts = vec![];
+
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        parts.push((&rest[..start], None));
+        let after = &rest[start + 1..];
+
+        // `%%{name}` is an escaped `%{name}`
+        if let Some(escaped) = after.strip_prefix("%{") {
+            parts.push(("%{", None));
+            rest = escaped;
+            continue;
+        }
+
+        let placeholder = after
+            .strip_prefix('{')
+            .and_then(|after| Some((after, after.find('}')?)));
+        match placeholder {
+            Some((after, end)) => {
+                let name = after[..end].split(':').next().unwrap_or_default();
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                parts.push(("", Some(name)));
+                rest = &after[end + 1..];
+            }
+            None => {
+                parts.push(("%", None));
+                rest = after;
+            }
+        }
+    }
+    parts.push((rest, None));
+
+    if parts.iter().all(|(_, name)| name.is_none()) {
+        return (parts.into_iter().map(|(text, _)| text).collect(), false);
+    }
+
+    let mut output = String::new();
+    for (text, name) in parts {
+        output.push_str(&text.replace('%', "%%"));
+        if let Some(name) = name {
+            let position = names.iter().position(|n| *n == name).unwrap_or_default() + 1;
+            let kind = match (name, platform) {
+                ("count", _) => "d",
+                (_, Platform::Android) => "s",
+                (_, Platform::Apple) => "@",
+            };
+            if plural && name == "count" {
+                output.push_str(&format!("%{}", kind));
+            } else {
+                output.push_str(&format!("%{}${}", position, kind));
+            }
+        }
+    }
+    (output, true)
+}
+
+/// Escape a key to a valid Android resource name, like `messages_hello` of `messages.hello`.
+pub fn android_resource_name(key: &str) -> String {
+    let mut name = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// The resource directory of a locale, like `values-de`, `values-pt-rBR` or `values-b+zh+Hant+TW`.
+pub fn android_values_dir(locale: &str) -> String {
+    let parts = locale.split(['-', '_']).collect::<Vec<_>>();
+    match parts.as_slice() {
+        [language] => format!("values-{}", language),
+        [language, region] if region.len() == 2 || region.chars().all(|c| c.is_ascii_digit()) => {
+            format!("values-{}-r{}", language, region.to_uppercase())
+        }
+        _ => format!("values-b+{}", parts.join("+")),
+    }
+}
+
+/// Escape a text of Android `strings.xml`.
+fn android_escape(text: &str) -> String {
+    let mut output = String::new();
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '\'' => output.push_str("\\'"),
+            '"' => output.push_str("\\\""),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '@' | '?' if i == 0 => {
+                output.push('\\');
+                output.push(c);
+            }
+            c => output.push(c),
+        }
+    }
+    partial_escape(&output).into_owned()
+}
+
+/// Generate the Android `strings.xml` of the translations of a locale.
+///
+/// The keys are escaped to resource names, and a key conflicts with a previous name is skipped with a warning.
+pub fn android_strings(translations: &HashMap<String, String>) -> String {
+    let mut content = String::new();
+    content.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    content.push_str("<resources>\n");
+
+    let mut names: HashMap<(bool, String), &str> = HashMap::new();
+    for (key, entry) in entries(translations) {
+        let name = android_resource_name(key);
+        let is_plural = matches!(entry, Entry::Plural(_));
+        if let Some(other) = names.insert((is_plural, name.clone()), key) {
+            eprintln!(
+                "Skipped `{}`, the Android resource name `{}` is used by `{}`",
+                key, name, other
+            );
+            names.insert((is_plural, name), other);
+            continue;
+        }
+
+        match entry {
+            Entry::Text(text) => {
+                let (value, has_arguments) = format_arguments(text, Platform::Android, false);
+                // A `%` of a text without arguments is not a format specifier
+                let formatted = if !has_arguments && value.contains('%') {
+                    " formatted=\"false\""
+                } else {
+                    ""
+                };
+                content.push_str(&format!(
+                    "    <string name=\"{}\"{}>{}</string>\n",
+                    name,
+                    formatted,
+                    android_escape(&value)
+                ));
+            }
+            Entry::Plural(forms) => {
+                content.push_str(&format!("    <plurals name=\"{}\">\n", name));
+                for category in PLURAL_CATEGORIES {
+                    if let Some(text) = forms.get(category) {
+                        let (value, _) = format_arguments(text, Platform::Android, true);
+                        content.push_str(&format!(
+                            "        <item quantity=\"{}\">{}</item>\n",
+                            category,
+                            android_escape(&value)
+                        ));
+                    }
+                }
+                content.push_str("    </plurals>\n");
+            }
+        }
+    }
+
+    content.push_str("</resources>\n");
+    content
+}
+
+/// Escape a string of Apple `.strings`.
+fn apple_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// Generate the Apple `Localizable.strings` of the texts of a locale, the plural keys are in the `.stringsdict`.
+pub fn apple_strings(translations: &HashMap<String, String>) -> String {
+    let mut content = String::new();
+    for (key, entry) in entries(translations) {
+        if let Entry::Text(text) = entry {
+            let (value, _) = format_arguments(text, Platform::Apple, false);
+            content.push_str(&format!(
+                "\"{}\" = \"{}\";\n",
+                apple_escape(key),
+                apple_escape(&value)
+            ));
+        }
+    }
+    content
+}
+
+/// Generate the Apple `Localizable.stringsdict` of the plural keys of a locale, `None` if there is none.
+///
+/// The `count` argument selects the plural form, like `String.localizedStringWithFormat(format, count)`.
+pub fn apple_stringsdict(translations: &HashMap<String, String>) -> Option<String> {
+    let mut content = String::new();
+    content.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    content.push_str("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n");
+    content.push_str("<plist version=\"1.0\">\n");
+    content.push_str("<dict>\n");
+
+    let mut has_plural = false;
+    for (key, entry) in entries(translations) {
+        let Entry::Plural(forms) = entry else {
+            continue;
+        };
+        has_plural = true;
+
+        content.push_str(&format!("    <key>{}</key>\n", escape(key)));
+        content.push_str("    <dict>\n");
+        content.push_str("        <key>NSStringLocalizedFormatKey</key>\n");
+        content.push_str("        <string>%#@count@</string>\n");
+        content.push_str("        <key>count</key>\n");
+        content.push_str("        <dict>\n");
+        content.push_str("            <key>NSStringFormatSpecTypeKey</key>\n");
+        content.push_str("            <string>NSStringPluralRuleType</string>\n");
+        content.push_str("            <key>NSStringFormatValueTypeKey</key>\n");
+        content.push_str("            <string>d</string>\n");
+        for category in PLURAL_CATEGORIES {
+            if let Some(text) = forms.get(category) {
+                let (value, _) = format_arguments(text, Platform::Apple, true);
+                content.push_str(&format!("            <key>{}</key>\n", category));
+                content.push_str(&format!(
+                    "            <string>{}</string>\n",
+                    escape(&value)
+                ));
+            }
+        }
+        content.push_str("        </dict>\n");
+        content.push_str("    </dict>\n");
+    }
+
+    content.push_str("</dict>\n");
+    content.push_str("</plist>\n");
+
+    has_plural.then_some(content)
+}
+
+/// Write the `values-<locale>/strings.xml` of all locales, the default locale is in `values/strings.xml`.
+pub fn export_android(
+    output: &Path,
+    translations: &HashMap<String, HashMap<String, String>>,
+    default_locale: &str,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for (locale, trs) in translations.iter().collect::<BTreeMap<_, _>>() {
+        let dir = if locale == default_locale {
+            "values".to_string()
+        } else {
+            android_values_dir(locale)
+        };
+
+        let path = output.join(dir).join("strings.xml");
+        std::fs::create_dir_all(path.parent().unwrap_or(output))?;
+        std::fs::write(&path, android_strings(trs))?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+/// Write the `<locale>.lproj/Localizable.strings` and `Localizable.stringsdict` of all locales.
+pub fn export_apple(
+    output: &Path,
+    translations: &HashMap<String, HashMap<String, String>>,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for (locale, trs) in translations.iter().collect::<BTreeMap<_, _>>() {
+        let dir = output.join(format!("{}.lproj", locale));
+        std::fs::create_dir_all(&dir)?;
+
+        let path = dir.join("Localizable.strings");
+        std::fs::write(&path, apple_strings(trs))?;
+        paths.push(path);
+
+        if let Some(content) = apple_stringsdict(trs) {
+            let path = dir.join("Localizable.stringsdict");
+            std::fs::write(&path, content)?;
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translations(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, text)| (key.to_string(), text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_format_arguments() {
+        let android = |text| format_arguments(text, Platform::Android, false).0;
+        let apple = |text| format_arguments(text, Platform::Apple, false).0;
+
+        assert_eq!(android("Hello"), "Hello");
+        assert_eq!(android("100%"), "100%");
+        assert_eq!(
+            android("Hi %{name}, %{count}% %{name}"),
+            "Hi %1$s, %2$d%% %1$s"
+        );
+        assert_eq!(apple("Hi %{name}, %{count}%"), "Hi %1$@, %2$d%%");
+        assert_eq!(
+            android("Use %%{name} for %{name:>6}"),
+            "Use %%{name} for %1$s"
+        );
+        assert_eq!(
+            format_arguments("%{name} has %{count} files", Platform::Android, true).0,
+            "%2$s has %d files"
+        );
+    }
+
+    #[test]
+    fn test_android_names() {
+        assert_eq!(android_resource_name("messages.hello"), "messages_hello");
+        assert_eq!(android_resource_name("Hello world!"), "Hello_world_");
+        assert_eq!(android_resource_name("404.title"), "_404_title");
+        assert_eq!(android_resource_name("-brand"), "_brand");
+
+        assert_eq!(android_values_dir("de"), "values-de");
+        assert_eq!(android_values_dir("pt-BR"), "values-pt-rBR");
+        assert_eq!(android_values_dir("es-419"), "values-es-r419");
+        assert_eq!(android_values_dir("zh-Hant-TW"), "values-b+zh+Hant+TW");
+        assert_eq!(android_values_dir("zh-Hant"), "values-b+zh+Hant");
+    }
+
+    #[test]
+    fn test_android_strings() {
+        let trs = translations(&[
+            ("hello", "Hello, %{name}!"),
+            ("quote", "It's \"100%\" <b>done</b>"),
+            ("at", "@home"),
+            ("inbox.one", "%{count} message"),
+            ("inbox.other", "%{count} messages"),
+            ("inbox", "Inbox"),
+            ("user.name", "Name"),
+            ("user_name", "Name"),
+            ("menu.one", "Not a plural"),
+        ]);
+
+        assert_eq!(
+            android_strings(&trs),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<resources>
+    <string name="at">\@home</string>
+    <string name="hello">Hello, %1$s!</string>
+    <plurals name="inbox">
+        <item quantity="one">%d message</item>
+        <item quantity="other">%d messages</item>
+    </plurals>
+    <string name="menu_one">Not a plural</string>
+    <string name="quote" formatted="false">It\'s \"100%\" &lt;b&gt;done&lt;/b&gt;</string>
+    <string name="user_name">Name</string>
+</resources>
+"#
+        );
+    }
+
+    #[test]
+    fn test_apple_strings() {
+        let trs = translations(&[
+            ("hello", "Hello, %{name}!"),
+            ("quote", "Say \"hi\"\nnow"),
+            ("inbox.one", "%{count} message in %{folder}"),
+            ("inbox.other", "%{count} messages in %{folder}"),
+        ]);
+
+        assert_eq!(
+            apple_strings(&trs),
+            "\"hello\" = \"Hello, %1$@!\";\n\"quote\" = \"Say \\\"hi\\\"\\nnow\";\n"
+        );
+        assert_eq!(
+            apple_stringsdict(&trs).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>inbox</key>
+    <dict>
+        <key>NSStringLocalizedFormatKey</key>
+        <string>%#@count@</string>
+        <key>count</key>
+        <dict>
+            <key>NSStringFormatSpecTypeKey</key>
+            <string>NSStringPluralRuleType</string>
+            <key>NSStringFormatValueTypeKey</key>
+            <string>d</string>
+            <key>one</key>
+            <string>%d message in %2$@</string>
+            <key>other</key>
+            <string>%d messages in %2$@</string>
+        </dict>
+    </dict>
+</dict>
+</plist>
+"#
+        );
+        assert_eq!(
+            apple_stringsdict(&translations(&[("hello", "Hello")])),
+            None
+        );
+    }
+}
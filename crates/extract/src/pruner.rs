@@ -0,0 +1,485 @@
+use crate::editor::edit_content;
+use crate::generator::is_todo_file;
+use crate::updater::{editable_format, parse_content};
+use anyhow::Error;
+use regex::Regex;
+use rust_i18n_support::{load_files, Layout, MESSAGE_FORMAT_KEY};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Lines of context around the changes of a diff.
+const DIFF_CONTEXT: usize = 2;
+/// Limit of the line pairs compared by a diff, the larger changes are shown as a whole.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// The keys not to prune, like the allowlist `legacy.title` or the dynamic keys `errors.*`.
+///
+/// A `*` matches any text in a part of the key, and `**` any parts. A key is also matched by
+/// the pattern of its parent key, so `errors` keeps `errors.not_found`.
+#[derive(Debug, Clone, Default)]
+pub struct KeyPatterns(Vec<Regex>);
+
+impl KeyPatterns {
+    pub fn new<S: AsRef<str>>(patterns: impl IntoIterator<Item = S>) -> Self {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                let parts = pattern
+                    .as_ref()
+                    .split('.')
+                    .map(|part| match part {
+                        "**" => ".*".to_string(),
+                        part => part
+                            .split('*')
+                            .map(regex::escape)
+                            .collect::<Vec<_>>()
+                            .join("[^.]*"),
+                    })
+                    .collect::<Vec<_>>();
+                Regex::new(&format!(r"^{}(\..*)?$", parts.join(r"\."))).unwrap()
+            })
+            .collect();
+        Self(patterns)
+    }
+
+    /// Whether the key or its parent key is matched.
+    pub fn matches(&self, key: &str) -> bool {
+        self.0.iter().any(|pattern| pattern.is_match(key))
+    }
+}
+
+/// The keys removed from a locale file, with the content before and after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pruned {
+    pub path: PathBuf,
+    pub keys: Vec<String>,
+    pub old: String,
+    pub new: String,
+    /// Whether the file is written as a whole, which loses its comments and formatting.
+    pub rewritten: bool,
+}
+
+/// Remove the keys not used in the source code from the YAML, JSON and TOML locale files, without writing them.
+///
+/// The files keep their format and nesting, the objects left empty are removed, and an array is removed only if
+/// none of its items is used. Only the lines of the removed keys are removed from the YAML and TOML files, unless
+/// a file can't be edited so, see [`Pruned::rewritten`]. The gettext, Fluent and `TODO.<locale>.yml` files are not changed.
+pub fn prune_locales(
+    locales_path: &Path,
+    layout: Layout,
+    used: &BTreeSet<&str>,
+    keep: &KeyPatterns,
+) -> Result<Vec<Pruned>, Error> {
+    let files = load_files(&locales_path.display().to_string(), layout, is_todo_file)?;
+    let is_unused = |key: &str, nested: bool| !keep.matches(key) && !is_used(used, key, nested);
+
+    let mut result = vec![];
+    for (path, _) in files {
+        let Some(format) = editable_format(&path) else {
+            continue;
+        };
+        let old = std::fs::read_to_string(&path)?;
+        let mut value = parse_content(format, &old)?;
+
+        let mut keys = vec![];
+        if layout.is_multi_locale() && layout.split_locales(&value).is_some() {
+            if let Value::Object(object) = &mut value {
+                for (key, value) in object.iter_mut() {
+                    if key.starts_with('_') {
+                        continue;
+                    }
+                    match layout {
+                        Layout::Rails => prune_value(value, "", &is_unused, &mut keys),
+                        _ => prune_leaf(value, key, &is_unused, &mut keys),
+                    }
+                }
+                object.retain(|key, value| key.starts_with('_') || !is_empty_object(value));
+            }
+        } else {
+            let namespace = layout
+                .locale_and_namespace(locales_path, &path)
+                .and_then(|(_, namespace)| namespace)
+                .unwrap_or_default();
+            if let Value::Object(object) = &mut value {
+                object.retain(|key, value| {
+                    key == MESSAGE_FORMAT_KEY
+                        || !prune_entry(key, value, &namespace, &is_unused, &mut keys)
+                });
+            }
+        }
+
+        if keys.is_empty() {
+            continue;
+        }
+        keys.sort();
+        keys.dedup();
+        let edit = edit_content(format, &old, &value)?;
+        result.push(Pruned {
+            path,
+            keys,
+            old,
+            new: edit.content,
+            rewritten: edit.rewritten,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Whether the key, its parent key, or with `nested` any key nested in it is used.
+fn is_used(used: &BTreeSet<&str>, key: &str, nested: bool) -> bool {
+    if used.contains(key)
+        || key
+            .match_indices('.')
+            .any(|(i, _)| used.contains(&key[..i]))
+    {
+        return true;
+    }
+
+    let prefix = format!("{}.", key);
+    nested
+        && used
+            .range(prefix.as_str()..)
+            .next()
+            .is_some_and(|used| used.starts_with(&prefix))
+}
+
+/// Remove the unused keys nested in the value.
+fn prune_value<F>(value: &mut Value, prefix: &str, is_unused: &F, keys: &mut Vec<String>)
+where
+    F: Fn(&str, bool) -> bool,
+{
+    if let Value::Object(object) = value {
+        object.retain(|key, value| !prune_entry(key, value, prefix, is_unused, keys));
+    }
+}
+
+/// Prune the entry of an object, returns whether the entry should be removed.
+fn prune_entry<F>(
+    key: &str,
+    value: &mut Value,
+    prefix: &str,
+    is_unused: &F,
+    keys: &mut Vec<String>,
+) -> bool
+where
+    F: Fn(&str, bool) -> bool,
+{
+    let key = join_key(prefix, key);
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            if !is_unused(&key, false) {
+                return false;
+            }
+            prune_value(value, &key, is_unused, keys);
+            is_empty_object(value)
+        }
+        Value::Object(_) => false,
+        Value::Array(_) => {
+            let unused = is_unused(&key, true);
+            if unused {
+                keys.push(key);
+            }
+            unused
+        }
+        _ => {
+            let unused = is_unused(&key, false);
+            if unused {
+                keys.push(key);
+            }
+            unused
+        }
+    }
+}
+
+/// Remove the maps of locales of the unused keys of a `leaf` file.
+fn prune_leaf<F>(value: &mut Value, key: &str, is_unused: &F, keys: &mut Vec<String>)
+where
+    F: Fn(&str, bool) -> bool,
+{
+    let Value::Object(object) = value else {
+        return;
+    };
+    if object.is_empty() {
+        return;
+    }
+
+    // A map of locales like `{ en: Hello, de: Hallo }` is the translations of the key
+    if object.values().all(|value| !value.is_object()) {
+        if is_unused(key, true) {
+            keys.push(key.to_string());
+            object.clear();
+        }
+        return;
+    }
+
+    if !is_unused(key, false) {
+        return;
+    }
+    for (child, value) in object.iter_mut() {
+        prune_leaf(value, &join_key(key, child), is_unused, keys);
+    }
+    object.retain(|_, value| !is_empty_object(value));
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn is_empty_object(value: &Value) -> bool {
+    value.as_object().is_some_and(|object| object.is_empty())
+}
+
+/// A unified diff of the lines, with the file name as `label`.
+pub fn diff(label: &str, old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let lines = diff_lines(&old, &new);
+
+    let mut content = format!("--- {}\n+++ {}\n", label, label);
+    let changes = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != ' ')
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let mut i = 0;
+    while i < changes.len() {
+        // The changes closer than the context lines are in the same hunk
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1] - changes[j] <= DIFF_CONTEXT * 2 + 1 {
+            j += 1;
+        }
+        let start = changes[i].saturating_sub(DIFF_CONTEXT);
+        let end = (changes[j] + DIFF_CONTEXT + 1).min(lines.len());
+
+        let count = |skip: char| lines[..start].iter().filter(|(op, _)| *op != skip).count();
+        let len = |skip: char| {
+            lines[start..end]
+                .iter()
+                .filter(|(op, _)| *op != skip)
+                .count()
+        };
+        let (old_len, new_len) = (len('+'), len('-'));
+        content.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            count('+') + usize::from(old_len > 0),
+            old_len,
+            count('-') + usize::from(new_len > 0),
+            new_len
+        ));
+        for (op, line) in &lines[start..end] {
+            content.push_str(&format!("{}{}\n", op, line));
+        }
+        i = j + 1;
+    }
+
+    content
+}
+
+/// The lines of the longest common subsequence as ` `, and the removed and added lines as `-` and `+`.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| (' ', *line))
+        .collect::<Vec<_>>();
+    if a.len() * b.len() > MAX_DIFF_CELLS {
+        lines.extend(a.iter().map(|line| ('-', *line)));
+        lines.extend(b.iter().map(|line| ('+', *line)));
+    } else {
+        // The lengths of the common subsequences of the suffixes of a and b
+        let mut table = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                table[i][j] = if a[i] == b[j] {
+                    table[i + 1][j + 1] + 1
+                } else {
+                    table[i + 1][j].max(table[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                lines.push((' ', a[i]));
+                i += 1;
+                j += 1;
+            } else if j == b.len() || (i < a.len() && table[i + 1][j] >= table[i][j + 1]) {
+                lines.push(('-', a[i]));
+                i += 1;
+            } else {
+                lines.push(('+', b[j]));
+                j += 1;
+            }
+        }
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|line| (' ', *line)));
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_patterns() {
+        let keep = KeyPatterns::new(["errors.*", "legacy.title", "countries.**.name"]);
+        assert!(keep.matches("errors.not_found"));
+        assert!(keep.matches("errors.not_found.title"));
+        assert!(!keep.matches("errors"));
+        assert!(keep.matches("legacy.title"));
+        assert!(!keep.matches("legacy.titles"));
+        assert!(keep.matches("countries.eu.de.name"));
+        assert!(!keep.matches("countries.de"));
+        assert!(!KeyPatterns::default().matches("hello"));
+    }
+
+    #[test]
+    fn test_prune_locales() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-pruner-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.yml"),
+            "_message_format: icu\n# Greetings\nhello: Hello\nold: Old\nmenu:\n  file: File\n  edit: Edit\n\
+             inbox:\n  one: One\n  other: Many\ndays: [Sun, Mon]\nmonths: [Jan]\n\
+             errors:\n  not_found: Not found\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("de.json"),
+            r#"{"hello": "Hallo", "menu": {"edit": "Bearbeiten"}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("TODO.de.yml"), "old: old\n").unwrap();
+
+        let used = BTreeSet::from(["hello", "menu.file", "inbox", "days.1"]);
+        let keep = KeyPatterns::new(["errors.*"]);
+        let pruned = prune_locales(&dir, Layout::default(), &used, &keep).unwrap();
+
+        assert_eq!(pruned.len(), 2);
+        assert_eq!(pruned[0].path, dir.join("de.json"));
+        assert_eq!(pruned[0].keys, vec!["menu.edit"]);
+        assert_eq!(pruned[0].new, "{\n  \"hello\": \"Hallo\"\n}\n");
+        assert!(pruned[0].rewritten);
+        assert_eq!(pruned[1].keys, vec!["menu.edit", "months", "old"]);
+        // Only the lines of the removed keys are removed
+        assert_eq!(
+            pruned[1].new,
+            "_message_format: icu\n# Greetings\nhello: Hello\nmenu:\n  file: File\ninbox:\n  one: One\n  other: Many\n\
+             days: [Sun, Mon]\nerrors:\n  not_found: Not found\n"
+        );
+        assert!(!pruned[1].rewritten);
+        // Nothing is written
+        assert!(std::fs::read_to_string(dir.join("en.yml"))
+            .unwrap()
+            .contains("old: Old"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_locales_with_all_locales_in_a_file() {
+        let dir =
+            std::env::temp_dir().join(format!("rust-i18n-pruner-multi-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("rails")).unwrap();
+        std::fs::create_dir_all(dir.join("leaf")).unwrap();
+        std::fs::write(
+            dir.join("rails/app.yml"),
+            "en:\n  hello: Hello\n  old: Old\nde:\n  old: Alt\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("leaf/app.yml"),
+            "hello:\n  en: Hello\n  de: Hallo\nold:\n  title:\n    en: Old\n",
+        )
+        .unwrap();
+
+        let used = BTreeSet::from(["hello"]);
+        let prune = |layout, path: &str| {
+            let pruned =
+                prune_locales(&dir.join(path), layout, &used, &KeyPatterns::default()).unwrap();
+            (pruned[0].keys.clone(), pruned[0].new.clone())
+        };
+
+        assert_eq!(
+            prune(Layout::Rails, "rails"),
+            (vec!["old".to_string()], "en:\n  hello: Hello\n".to_string())
+        );
+        assert_eq!(
+            prune(Layout::Leaf, "leaf"),
+            (
+                vec!["old.title".to_string()],
+                "hello:\n  en: Hello\n  de: Hallo\n".to_string()
+            )
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_locales_of_t_list_and_t_map() {
+        let dir =
+            std::env::temp_dir().join(format!("rust-i18n-pruner-macros-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.yml"),
+            "days: [Sun, Mon]\nmenu:\n  file: File\n  edit: Edit\nold: Old\n",
+        )
+        .unwrap();
+
+        let source = r#"fn main() { t_list!("days"); rust_i18n::t_map!("menu", locale = "en"); }"#;
+        let mut results = Default::default();
+        let path = PathBuf::from("main.rs");
+        crate::extractor::extract(
+            &mut results,
+            &path,
+            source,
+            &crate::extractor::MacroName::builtin(),
+        )
+        .unwrap();
+        let used = results.keys().map(|key| key.as_str()).collect();
+
+        let pruned =
+            prune_locales(&dir, Layout::default(), &used, &KeyPatterns::default()).unwrap();
+        assert_eq!(pruned[0].keys, vec!["old"]);
+        assert_eq!(
+            pruned[0].new,
+            "days: [Sun, Mon]\nmenu:\n  file: File\n  edit: Edit\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nc\nd\ne\nf\ng\nh\nj\nk\n";
+        assert_eq!(
+            diff("en.yml", old, new),
+            "--- en.yml\n+++ en.yml\n\
+             @@ -1,4 +1,3 @@\n a\n-b\n c\n d\n\
+             @@ -7,4 +6,4 @@\n g\n h\n-i\n j\n+k\n"
+        );
+        assert_eq!(diff("en.yml", old, old), "--- en.yml\n+++ en.yml\n");
+    }
+}
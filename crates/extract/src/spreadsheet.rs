@@ -0,0 +1,294 @@
+use crate::extractor::Message;
+use crate::generator::relative_path;
+use anyhow::{anyhow, Error};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+const KEY_COLUMN: &str = "key";
+const LOCATIONS_COLUMN: &str = "locations";
+const NOTES_COLUMN: &str = "notes";
+
+/// The field delimiter of a sheet file, `,` for `.csv`, tab for `.tsv` and `.tab`.
+pub fn delimiter(path: &Path) -> Option<u8> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "csv" => Some(b','),
+        "tsv" | "tab" => Some(b'\t'),
+        _ => None,
+    }
+}
+
+/// Generate a sheet with a row per key and a column per locale, the keys are the keys of the locales
+/// and the keys used in the source code, with the source locations and the notes of the translators.
+///
+/// The locations are `file:line` with the file relative to the crate root, one per line of the cell.
+pub fn generate<'a>(
+    delimiter: u8,
+    locales: &[String],
+    translations: &HashMap<String, HashMap<String, String>>,
+    source_root: &Path,
+    messages: impl IntoIterator<Item = &'a Message>,
+) -> Result<String, Error> {
+    let messages = messages
+        .into_iter()
+        .map(|m| (m.key.as_str(), m))
+        .collect::<HashMap<_, _>>();
+    let keys = locales
+        .iter()
+        .filter_map(|locale| translations.get(locale))
+        .flat_map(|trs| trs.keys().map(|key| key.as_str()))
+        .chain(messages.keys().copied())
+        .collect::<BTreeSet<_>>();
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(vec![]);
+
+    let mut header = vec![KEY_COLUMN];
+    header.extend(locales.iter().map(|locale| locale.as_str()));
+    header.extend([LOCATIONS_COLUMN, NOTES_COLUMN]);
+    writer.write_record(&header)?;
+
+    for key in keys {
+        let mut record = vec![key.to_string()];
+        record.extend(locales.iter().map(|locale| {
+            translations
+                .get(locale)
+                .and_then(|trs| trs.get(key))
+                .cloned()
+                .unwrap_or_default()
+        }));
+
+        let message = messages.get(key);
+        let locations = message.map(|m| {
+            m.locations
+                .iter()
+                .map(|l| format!("{}:{}", relative_path(source_root, &l.file), l.line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+        record.push(locations.unwrap_or_default());
+        record.push(message.map(|m| m.notes.join("\n")).unwrap_or_default());
+
+        writer.write_record(&record)?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// The translations of a sheet.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Sheet {
+    /// The non-empty cells of the locale columns, as locale => key => text.
+    pub translations: BTreeMap<String, BTreeMap<String, String>>,
+    /// The keys with different texts of a locale in more than one row, which are not imported.
+    pub conflicts: Vec<String>,
+}
+
+/// Parse a sheet, the header has the `key` column, the other columns except `locations` and `notes` are locales.
+///
+/// The empty cells are skipped, so they leave the locale files untouched.
+pub fn parse(content: &str, delimiter: u8) -> Result<Sheet, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let header = reader.headers()?.clone();
+    let columns = header.iter().map(|name| name.trim()).collect::<Vec<_>>();
+    let key_index = columns
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(KEY_COLUMN))
+        .ok_or_else(|| anyhow!("invalid sheet, the header has no `{}` column", KEY_COLUMN))?;
+    let locale_columns = columns
+        .iter()
+        .enumerate()
+        .filter(|(i, name)| {
+            *i != key_index
+                && !name.is_empty()
+                && !name.eq_ignore_ascii_case(LOCATIONS_COLUMN)
+                && !name.eq_ignore_ascii_case(NOTES_COLUMN)
+        })
+        .map(|(i, name)| (i, *name))
+        .collect::<Vec<_>>();
+
+    let mut sheet = Sheet::default();
+    // The row of the first text of a locale and key, to report the conflicts
+    let mut rows: HashMap<(&str, String), u64> = HashMap::new();
+    let mut conflicts = BTreeSet::new();
+
+    for record in reader.records() {
+        let record = record?;
+        let row = record.position().map(|p| p.line()).unwrap_or_default();
+        let key = match record.get(key_index).map(|key| key.trim()) {
+            Some(key) if !key.is_empty() => key,
+            _ => continue,
+        };
+
+        for (i, locale) in &locale_columns {
+            let text = match record.get(*i) {
+                Some(text) if !text.trim().is_empty() => text,
+                _ => continue,
+            };
+
+            let trs = sheet.translations.entry(locale.to_string()).or_default();
+            match trs.get(key) {
+                Some(first) if first != text => {
+                    let first_row = rows[&(*locale, key.to_string())];
+                    sheet.conflicts.push(format!(
+                        "`{}` has different {} texts in rows {} and {}",
+                        key, locale, first_row, row
+                    ));
+                    conflicts.insert((locale.to_string(), key.to_string()));
+                }
+                Some(_) => {}
+                None => {
+                    trs.insert(key.to_string(), text.to_string());
+                    rows.insert((*locale, key.to_string()), row);
+                }
+            }
+        }
+    }
+
+    for (locale, key) in conflicts {
+        if let Some(trs) = sheet.translations.get_mut(&locale) {
+            trs.remove(&key);
+        }
+    }
+    sheet.translations.retain(|_, trs| !trs.is_empty());
+
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extractor::Location;
+    use std::path::PathBuf;
+
+    fn sample_translations() -> HashMap<String, HashMap<String, String>> {
+        HashMap::from([
+            (
+                "en".to_string(),
+                HashMap::from([
+                    ("hello".to_string(), "Hello, \"world\"".to_string()),
+                    ("bye".to_string(), "Bye".to_string()),
+                ]),
+            ),
+            (
+                "de".to_string(),
+                HashMap::from([("hello".to_string(), "Hallo, Welt".to_string())]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_delimiter() {
+        assert_eq!(delimiter(Path::new("sheet.csv")), Some(b','));
+        assert_eq!(delimiter(Path::new("sheet.TSV")), Some(b'\t'));
+        assert_eq!(delimiter(Path::new("sheet.xlf")), None);
+        assert_eq!(delimiter(Path::new("sheet")), None);
+    }
+
+    #[test]
+    fn test_generate() {
+        let messages = [Message {
+            key: "hello".into(),
+            index: 0,
+            locations: vec![
+                Location {
+                    file: PathBuf::from("./src/main.rs"),
+                    line: 10,
+                },
+                Location {
+                    file: PathBuf::from("./src/lib.rs"),
+                    line: 2,
+                },
+            ],
+            notes: vec!["The greeting".into()],
+        }];
+        let locales = ["en".to_string(), "de".to_string()];
+
+        let content = generate(
+            b',',
+            &locales,
+            &sample_translations(),
+            Path::new("./"),
+            &messages,
+        )
+        .unwrap();
+        assert_eq!(
+            content,
+            "key,en,de,locations,notes\n\
+             bye,Bye,,,\n\
+             hello,\"Hello, \"\"world\"\"\",\"Hallo, Welt\",\"src/main.rs:10\nsrc/lib.rs:2\",The greeting\n"
+        );
+
+        let content = generate(
+            b'\t',
+            &locales[1..],
+            &sample_translations(),
+            Path::new("./"),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            content,
+            "key\tde\tlocations\tnotes\nhello\tHallo, Welt\t\t\n"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = "Key,en,de,fr,Notes\n\
+                       hello,Hello,Hallo,,The greeting\n\
+                       bye,Bye,Tschüss,Au revoir\n\
+                       ,Empty,Leer,\n\
+                       hello,Hello,Hallo!,Bonjour\n\
+                       bye,Bye,Tschüss,\n";
+        let sheet = parse(content, b',').unwrap();
+
+        let translations = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(key, text)| (key.to_string(), text.to_string()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert_eq!(
+            sheet.translations,
+            BTreeMap::from([
+                (
+                    "en".to_string(),
+                    translations(&[("hello", "Hello"), ("bye", "Bye")])
+                ),
+                ("de".to_string(), translations(&[("bye", "Tschüss")])),
+                (
+                    "fr".to_string(),
+                    translations(&[("bye", "Au revoir"), ("hello", "Bonjour")])
+                ),
+            ])
+        );
+        assert_eq!(
+            sheet.conflicts,
+            vec!["`hello` has different de texts in rows 2 and 5"]
+        );
+
+        let sheet = generate(
+            b'\t',
+            &["en".to_string(), "de".to_string()],
+            &sample_translations(),
+            Path::new("./"),
+            &[],
+        )
+        .and_then(|content| parse(&content, b'\t'))
+        .unwrap();
+        assert_eq!(sheet.translations["en"]["hello"], "Hello, \"world\"");
+        assert_eq!(sheet.translations["de"].len(), 1);
+        assert!(sheet.conflicts.is_empty());
+
+        assert_eq!(
+            parse("id,en\nhello,Hello\n", b',').unwrap_err().to_string(),
+            "invalid sheet, the header has no `key` column"
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use crate::editor::edit_content;
+use crate::generator::is_todo_file;
+use anyhow::Error;
+use rust_i18n_support::{load_files, FileFormat, Layout};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// A YAML, JSON or TOML locale file to update.
+struct LocaleFile {
+    path: PathBuf,
+    format: FileFormat,
+    /// The namespace of the keys by the layout, the value is nested in it.
+    namespace: Option<String>,
+    value: Value,
+}
+
+impl LocaleFile {
+    /// Whether a key can be written to the file, the key is in the namespace of the file if any.
+    fn accepts(&self, key: &str) -> bool {
+        self.namespace.as_deref().is_none_or(|namespace| {
+            key.strip_prefix(namespace)
+                .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    /// The value to write, without the namespace.
+    fn content(&self) -> Option<&Value> {
+        let Some(namespace) = &self.namespace else {
+            return Some(&self.value);
+        };
+        namespace
+            .split('.')
+            .try_fold(&self.value, |value, part| value.get(part))
+    }
+}
+
+/// The result of [`update_locales`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Update {
+    /// The changed files.
+    pub paths: Vec<PathBuf>,
+    /// The keys not written, as they are the nested objects of other keys.
+    pub conflicts: Vec<String>,
+    /// The changed files written as a whole, which loses their comments and formatting.
+    pub rewritten: Vec<PathBuf>,
+}
+
+/// Write the translations of a locale into the locale files, in their own format.
+///
+/// A translation replaces the key in the file that has it, a new key is added to the first file of
+/// the locale, or to a new `<locale>.yml`. The gettext, Fluent and `TODO.<locale>.yml` files are not changed.
+/// With the namespaces of the layout, a key is only written to the files of its namespace, or without a namespace.
+/// Only the lines of the changed keys are written to the YAML and TOML files, unless a file can't be edited so,
+/// see [`Update::rewritten`].
+pub fn update_locales<I>(
+    locales_path: &Path,
+    layout: Layout,
+    locale: &str,
+    translations: I,
+) -> Result<Update, Error>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut files = load_files(&locales_path.display().to_string(), layout, is_todo_file)?
+        .into_iter()
+        .filter_map(|(path, mut trs)| {
+            let format = editable_format(&path)?;
+            let value = trs.remove(locale)?;
+            let namespace = layout
+                .locale_and_namespace(locales_path, &path)
+                .and_then(|(_, namespace)| namespace);
+            Some(LocaleFile {
+                path,
+                format,
+                namespace,
+                value,
+            })
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut update = Update::default();
+    let mut changed = BTreeSet::new();
+    for (key, text) in translations {
+        if files
+            .iter()
+            .any(|file| get_key(&file.value, &key).is_some_and(is_parent))
+        {
+            update.conflicts.push(key);
+            continue;
+        }
+
+        // The file has the key, or the nested object of its longest prefix
+        let index = files
+            .iter()
+            .position(|file| file.accepts(&key) && has_key(&file.value, &key))
+            .or_else(|| {
+                key.rmatch_indices('.').find_map(|(i, _)| {
+                    files.iter().position(|file| {
+                        file.accepts(&key)
+                            && get_key(&file.value, &key[..i]).is_some_and(Value::is_object)
+                    })
+                })
+            })
+            .or_else(|| files.iter().position(|file| file.accepts(&key)));
+        let index = match index {
+            Some(index) => index,
+            None => {
+                files.push(LocaleFile {
+                    path: locales_path.join(format!("{}.yml", locale)),
+                    format: FileFormat::Yaml,
+                    namespace: None,
+                    value: Value::Object(Map::new()),
+                });
+                files.len() - 1
+            }
+        };
+
+        let file = &mut files[index];
+        if !file.value.is_object() {
+            file.value = Value::Object(Map::new());
+        }
+        if let Value::Object(object) = &mut file.value {
+            if set_key(object, &key, text) {
+                changed.insert(index);
+            }
+        }
+    }
+
+    for index in changed {
+        let file = &files[index];
+        let Some(value) = file.content() else {
+            continue;
+        };
+        let value = if layout.is_multi_locale() {
+            merged_file(layout, file, locale, value)?
+        } else {
+            value.clone()
+        };
+        let content = if file.path.exists() {
+            let edit = edit_content(file.format, &std::fs::read_to_string(&file.path)?, &value)?;
+            if edit.rewritten {
+                update.rewritten.push(file.path.clone());
+            }
+            edit.content
+        } else {
+            format_content(file.format, &value)?
+        };
+        std::fs::write(&file.path, content)?;
+        update.paths.push(file.path.clone());
+    }
+
+    Ok(update)
+}
+
+/// The file of the `rails` or `leaf` layout with the translations of the locale, and the other locales
+/// as they are, or only the translations if the file is not in the structure of the layout.
+fn merged_file(
+    layout: Layout,
+    file: &LocaleFile,
+    locale: &str,
+    value: &Value,
+) -> Result<Value, Error> {
+    let mut content = Value::Object(Map::new());
+    if file.path.exists() {
+        content = parse_content(file.format, &std::fs::read_to_string(&file.path)?)?;
+    }
+
+    if layout.split_locales(&content).is_none() {
+        return Ok(value.clone());
+    }
+    layout.merge_locale(&mut content, locale, value);
+    Ok(content)
+}
+
+/// The format of a YAML, JSON or TOML locale file, which can be written, `None` for the other files.
+pub(crate) fn editable_format(path: &Path) -> Option<FileFormat> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(FileFormat::from_extension)
+        .filter(|format| {
+            matches!(
+                format,
+                FileFormat::Yaml | FileFormat::Json | FileFormat::Toml
+            )
+        })
+}
+
+/// Parse the content of a YAML, JSON or TOML file.
+pub(crate) fn parse_content(format: FileFormat, text: &str) -> Result<Value, Error> {
+    Ok(match format {
+        FileFormat::Json => serde_json::from_str(text)?,
+        FileFormat::Toml => toml::from_str(text)?,
+        _ => serde_yaml::from_str(text)?,
+    })
+}
+
+/// The content of a YAML, JSON or TOML file.
+pub(crate) fn format_content(format: FileFormat, value: &Value) -> Result<String, Error> {
+    Ok(match format {
+        FileFormat::Json => format!("{}\n", serde_json::to_string_pretty(value)?),
+        FileFormat::Toml => toml::to_string(value)?,
+        _ => serde_yaml::to_string(value)?,
+    })
+}
+
+/// Get the value of a flatten key like `a.b`, which is also the nested `a: { b: ... }`,
+/// or `a.0` for the first item of the array `a: [...]`.
+pub(crate) fn get_key<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    if let Some(value) = child(value, key) {
+        return Some(value);
+    }
+
+    key.match_indices('.')
+        .find_map(|(i, _)| get_key(child(value, &key[..i])?, &key[i + 1..]))
+}
+
+/// Get the child of an object by key, or the item of an array by index.
+fn child<'a>(value: &'a Value, part: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(object) => object.get(part),
+        Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+/// Whether the value has the keys nested in it, as an object or an array.
+fn is_parent(value: &Value) -> bool {
+    value.is_object() || value.is_array()
+}
+
+/// Whether the key is a text of the value.
+fn has_key(value: &Value, key: &str) -> bool {
+    get_key(value, key).is_some_and(|value| !is_parent(value))
+}
+
+/// Set the text of a key, in the file it's in if any, returns whether it changed.
+fn set_key(object: &mut Map<String, Value>, key: &str, text: String) -> bool {
+    if object.get(key).is_some_and(|value| !is_parent(value)) {
+        let text = Value::String(text);
+        return object.insert(key.to_string(), text.clone()) != Some(text);
+    }
+
+    for (i, _) in key.match_indices('.') {
+        let (head, rest) = (&key[..i], &key[i + 1..]);
+        if !object.get(head).is_some_and(|child| has_key(child, rest)) {
+            continue;
+        }
+        match object.get_mut(head) {
+            Some(Value::Object(child)) => return set_key(child, rest, text),
+            Some(Value::Array(items)) => return set_item(items, rest, text),
+            _ => {}
+        }
+    }
+
+    insert_key(object, key, text);
+    true
+}
+
+/// Set the text of an existing key in the items of an array, the first part of the key is the index.
+fn set_item(items: &mut [Value], key: &str, text: String) -> bool {
+    let (index, rest) = match key.split_once('.') {
+        Some((index, rest)) => (index, Some(rest)),
+        None => (key, None),
+    };
+    let Some(item) = index.parse::<usize>().ok().and_then(|i| items.get_mut(i)) else {
+        return false;
+    };
+
+    match (item, rest) {
+        (Value::Object(child), Some(rest)) => set_key(child, rest, text),
+        (Value::Array(items), Some(rest)) => set_item(items, rest, text),
+        (item, None) => {
+            let text = Value::String(text);
+            let changed = *item != text;
+            *item = text;
+            changed
+        }
+        _ => false,
+    }
+}
+
+/// Insert a new key, nested in the objects of its parts, or as a flatten key after a part is a text.
+fn insert_key(object: &mut Map<String, Value>, key: &str, text: String) {
+    if let Some((head, rest)) = key.split_once('.') {
+        let child = object
+            .entry(head.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(child) = child {
+            return insert_key(child, rest, text);
+        }
+    }
+
+    object.insert(key.to_string(), Value::String(text));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_set_key() {
+        let mut value = json!({
+            "hello": "Hello",
+            "messages": { "hi": "Hi", "user.name": "Name" },
+            "a.b": "Flatten",
+        });
+        let Value::Object(object) = &mut value else {
+            unreachable!()
+        };
+
+        assert!(!set_key(object, "hello", "Hello".into()));
+        assert!(set_key(object, "hello", "Hallo".into()));
+        assert!(set_key(object, "messages.hi", "Hallo".into()));
+        assert!(set_key(object, "messages.user.name", "Nom".into()));
+        assert!(set_key(object, "a.b", "Flach".into()));
+        assert!(set_key(object, "messages.new", "Neu".into()));
+        assert!(set_key(object, "nested.new.key", "Neu".into()));
+        assert!(set_key(object, "hello.world", "Welt".into()));
+
+        assert_eq!(
+            value,
+            json!({
+                "hello": "Hallo",
+                "hello.world": "Welt",
+                "messages": { "hi": "Hallo", "user.name": "Nom", "new": "Neu" },
+                "a.b": "Flach",
+                "nested": { "new": { "key": "Neu" } },
+            })
+        );
+        assert!(has_key(&value, "messages.user.name"));
+        assert!(has_key(&value, "nested.new.key"));
+        assert!(!has_key(&value, "nested.new"));
+        assert!(!has_key(&value, "missing"));
+    }
+
+    #[test]
+    fn test_set_key_in_arrays() {
+        let mut value = json!({
+            "days": ["Sunday", "Monday"],
+            "menu": { "items": [{ "label": "File" }, ["Edit", "Copy"]] },
+        });
+        let Value::Object(object) = &mut value else {
+            unreachable!()
+        };
+
+        assert!(set_key(object, "days.1", "Montag".into()));
+        assert!(!set_key(object, "days.0", "Sunday".into()));
+        assert!(set_key(object, "menu.items.0.label", "Datei".into()));
+        assert!(set_key(object, "menu.items.1.1", "Kopieren".into()));
+
+        assert_eq!(
+            value,
+            json!({
+                "days": ["Sunday", "Montag"],
+                "menu": { "items": [{ "label": "Datei" }, ["Edit", "Kopieren"]] },
+            })
+        );
+        assert!(has_key(&value, "days.0"));
+        assert!(!has_key(&value, "days"));
+        assert!(!has_key(&value, "days.2"));
+    }
+
+    #[test]
+    fn test_update_locales() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-updater-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.de.yml"), "# Greetings\nhello: Hallo\n").unwrap();
+        std::fs::write(dir.join("de.json"), r#"{"messages": {"hi": "Hi"}}"#).unwrap();
+        std::fs::write(dir.join("de.toml"), "# Menu\n[menu]\nfile = \"Datei\"\n").unwrap();
+
+        let translations = [
+            ("messages.hi", "Hallo"),
+            ("menu.file", "Datei"),
+            ("menu.edit", "Bearbeiten"),
+            ("bye", "Tschüss"),
+            ("menu", "Menü"),
+        ]
+        .map(|(key, text)| (key.to_string(), text.to_string()));
+        let update = update_locales(&dir, Layout::default(), "de", translations).unwrap();
+        assert_eq!(update.conflicts, vec!["menu"]);
+        assert_eq!(
+            update.paths,
+            vec![
+                dir.join("app.de.yml"),
+                dir.join("de.json"),
+                dir.join("de.toml")
+            ]
+        );
+        assert_eq!(update.rewritten, vec![dir.join("de.json")]);
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(
+            read("app.de.yml"),
+            "# Greetings\nhello: Hallo\nbye: Tschüss\n"
+        );
+        assert_eq!(
+            read("de.json"),
+            "{\n  \"messages\": {\n    \"hi\": \"Hallo\"\n  }\n}\n"
+        );
+        assert_eq!(
+            read("de.toml"),
+            "# Menu\n[menu]\nfile = \"Datei\"\nedit = \"Bearbeiten\"\n"
+        );
+
+        // A new locale is written to a new file
+        let translations = [("hello".to_string(), "Bonjour".to_string())];
+        let update = update_locales(&dir, Layout::default(), "fr", translations).unwrap();
+        assert_eq!(update.paths, vec![dir.join("fr.yml")]);
+        assert_eq!(read("fr.yml"), "hello: Bonjour\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_locales_with_all_locales_in_a_file() {
+        let dir =
+            std::env::temp_dir().join(format!("rust-i18n-updater-multi-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("app.yml"),
+            "en:\n  hello: Hello\nde:\n  hello: Hallo\n",
+        )
+        .unwrap();
+
+        let translations = [("hello".to_string(), "Hallo!".to_string())];
+        update_locales(&dir, Layout::Rails, "de", translations).unwrap();
+        let translations = [("bye".to_string(), "Au revoir".to_string())];
+        update_locales(&dir, Layout::Rails, "fr", translations).unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(
+            read("app.yml"),
+            "en:\n  hello: Hello\nde:\n  hello: Hallo!\n"
+        );
+        assert_eq!(read("fr.yml"), "fr:\n  bye: Au revoir\n");
+
+        std::fs::write(dir.join("app.yml"), "hello:\n  en: Hello\n").unwrap();
+        std::fs::remove_file(dir.join("fr.yml")).unwrap();
+        let translations = [
+            ("hello".to_string(), "Hello!".to_string()),
+            ("messages.hi".to_string(), "Hi".to_string()),
+        ];
+        update_locales(&dir, Layout::Leaf, "en", translations).unwrap();
+        assert_eq!(
+            read("app.yml"),
+            "hello:\n  en: Hello!\nmessages:\n  hi:\n    en: Hi\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_locales_with_namespaces() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-updater-ns-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("de")).unwrap();
+        std::fs::write(dir.join("de/admin.yml"), "title: Verwaltung\n").unwrap();
+        std::fs::write(dir.join("de/common.yml"), "hello: Hallo\n").unwrap();
+
+        let translations = [
+            ("common.hello", "Hallo!"),
+            ("common.bye", "Tschüss"),
+            ("admin.users.title", "Benutzer"),
+            ("other.key", "Andere"),
+        ]
+        .map(|(key, text)| (key.to_string(), text.to_string()));
+        let update = update_locales(&dir, Layout::LocaleDirectory, "de", translations).unwrap();
+        assert_eq!(
+            update.paths,
+            vec![
+                dir.join("de/admin.yml"),
+                dir.join("de/common.yml"),
+                dir.join("de.yml")
+            ]
+        );
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(
+            read("de/admin.yml"),
+            "title: Verwaltung\nusers:\n  title: Benutzer\n"
+        );
+        assert_eq!(read("de/common.yml"), "hello: Hallo!\nbye: Tschüss\n");
+        assert_eq!(read("de.yml"), "other:\n  key: Andere\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
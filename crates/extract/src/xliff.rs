@@ -0,0 +1,496 @@
+use crate::extractor::Message;
+use crate::generator::relative_path;
+use anyhow::{anyhow, Error};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// The version of an XLIFF document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// XLIFF 1.2, `<trans-unit>` elements with the `<context-group>` of the source locations.
+    V1_2,
+    /// XLIFF 2.0, `<unit>` elements with a `<segment>` and the `<notes>` of the source locations.
+    V2_0,
+}
+
+/// A translation unit, the text of a key in the source and target locales.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    pub key: String,
+    pub source: String,
+    /// The translation, `None` if the target locale has no translation of the key.
+    pub target: Option<String>,
+    /// The source locations of `t!`, as `(file, line)` with the file relative to the crate root.
+    pub locations: Vec<(String, usize)>,
+}
+
+/// Collect the units of all keys of the source locale, and the keys used in the source code.
+///
+/// A key only used in the source code has the key itself as the source text.
+pub fn units<'a>(
+    translations: &HashMap<String, HashMap<String, String>>,
+    source_locale: &str,
+    target_locale: &str,
+    source_root: &Path,
+    messages: impl IntoIterator<Item = &'a Message>,
+) -> Vec<Unit> {
+    let empty = HashMap::new();
+    let source = translations.get(source_locale).unwrap_or(&empty);
+    let target = translations.get(target_locale).unwrap_or(&empty);
+
+    let mut locations: HashMap<&str, Vec<(String, usize)>> = HashMap::new();
+    for m in messages {
+        let entry = locations.entry(&m.key).or_default();
+        for location in &m.locations {
+            entry.push((relative_path(source_root, &location.file), location.line));
+        }
+    }
+
+    let keys = source
+        .keys()
+        .map(|key| key.as_str())
+        .chain(locations.keys().copied())
+        .collect::<BTreeSet<_>>();
+
+    keys.into_iter()
+        .map(|key| Unit {
+            key: key.to_string(),
+            source: source.get(key).cloned().unwrap_or_else(|| key.to_string()),
+            target: target.get(key).cloned(),
+            locations: locations.remove(key).unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Generate an XLIFF document of the units, an unit without target is left to translate.
+pub fn generate(
+    version: Version,
+    source_locale: &str,
+    target_locale: &str,
+    units: &[Unit],
+) -> String {
+    let mut content = String::new();
+    content.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    match version {
+        Version::V1_2 => {
+            content.push_str(
+                "<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n",
+            );
+            content.push_str(&format!(
+                "  <file original=\"rust-i18n\" datatype=\"plaintext\" source-language=\"{}\" target-language=\"{}\">\n",
+                escape(source_locale),
+                escape(target_locale)
+            ));
+            content.push_str("    <body>\n");
+            for unit in units {
+                content.push_str(&format!(
+                    "      <trans-unit id=\"{}\">\n",
+                    escape(&unit.key)
+                ));
+                content.push_str(&format!(
+                    "        <source>{}</source>\n",
+                    escape(&unit.source)
+                ));
+                if let Some(target) = &unit.target {
+                    content.push_str(&format!(
+                        "        <target state=\"translated\">{}</target>\n",
+                        escape(target)
+                    ));
+                }
+                for (file, line) in &unit.locations {
+                    content.push_str("        <context-group purpose=\"location\">\n");
+                    content.push_str(&format!(
+                        "          <context context-type=\"sourcefile\">{}</context>\n",
+                        escape(file)
+                    ));
+                    content.push_str(&format!(
+                        "          <context context-type=\"linenumber\">{}</context>\n",
+                        line
+                    ));
+                    content.push_str("        </context-group>\n");
+                }
+                content.push_str("      </trans-unit>\n");
+            }
+            content.push_str("    </body>\n");
+            content.push_str("  </file>\n");
+        }
+        Version::V2_0 => {
+            content.push_str(&format!(
+                "<xliff version=\"2.0\" xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" srcLang=\"{}\" trgLang=\"{}\">\n",
+                escape(source_locale),
+                escape(target_locale)
+            ));
+            content.push_str("  <file id=\"rust-i18n\">\n");
+            for unit in units {
+                content.push_str(&format!("    <unit id=\"{}\">\n", escape(&unit.key)));
+                if !unit.locations.is_empty() {
+                    content.push_str("      <notes>\n");
+                    for (file, line) in &unit.locations {
+                        content.push_str(&format!(
+                            "        <note category=\"location\">{}:{}</note>\n",
+                            escape(file),
+                            line
+                        ));
+                    }
+                    content.push_str("      </notes>\n");
+                }
+                let state = if unit.target.is_some() {
+                    "translated"
+                } else {
+                    "initial"
+                };
+                content.push_str(&format!("      <segment state=\"{}\">\n", state));
+                content.push_str(&format!(
+                    "        <source>{}</source>\n",
+                    escape(&unit.source)
+                ));
+                if let Some(target) = &unit.target {
+                    content.push_str(&format!("        <target>{}</target>\n", escape(target)));
+                }
+                content.push_str("      </segment>\n");
+                content.push_str("    </unit>\n");
+            }
+            content.push_str("  </file>\n");
+        }
+    }
+
+    content.push_str("</xliff>\n");
+    content
+}
+
+/// The translated units of an XLIFF document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Document {
+    pub version: Option<Version>,
+    pub source_locale: Option<String>,
+    pub target_locale: Option<String>,
+    /// The key and translation of the units.
+    pub units: Vec<(String, String)>,
+}
+
+/// The unit being parsed.
+#[derive(Default)]
+struct PendingUnit {
+    id: String,
+    translated: bool,
+    target: Option<String>,
+}
+
+/// Parse an XLIFF 1.2 or 2.0 document.
+///
+/// The units without a target or with an empty target are skipped, and the units marked as not
+/// translated, by `translate="no"`, a 1.2 target state like `new` or `needs-translation`, or
+/// the `initial` state of a 2.0 segment.
+pub fn parse(content: &str) -> Result<Document, Error> {
+    let mut reader = Reader::from_str(content);
+    let mut document = Document::default();
+    let mut unit: Option<PendingUnit> = None;
+    // The depth of the elements in `<target>`, the text of the inline elements is kept
+    let mut target_depth = 0;
+
+    loop {
+        let position = reader.buffer_position();
+        let error = |err: &dyn std::fmt::Display| {
+            anyhow!("invalid XLIFF at position {}, {}", position, err)
+        };
+
+        let event = reader.read_event().map_err(|err| error(&err))?;
+        let target = unit.as_mut().and_then(|unit| unit.target.as_mut());
+        match event {
+            Event::Start(_) if target_depth > 0 => target_depth += 1,
+            Event::End(_) if target_depth > 0 => target_depth -= 1,
+            Event::Text(e) if target_depth > 0 => {
+                let text = e.unescape().map_err(|err| error(&err))?;
+                target.into_iter().for_each(|target| target.push_str(&text));
+            }
+            Event::CData(e) if target_depth > 0 => {
+                let text = String::from_utf8_lossy(&e);
+                target.into_iter().for_each(|target| target.push_str(&text));
+            }
+            Event::Start(e) => {
+                let is_target =
+                    start_element(&e, true, &mut document, &mut unit).map_err(|err| error(&err))?;
+                if is_target {
+                    target_depth = 1;
+                }
+            }
+            Event::Empty(e) => {
+                start_element(&e, false, &mut document, &mut unit).map_err(|err| error(&err))?;
+            }
+            Event::End(e) => {
+                if matches!(e.local_name().as_ref(), b"trans-unit" | b"unit") {
+                    if let Some(unit) = unit.take() {
+                        match unit.target {
+                            Some(target) if unit.translated && !target.is_empty() => {
+                                document.units.push((unit.id, target));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if document.version.is_none() {
+        return Err(anyhow!(
+            "invalid XLIFF, expected an `<xliff>` of version 1.2 or 2.0"
+        ));
+    }
+
+    Ok(document)
+}
+
+/// Handle a start or empty element, returns whether it's the start of a `<target>`.
+fn start_element(
+    e: &BytesStart,
+    is_start: bool,
+    document: &mut Document,
+    unit: &mut Option<PendingUnit>,
+) -> Result<bool, Error> {
+    let attribute = |name: &str| -> Result<Option<String>, Error> {
+        match e.try_get_attribute(name)? {
+            Some(attr) => Ok(Some(attr.unescape_value()?.into_owned())),
+            None => Ok(None),
+        }
+    };
+
+    match e.local_name().as_ref() {
+        b"xliff" => {
+            document.version = match attribute("version")?.as_deref() {
+                Some("1.2") => Some(Version::V1_2),
+                Some(version) if version.starts_with("2.") => Some(Version::V2_0),
+                _ => None,
+            };
+            document.source_locale = attribute("srcLang")?;
+            document.target_locale = attribute("trgLang")?;
+        }
+        b"file" => {
+            if let Some(locale) = attribute("source-language")? {
+                document.source_locale = Some(locale);
+            }
+            if let Some(locale) = attribute("target-language")? {
+                document.target_locale = Some(locale);
+            }
+        }
+        b"trans-unit" | b"unit" if is_start => {
+            let id = attribute("id")?.ok_or_else(|| anyhow!("expected the `id` of a unit"))?;
+            *unit = Some(PendingUnit {
+                id,
+                translated: attribute("translate")?.as_deref() != Some("no"),
+                target: None,
+            });
+        }
+        b"segment" => {
+            if let (Some("initial"), Some(unit)) = (attribute("state")?.as_deref(), unit.as_mut()) {
+                unit.translated = false;
+            }
+        }
+        b"target" => {
+            if let Some(unit) = unit.as_mut() {
+                if matches!(
+                    attribute("state")?.as_deref(),
+                    Some("new" | "needs-translation" | "needs-adaptation" | "needs-l10n")
+                ) {
+                    unit.translated = false;
+                }
+                // The targets of the segments of a 2.0 unit are joined
+                unit.target.get_or_insert_with(String::new);
+            }
+            return Ok(is_start);
+        }
+        _ => {}
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extractor::Location;
+    use std::path::PathBuf;
+
+    fn sample_units() -> Vec<Unit> {
+        let translations = HashMap::from([
+            (
+                "en".to_string(),
+                HashMap::from([
+                    ("hello".to_string(), "Hello <b>world</b>".to_string()),
+                    ("bye".to_string(), "Bye".to_string()),
+                ]),
+            ),
+            (
+                "de".to_string(),
+                HashMap::from([("hello".to_string(), "Hallo <b>Welt</b>".to_string())]),
+            ),
+        ]);
+        let messages = [Message {
+            key: "hello".into(),
+            index: 0,
+            locations: vec![Location {
+                file: PathBuf::from("./src/main.rs"),
+                line: 10,
+            }],
+            notes: vec![],
+        }];
+
+        units(&translations, "en", "de", Path::new("./"), &messages)
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(
+            sample_units(),
+            vec![
+                Unit {
+                    key: "bye".into(),
+                    source: "Bye".into(),
+                    target: None,
+                    locations: vec![],
+                },
+                Unit {
+                    key: "hello".into(),
+                    source: "Hello <b>world</b>".into(),
+                    target: Some("Hallo <b>Welt</b>".into()),
+                    locations: vec![("src/main.rs".into(), 10)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_v1_2() {
+        assert_eq!(
+            generate(Version::V1_2, "en", "de", &sample_units()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file original="rust-i18n" datatype="plaintext" source-language="en" target-language="de">
+    <body>
+      <trans-unit id="bye">
+        <source>Bye</source>
+      </trans-unit>
+      <trans-unit id="hello">
+        <source>Hello &lt;b&gt;world&lt;/b&gt;</source>
+        <target state="translated">Hallo &lt;b&gt;Welt&lt;/b&gt;</target>
+        <context-group purpose="location">
+          <context context-type="sourcefile">src/main.rs</context>
+          <context context-type="linenumber">10</context>
+        </context-group>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>
+"#
+        );
+    }
+
+    #[test]
+    fn test_generate_v2_0() {
+        assert_eq!(
+            generate(Version::V2_0, "en", "de", &sample_units()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="en" trgLang="de">
+  <file id="rust-i18n">
+    <unit id="bye">
+      <segment state="initial">
+        <source>Bye</source>
+      </segment>
+    </unit>
+    <unit id="hello">
+      <notes>
+        <note category="location">src/main.rs:10</note>
+      </notes>
+      <segment state="translated">
+        <source>Hello &lt;b&gt;world&lt;/b&gt;</source>
+        <target>Hallo &lt;b&gt;Welt&lt;/b&gt;</target>
+      </segment>
+    </unit>
+  </file>
+</xliff>
+"#
+        );
+    }
+
+    #[test]
+    fn test_parse_generated() {
+        for version in [Version::V1_2, Version::V2_0] {
+            let document = parse(&generate(version, "en", "de", &sample_units())).unwrap();
+            assert_eq!(document.version, Some(version));
+            assert_eq!(document.source_locale.as_deref(), Some("en"));
+            assert_eq!(document.target_locale.as_deref(), Some("de"));
+            assert_eq!(
+                document.units,
+                vec![("hello".to_string(), "Hallo <b>Welt</b>".to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_v1_2() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file original="app" source-language="en" target-language="fr" datatype="plaintext">
+    <body>
+      <trans-unit id="hello"><source>Hello</source><target state="final">Bonjour</target></trans-unit>
+      <trans-unit id="inline"><source>A</source><target>Un <g id="1">mot</g><x id="2"/> &amp; <![CDATA[<b>]]></target></trans-unit>
+      <trans-unit id="new"><source>New</source><target state="new">New</target></trans-unit>
+      <trans-unit id="needs"><source>Needs</source><target state="needs-translation"></target></trans-unit>
+      <trans-unit id="no" translate="no"><source>No</source><target>Non</target></trans-unit>
+      <trans-unit id="empty"><source>Empty</source><target/></trans-unit>
+      <trans-unit id="missing"><source>Missing</source></trans-unit>
+    </body>
+  </file>
+</xliff>"#;
+        let document = parse(content).unwrap();
+        assert_eq!(document.version, Some(Version::V1_2));
+        assert_eq!(document.target_locale.as_deref(), Some("fr"));
+        assert_eq!(
+            document.units,
+            vec![
+                ("hello".to_string(), "Bonjour".to_string()),
+                ("inline".to_string(), "Un mot & <b>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_v2_0() {
+        let content = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="ja">
+  <file id="f1">
+    <unit id="hello">
+      <segment state="reviewed"><source>Hello</source><target>こんにちは</target></segment>
+      <segment><source> world</source><target>世界</target></segment>
+    </unit>
+    <unit id="initial"><segment state="initial"><source>Initial</source><target>Initial</target></segment></unit>
+    <unit id="missing"><segment><source>Missing</source></segment></unit>
+  </file>
+</xliff>"#;
+        let document = parse(content).unwrap();
+        assert_eq!(document.version, Some(Version::V2_0));
+        assert_eq!(document.source_locale.as_deref(), Some("en"));
+        assert_eq!(document.target_locale.as_deref(), Some("ja"));
+        assert_eq!(
+            document.units,
+            vec![("hello".to_string(), "こんにちは世界".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse("<xliff><file></xliff>")
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid XLIFF at position"));
+        assert_eq!(
+            parse("<root/>").unwrap_err().to_string(),
+            "invalid XLIFF, expected an `<xliff>` of version 1.2 or 2.0"
+        );
+    }
+}
@@ -1,12 +1,20 @@
 use quote::quote;
-use rust_i18n_support::{is_debug, load_locales};
+use rust_i18n_support::message_format::Message;
+use rust_i18n_support::{is_debug, load_message_formats, try_load_locales, Layout};
 use std::collections::HashMap;
 use syn::{parse_macro_input, DeriveInput, Expr, Ident, LitStr, Token};
 
+mod metadata;
+mod verify;
+
 struct Args {
     locales_path: String,
-    fallback: Option<String>,
+    fallback: Option<Vec<String>>,
+    fallback_map: Option<Vec<(String, Vec<String>)>>,
     extend: Option<Expr>,
+    check_keys: Option<verify::CheckMode>,
+    message_format: Option<String>,
+    layout: Option<Layout>,
 }
 
 impl Args {
@@ -23,25 +31,79 @@ impl Args {
 
         match ident.as_str() {
             "fallback" => {
-                let val = input.parse::<LitStr>()?.value();
-                self.fallback = Some(val);
+                self.fallback = Some(Self::parse_locales(input)?);
+            }
+            "fallback_map" => {
+                let content;
+                syn::braced!(content in input);
+
+                let mut fallback_map = vec![];
+                while !content.is_empty() {
+                    let locale = content.parse::<LitStr>()?.value();
+                    content.parse::<Token![=>]>()?;
+                    fallback_map.push((locale, Self::parse_locales(&content)?));
+
+                    if content.parse::<Token![,]>().is_err() {
+                        break;
+                    }
+                }
+                self.fallback_map = Some(fallback_map);
             }
             "backend" => {
                 let val = input.parse::<Expr>()?;
                 self.extend = Some(val);
             }
+            "check_keys" => {
+                let val = input.parse::<LitStr>()?;
+                self.check_keys = Some(verify::CheckMode::parse(&val)?);
+            }
+            "message_format" => {
+                let val = input.parse::<LitStr>()?;
+                if !MESSAGE_FORMATS.contains(&val.value().as_str()) {
+                    return Err(syn::Error::new(
+                        val.span(),
+                        "message_format must be \"icu\", \"fluent\" or \"default\"",
+                    ));
+                }
+                self.message_format = Some(val.value());
+            }
+            "layout" => {
+                let val = input.parse::<LitStr>()?;
+                let layout = val
+                    .value()
+                    .parse()
+                    .map_err(|err| syn::Error::new(val.span(), err))?;
+                self.layout = Some(layout);
+            }
             _ => {}
         }
 
         // Continue to consume reset of options
-        if input.parse::<Token![,]>().is_ok() {
+        if input.parse::<Token![,]>().is_ok() && !input.is_empty() {
             self.consume_options(input)?;
         }
 
         Ok(())
     }
+
+    /// Parse a locale `"en"` or a list of locales `["pt-PT", "en"]`.
+    fn parse_locales(input: syn::parse::ParseStream) -> syn::parse::Result<Vec<String>> {
+        if input.peek(syn::token::Bracket) {
+            let content;
+            syn::bracketed!(content in input);
+
+            let locales = content.parse_terminated(|input| input.parse::<LitStr>(), Token![,])?;
+            return Ok(locales.iter().map(|locale| locale.value()).collect());
+        }
+
+        Ok(vec![input.parse::<LitStr>()?.value()])
+    }
 }
 
+/// Syntax of the translations, `default` for `%{name}` placeholders, `icu` for ICU MessageFormat,
+/// and `fluent` for the Fluent syntax, which is also the format of the `.ftl` files.
+const MESSAGE_FORMATS: &[&str] = &["default", "icu", "fluent"];
+
 impl syn::parse::Parse for Args {
     /// Parse macro arguments.
     ///
@@ -49,6 +111,7 @@ impl syn::parse::Parse for Args {
     /// i18n!();
     /// i18n!("locales");
     /// i18n!("locales", fallback = "en");
+    /// i18n!("locales", fallback = ["pt-PT", "en"], fallback_map = { "pt-BR" => ["pt-PT", "en"], "gl" => "es" });
     /// ```
     ///
     /// Ref: https://docs.rs/syn/latest/syn/parse/index.html
@@ -58,7 +121,11 @@ impl syn::parse::Parse for Args {
         let mut result = Self {
             locales_path: String::from("locales"),
             fallback: None,
+            fallback_map: None,
             extend: None,
+            check_keys: None,
+            message_format: None,
+            layout: None,
         };
 
         if lookahead.peek(LitStr) {
@@ -80,23 +147,95 @@ impl syn::parse::Parse for Args {
 /// This will load all translations by glob `**/*.yml` from the given path, default: `${CARGO_MANIFEST_DIR}/locales`.
 ///
 /// Attribute `fallback` for set the fallback locale, if present `t` macro will use it as the fallback locale.
+/// It can be a list of locales, tried in order.
+///
+/// Attribute `fallback_map` for set the fallback locales of a locale, which are tried before the `fallback` locales.
+///
+/// Both attributes default to `fallback` and `fallback-map` of `[package.metadata.i18n]` in Cargo.toml.
+///
+/// Attribute `check_keys` for verify the literal keys of `t!` exist in the locales at compile time,
+/// `"strict"` emits a compile error for a missing key, and `"lenient"` emits a warning.
+///
+/// Attribute `message_format = "icu"` for parse all translations as ICU MessageFormat, which is evaluated with
+/// the named arguments of `t!`. A locale file can also set it by a top-level `_message_format: icu` key,
+/// which takes precedence. The default is `message-format` of `[package.metadata.i18n]`, or `"default"`.
+/// Malformed ICU messages are compile errors.
+///
+/// Attribute `layout` for take the locale, and the namespace of the keys, from the file paths: `"{locale}/{namespace}.yml"`,
+/// `"{namespace}.{locale}.yml"` or `"{locale}.yml"`. The namespace is the prefix of the keys, like `common.hello`
+/// of `hello` in `de/common.yml`. The default is `layout` of `[package.metadata.i18n]`, or the locale is the
+/// last part of the file stem, like `en` of `app.en.yml`. With `"rails"` the top-level keys of a file are the locales,
+/// like `en: { hello: Hello }`, and with `"leaf"` the locales are the keys of the texts, like `hello: { en: Hello }`.
+///
+/// A missing translation is looked up in the BCP-47 truncated locales first, e.g. `zh-Hant-TW` => `zh-Hant` => `zh`,
+/// and then in the fallback locales.
 ///
 /// ```ignore
 /// i18n!();
 /// i18n!("locales");
 /// i18n!("locales", fallback = "en");
+/// i18n!("locales", fallback = ["pt-PT", "es", "en"]);
+/// i18n!("locales", fallback_map = { "pt-BR" => ["pt-PT", "en"], "gl" => ["es", "en"] });
+/// i18n!("locales", check_keys = "strict");
+/// i18n!("locales", message_format = "icu");
+/// i18n!("locales", layout = "{locale}/{namespace}.yml");
+/// i18n!("config/locales", layout = "rails");
 /// ```
 #[proc_macro]
 pub fn i18n(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let args = parse_macro_input!(input as Args);
+    let mut args = parse_macro_input!(input as Args);
 
     // CARGO_MANIFEST_DIR is current build directory
     let cargo_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is empty");
     let current_dir = std::path::PathBuf::from(cargo_dir);
     let locales_path = current_dir.join(&args.locales_path);
 
-    let data = load_locales(&locales_path.display().to_string(), |_| false);
-    let code = generate_code(data, args);
+    if let Ok(contents) = std::fs::read_to_string(current_dir.join("Cargo.toml")) {
+        let metadata = metadata::parse(&contents);
+        args.fallback = args.fallback.or(metadata.fallback);
+        args.fallback_map = args.fallback_map.or(metadata.fallback_map);
+        args.message_format = args.message_format.or(metadata.message_format);
+        match metadata.layout.as_deref().map(str::parse) {
+            Some(Ok(layout)) => args.layout = args.layout.or(Some(layout)),
+            Some(Err(err)) if args.layout.is_none() => {
+                let message = format!("{} in Cargo.toml", err);
+                return syn::Error::new(proc_macro2::Span::call_site(), message)
+                    .to_compile_error()
+                    .into();
+            }
+            _ => {}
+        }
+    }
+    let layout = args.layout.unwrap_or_default();
+
+    if let Some(format) = args.message_format.as_deref() {
+        if !MESSAGE_FORMATS.contains(&format) {
+            let message = format!("unknown message format `{}` in Cargo.toml", format);
+            return syn::Error::new(proc_macro2::Span::call_site(), message)
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let locales_path = locales_path.display().to_string();
+    let data = match try_load_locales(&locales_path, layout, |_| false) {
+        Ok(data) => data,
+        Err(err) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), err)
+                .to_compile_error()
+                .into()
+        }
+    };
+    let messages = match parsed_messages(
+        &data,
+        &locales_path,
+        layout,
+        args.message_format.as_deref(),
+    ) {
+        Ok(messages) => messages,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let code = generate_code(data, messages, &locales_path, args);
 
     if is_debug() {
         println!(
@@ -108,8 +247,97 @@ pub fn i18n(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     code.into()
 }
 
+/// Verify a literal `t!` key exists in the locales, see the `check_keys` attribute of `i18n!`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn _rust_i18n_verify_key(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input as verify::VerifyArgs);
+    verify::verify(args).into()
+}
+
+/// The translations in ICU MessageFormat or Fluent syntax as `(locale, key, format, value)`,
+/// all of them are checked to be valid.
+fn parsed_messages(
+    translations: &HashMap<String, HashMap<String, String>>,
+    locales_path: &str,
+    layout: Layout,
+    message_format: Option<&str>,
+) -> syn::parse::Result<Vec<(String, String, String, String)>> {
+    let formats = load_message_formats(locales_path, layout, |_| false)
+        .map_err(|err| syn::Error::new(proc_macro2::Span::call_site(), err))?;
+
+    let mut messages = vec![];
+    let mut errors = vec![];
+    let mut references = HashMap::<&str, HashMap<String, Vec<String>>>::new();
+    for (locale, trs) in translations {
+        for (key, value) in trs {
+            let format = formats
+                .get(locale)
+                .and_then(|formats| formats.get(key))
+                .map(|format| format.as_str())
+                .or(message_format);
+
+            let (parsed, name) = match format {
+                Some("icu") => (Message::parse(value), "ICU"),
+                Some("fluent") => (Message::parse_fluent(value), "Fluent"),
+                Some(format) if !MESSAGE_FORMATS.contains(&format) => {
+                    errors.push(format!(
+                        "unknown message format `{}` of `{}` in locale `{}`",
+                        format, key, locale
+                    ));
+                    continue;
+                }
+                _ => continue,
+            };
+
+            match parsed {
+                Ok(message) => {
+                    references
+                        .entry(locale)
+                        .or_default()
+                        .insert(key.clone(), message.references());
+                    messages.push((
+                        locale.clone(),
+                        key.clone(),
+                        format.unwrap_or_default().to_string(),
+                        message.source().to_string(),
+                    ))
+                }
+                Err(err) => errors.push(format!(
+                    "invalid {} message `{}` in locale `{}`: {}",
+                    name, key, locale, err
+                )),
+            }
+        }
+    }
+
+    // A cyclic reference can't be formatted
+    for (locale, references) in &references {
+        for key in rust_i18n_support::fluent::cyclic_keys(references) {
+            errors.push(format!(
+                "Fluent message `{}` in locale `{}` references itself",
+                key, locale
+            ));
+        }
+    }
+
+    errors.sort();
+    let mut errors = errors
+        .into_iter()
+        .map(|message| syn::Error::new(proc_macro2::Span::call_site(), message));
+    if let Some(mut error) = errors.next() {
+        errors.for_each(|other| error.combine(other));
+        return Err(error);
+    }
+
+    messages.sort();
+    Ok(messages)
+}
+
 fn generate_code(
     translations: HashMap<String, HashMap<String, String>>,
+    messages: Vec<(String, String, String, String)>,
+    locales_path: &str,
     args: Args,
 ) -> proc_macro2::TokenStream {
     let mut all_translations = Vec::<proc_macro2::TokenStream>::new();
@@ -131,15 +359,23 @@ fn generate_code(
         });
     });
 
-    let fallback = if let Some(fallback) = args.fallback {
-        quote! {
-            Some(#fallback)
-        }
-    } else {
+    let messages = messages.iter().map(|(locale, key, format, value)| {
         quote! {
-            None
+            (#locale, #key, #format, #value)
         }
-    };
+    });
+
+    let fallback = args.fallback.unwrap_or_default();
+
+    let locale_fallbacks = args
+        .fallback_map
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(locale, fallback)| {
+            quote! {
+                fallback.add_locale_fallback(#locale, &[#(#fallback),*]);
+            }
+        });
 
     let extend_code = if let Some(extend) = args.extend {
         quote! {
@@ -149,49 +385,177 @@ fn generate_code(
         quote! {}
     };
 
+    let check_key_code = if let Some(mode) = args.check_keys {
+        let mode = mode.as_str();
+        let layout = args
+            .layout
+            .filter(|layout| *layout != Layout::default())
+            .map(|layout| {
+                let layout = layout.to_string();
+                quote! { layout = #layout, }
+            });
+        quote! {
+            ($key:expr) => {
+                rust_i18n::_rust_i18n_verify_key!(#locales_path, #layout #mode, $key)
+            };
+        }
+    } else {
+        quote! {
+            ($key:expr) => {
+                $key
+            };
+        }
+    };
+
     // result
     quote! {
         use rust_i18n::BackendExt;
-        use std::sync::{Arc, Mutex};
 
-        /// I18n backend instance
-        static _RUST_I18N_BACKEND: rust_i18n::once_cell::sync::Lazy<Arc<Mutex<Box<dyn rust_i18n::Backend>>>> = rust_i18n::once_cell::sync::Lazy::new(|| {
+        /// I18n backend instance, reads are lock-free
+        static _RUST_I18N_BACKEND: rust_i18n::once_cell::sync::Lazy<rust_i18n::AtomicBackend> = rust_i18n::once_cell::sync::Lazy::new(|| {
             let mut backend = rust_i18n::SimpleBackend::new();
             #(#all_translations)*
             #extend_code
 
-            Arc::new(Mutex::new(Box::new(backend)))
+            rust_i18n::AtomicBackend::new(Box::new(backend))
+        });
+
+        /// Locales to lookup for each requested locale, with BCP-47 truncation and the fallback locales
+        static _RUST_I18N_FALLBACK: rust_i18n::once_cell::sync::Lazy<rust_i18n::LocaleFallback> = rust_i18n::once_cell::sync::Lazy::new(|| {
+            let mut fallback = rust_i18n::LocaleFallback::new(&[#(#fallback),*]);
+            #(#locale_fallbacks)*
+            fallback
         });
 
-        static _RUST_I18N_FALLBACK_LOCALE: Option<&'static str> = #fallback;
+        /// Translations in ICU MessageFormat or Fluent syntax by locale and key, parsed on first use
+        static _RUST_I18N_MESSAGES: rust_i18n::once_cell::sync::Lazy<std::collections::HashMap<&'static str, std::collections::HashMap<&'static str, rust_i18n::Message>>> = rust_i18n::once_cell::sync::Lazy::new(|| {
+            let all: &[(&str, &str, &str, &str)] = &[#(#messages),*];
 
-        /// Get I18n text by locale and key
+            let mut messages = std::collections::HashMap::<&'static str, std::collections::HashMap<&'static str, rust_i18n::Message>>::new();
+            for &(locale, key, format, value) in all {
+                // All messages are validated by `i18n!` at compile time
+                let message = match format {
+                    "fluent" => rust_i18n::Message::parse_fluent(value).expect("Invalid Fluent message"),
+                    _ => rust_i18n::Message::parse(value).expect("Invalid ICU message"),
+                };
+                messages.entry(locale).or_default().insert(key, message);
+            }
+            messages
+        });
+
+        /// Lookup the translation in a locale, and format it with the arguments if it's an ICU or Fluent message,
+        /// the messages and terms referenced by a Fluent message are looked up with the fallback locales
         #[inline]
-        pub fn _rust_i18n_translate(locale: &str, key: &str) -> String {
-            let mut backend = _RUST_I18N_BACKEND.lock().unwrap();
+        fn _rust_i18n_lookup(locale: &str, key: &str, args: &[(&str, String)]) -> Option<std::borrow::Cow<'static, str>> {
+            let value = _RUST_I18N_BACKEND.translate(locale, key)?;
+
+            match _RUST_I18N_MESSAGES.get(locale).and_then(|messages| messages.get(key)) {
+                // Skip the message replaced at runtime, e.g. by `t_add!`
+                Some(message) if message.source() == value => {
+                    let resolve = |key: &str, args: &[(&str, String)]| {
+                        _RUST_I18N_FALLBACK
+                            .chain(locale)
+                            .iter()
+                            .find_map(|locale| _rust_i18n_lookup(locale, key, args))
+                            .map(|value| value.into_owned())
+                    };
+                    Some(message.format_with(locale, args, &resolve))
+                }
+                _ => Some(value),
+            }
+        }
 
-            if let Some(value) = backend.translate(locale, key) {
-                return value.to_owned();
+        /// Get I18n text by locale and key, borrowed from the embedded translations if found
+        #[inline]
+        pub fn _rust_i18n_translate(locale: &str, key: &str) -> std::borrow::Cow<'static, str> {
+            _rust_i18n_translate_args(locale, key, &[])
+        }
+
+        /// Get I18n text by locale and key with the named arguments of `t!`,
+        /// a `count` argument picks the plural form with each locale's rules
+        pub fn _rust_i18n_translate_args(locale: &str, key: &str, args: &[(&str, String)]) -> std::borrow::Cow<'static, str> {
+            let count = args
+                .iter()
+                .find(|(name, _)| *name == "count")
+                .and_then(|(_, count)| count.parse::<rust_i18n::PluralOperands>().ok());
+
+            let chain = _RUST_I18N_FALLBACK.chain(locale);
+            for locale in chain.iter() {
+                let value = match &count {
+                    Some(count) => rust_i18n::plural_keys(locale, key, count)
+                        .iter()
+                        .find_map(|plural_key| _rust_i18n_lookup(locale, plural_key, args)),
+                    None => _rust_i18n_lookup(locale, key, args),
+                };
+
+                if let Some(value) = value {
+                    return value;
+                }
             }
 
-            if let Some(fallback) = _RUST_I18N_FALLBACK_LOCALE {
-                if let Some(value) = backend.translate(fallback, key) {
-                    return value.to_owned();
+            rust_i18n::handle_missing(locale, key, &chain)
+        }
+
+        /// Get the texts of an array by locale and key, from the first locale of the chain which has the array
+        pub fn _rust_i18n_translate_list(locale: &str, key: &str) -> Vec<String> {
+            let chain = _RUST_I18N_FALLBACK.chain(locale);
+            for locale in chain.iter() {
+                let items = (0..)
+                    .map_while(|i| _rust_i18n_lookup(locale, &format!("{}.{}", key, i), &[]))
+                    .map(|value| rust_i18n::format_placeholders(value, &[]).into_owned())
+                    .collect::<Vec<_>>();
+
+                if !items.is_empty() {
+                    return items;
                 }
             }
 
-            format!("{}", key.to_owned())
+            vec![]
+        }
+
+        /// Get the texts of the keys under a prefix by locale as a nested map, each text is looked up with the fallback locales
+        pub fn _rust_i18n_translate_map(locale: &str, prefix: &str) -> std::collections::BTreeMap<String, rust_i18n::TextTree> {
+            let prefix = format!("{}.", prefix);
+            let mut keys = _RUST_I18N_FALLBACK
+                .chain(locale)
+                .iter()
+                .flat_map(|locale| _RUST_I18N_BACKEND.keys(locale))
+                .filter(|key| key.starts_with(&prefix))
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+
+            rust_i18n::build_tree(keys.iter().map(|key| {
+                let text = rust_i18n::format_placeholders(_rust_i18n_translate(locale, key), &[]);
+                (&key[prefix.len()..], text.into_owned())
+            }))
         }
-    
+
         #[inline]
         pub fn _rust_i18n_add(locale: &str, key: &str, value: &str) {
-            let mut backend = _RUST_I18N_BACKEND.lock().unwrap();
-            backend.add(locale, key, value);
+            _RUST_I18N_BACKEND.add(locale, key, value);
+        }
+
+        /// The helpers of `t!`, `t_list!` and `t_map!` besides `_rust_i18n_translate`, in a module of the same name,
+        /// so importing `_rust_i18n_translate` into a submodule imports them too.
+        #[doc(hidden)]
+        pub mod _rust_i18n_translate {
+            pub use super::{
+                _rust_i18n_translate_args as args,
+                _rust_i18n_translate_list as list,
+                _rust_i18n_translate_map as map,
+            };
+
+            /// Check the key of `t!` exists at compile time, if enabled by `check_keys`.
+            macro_rules! check_key {
+                #check_key_code
+            }
+            #[allow(unused_imports)]
+            pub(crate) use check_key;
         }
 
         pub fn _rust_i18n_available_locales() -> Vec<String> {
-            let mut backend = _RUST_I18N_BACKEND.lock().unwrap();
-            let mut locales = backend.available_locales();
+            let mut locales = _RUST_I18N_BACKEND.available_locales();
             locales.sort();
             locales
         }
@@ -235,7 +599,7 @@ pub fn derive_this_error(input: proc_macro::TokenStream) -> proc_macro::TokenStr
                 let suggestion = t!(&suggestion_key);
         
                 let suggestion = match suggestion != suggestion_key {
-                    true => Some(suggestion),
+                    true => Some(suggestion.into_owned()),
                     false => None
                 };
 
@@ -0,0 +1,121 @@
+use toml::Value;
+
+/// Options of `[package.metadata.i18n]` in Cargo.toml, used as the defaults of `i18n!` arguments.
+///
+/// ```toml
+/// [package.metadata.i18n]
+/// fallback = ["pt-PT", "en"]
+/// message-format = "icu"
+/// layout = "{locale}/{namespace}.yml"
+///
+/// [package.metadata.i18n.fallback-map]
+/// pt-BR = ["pt-PT", "en"]
+/// gl = "es"
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Metadata {
+    pub fallback: Option<Vec<String>>,
+    pub fallback_map: Option<Vec<(String, Vec<String>)>>,
+    pub message_format: Option<String>,
+    pub layout: Option<String>,
+}
+
+/// Parse the metadata from Cargo.toml contents, invalid or missing options are ignored.
+pub fn parse(contents: &str) -> Metadata {
+    let mut metadata = Metadata::default();
+
+    let Ok(manifest) = contents.parse::<toml::Table>() else {
+        return metadata;
+    };
+
+    let Some(i18n) = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("i18n"))
+    else {
+        return metadata;
+    };
+
+    metadata.fallback = i18n.get("fallback").and_then(locales);
+    metadata.fallback_map = i18n
+        .get("fallback-map")
+        .and_then(|map| map.as_table())
+        .map(|map| {
+            map.iter()
+                .filter_map(|(locale, fallback)| Some((locale.clone(), locales(fallback)?)))
+                .collect()
+        });
+    metadata.message_format = i18n
+        .get("message-format")
+        .and_then(|format| format.as_str())
+        .map(|format| format.to_string());
+    metadata.layout = i18n
+        .get("layout")
+        .and_then(|layout| layout.as_str())
+        .map(|layout| layout.to_string());
+
+    metadata
+}
+
+/// A locale `"en"` or a list of locales `["pt-PT", "en"]`.
+fn locales(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::String(locale) => Some(vec![locale.clone()]),
+        Value::Array(locales) => locales
+            .iter()
+            .map(|locale| locale.as_str().map(|locale| locale.to_string()))
+            .collect(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let metadata = parse(
+            r#"
+            [package]
+            name = "foo"
+
+            [package.metadata.i18n]
+            fallback = ["pt-PT", "en"]
+            message-format = "icu"
+            layout = "{locale}/{namespace}.yml"
+
+            [package.metadata.i18n.fallback-map]
+            pt-BR = ["pt-PT", "en"]
+            gl = "es"
+            "#,
+        );
+
+        assert_eq!(
+            metadata.fallback,
+            Some(vec!["pt-PT".to_string(), "en".to_string()])
+        );
+        assert_eq!(
+            metadata.fallback_map,
+            Some(vec![
+                ("gl".to_string(), vec!["es".to_string()]),
+                (
+                    "pt-BR".to_string(),
+                    vec!["pt-PT".to_string(), "en".to_string()]
+                ),
+            ])
+        );
+        assert_eq!(metadata.message_format, Some("icu".to_string()));
+        assert_eq!(
+            metadata.layout,
+            Some("{locale}/{namespace}.yml".to_string())
+        );
+
+        let metadata = parse("[package.metadata.i18n]\nfallback = \"en\"");
+        assert_eq!(metadata.fallback, Some(vec!["en".to_string()]));
+        assert_eq!(metadata.fallback_map, None);
+
+        assert_eq!(parse("[package]\nname = \"foo\""), Metadata::default());
+        assert_eq!(parse("invalid = "), Metadata::default());
+    }
+}
@@ -0,0 +1,326 @@
+use once_cell::sync::Lazy;
+use quote::{quote, quote_spanned};
+use rust_i18n_support::{try_load_locales, Layout};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use syn::{Expr, Lit, LitStr, Token};
+
+/// How `t!` calls with a literal key missing in all locales are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckMode {
+    /// Emit a compile error.
+    Strict,
+    /// Emit a warning.
+    Lenient,
+}
+
+impl CheckMode {
+    pub fn parse(lit: &LitStr) -> syn::parse::Result<Self> {
+        match lit.value().as_str() {
+            "strict" => Ok(CheckMode::Strict),
+            "lenient" => Ok(CheckMode::Lenient),
+            _ => Err(syn::Error::new(
+                lit.span(),
+                "check_keys must be \"strict\" or \"lenient\"",
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckMode::Strict => "strict",
+            CheckMode::Lenient => "lenient",
+        }
+    }
+}
+
+/// Arguments of `_rust_i18n_verify_key!("locales path", "strict", key)`, with the optional
+/// `layout = "{locale}/{namespace}.yml"` after the path.
+pub struct VerifyArgs {
+    locales_path: String,
+    layout: Layout,
+    mode: CheckMode,
+    key: Expr,
+}
+
+impl syn::parse::Parse for VerifyArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let locales_path = input.parse::<LitStr>()?.value();
+        input.parse::<Token![,]>()?;
+        let mut layout = Layout::default();
+        if input.peek(syn::Ident) {
+            let ident = input.parse::<syn::Ident>()?;
+            if ident != "layout" {
+                return Err(syn::Error::new(ident.span(), "expected `layout`"));
+            }
+            input.parse::<Token![=]>()?;
+            let val = input.parse::<LitStr>()?;
+            layout = val
+                .value()
+                .parse()
+                .map_err(|err| syn::Error::new(val.span(), err))?;
+            input.parse::<Token![,]>()?;
+        }
+        let mode = CheckMode::parse(&input.parse::<LitStr>()?)?;
+        input.parse::<Token![,]>()?;
+        let key = input.parse::<Expr>()?;
+
+        Ok(Self {
+            locales_path,
+            layout,
+            mode,
+            key,
+        })
+    }
+}
+
+/// The files with their modification times of the locales path.
+type ModifiedTimes = Vec<(PathBuf, Option<SystemTime>)>;
+
+/// The keys of each locales path and layout, with the modification times of the files they are loaded from.
+type KeysCache = HashMap<(String, Layout), (ModifiedTimes, Arc<BTreeSet<String>>)>;
+
+/// All keys of the locales path, loaded again when a file is changed, added or removed, as the compiler process
+/// may be long-lived like the proc-macro server of rust-analyzer.
+static KEYS: Lazy<Mutex<KeysCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn keys(locales_path: &str, layout: Layout) -> Arc<BTreeSet<String>> {
+    let mut times = vec![];
+    modified_times(Path::new(locales_path), &mut times);
+    times.sort();
+
+    let mut cache = KEYS.lock().unwrap();
+    let cache_key = (locales_path.to_string(), layout);
+    if let Some((cached_times, keys)) = cache.get(&cache_key) {
+        if *cached_times == times {
+            return keys.clone();
+        }
+    }
+
+    // The errors of locale files are reported by `i18n!`
+    let data = try_load_locales(locales_path, layout, |_| false).unwrap_or_default();
+    let keys = Arc::new(
+        data.into_values()
+            .flat_map(|trs| trs.into_keys())
+            .collect::<BTreeSet<_>>(),
+    );
+    cache.insert(cache_key, (times, keys.clone()));
+    keys
+}
+
+fn modified_times(path: &Path, times: &mut ModifiedTimes) {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            modified_times(&path, times);
+        } else {
+            let modified = entry.metadata().and_then(|meta| meta.modified()).ok();
+            times.push((path, modified));
+        }
+    }
+}
+
+/// Expand to the key itself, with a compile error or a warning if the literal key is missing.
+pub fn verify(args: VerifyArgs) -> proc_macro2::TokenStream {
+    let key = args.key;
+
+    let lit = match literal_key(&key) {
+        Some(lit) => lit,
+        None => return quote! { #key },
+    };
+
+    // Nothing to verify against, `i18n!` also accepts a path without locales
+    let keys = keys(&args.locales_path, args.layout);
+    if keys.is_empty() || contains_key(&keys, &lit.value()) {
+        return quote! { #key };
+    }
+
+    let mut message = format!(
+        "translation key `{}` is missing in all locales",
+        lit.value()
+    );
+    if let Some(suggestion) = closest_key(&keys, &lit.value()) {
+        message.push_str(&format!(", did you mean `{}`?", suggestion));
+    }
+
+    match args.mode {
+        CheckMode::Strict => syn::Error::new(lit.span(), message).to_compile_error(),
+        // Use a deprecated item for emit a warning on stable Rust
+        CheckMode::Lenient => quote_spanned! { lit.span() =>
+            {
+                #[deprecated(note = #message)]
+                #[allow(non_upper_case_globals)]
+                const missing_translation_key: () = ();
+                let _ = missing_translation_key;
+                #key
+            }
+        },
+    }
+}
+
+/// Get the string literal of a key, through the groups of `macro_rules` fragments.
+fn literal_key(key: &Expr) -> Option<&LitStr> {
+    match key {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Str(lit) => Some(lit),
+            _ => None,
+        },
+        Expr::Group(expr) => literal_key(&expr.expr),
+        Expr::Paren(expr) => literal_key(&expr.expr),
+        _ => None,
+    }
+}
+
+/// A key exists as a translation, or as the parent of translations like plural forms `inbox.one`.
+fn contains_key(keys: &BTreeSet<String>, key: &str) -> bool {
+    if keys.contains(key) {
+        return true;
+    }
+
+    let prefix = format!("{}.", key);
+    keys.range(prefix.clone()..)
+        .next()
+        .is_some_and(|next| next.starts_with(&prefix))
+}
+
+/// The existing key with the smallest edit distance, if it is close enough to be a typo.
+fn closest_key<'a>(keys: &'a BTreeSet<String>, key: &str) -> Option<&'a str> {
+    let max_distance = (key.chars().count() / 3).max(2);
+
+    keys.iter()
+        .map(|candidate| (levenshtein(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(current)
+            };
+            prev = current;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> BTreeSet<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("hello", "hello"), 0);
+        assert_eq!(levenshtein("mesages.hello", "messages.hello"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_contains_key() {
+        let keys = keys(&["hello", "inbox.one", "inbox.other", "inboxes"]);
+        assert!(contains_key(&keys, "hello"));
+        assert!(contains_key(&keys, "inbox"));
+        assert!(contains_key(&keys, "inboxes"));
+        assert!(!contains_key(&keys, "inbox.few"));
+        assert!(!contains_key(&keys, "hell"));
+    }
+
+    #[test]
+    fn test_closest_key() {
+        let keys = keys(&["hello", "messages.hello", "messages.world"]);
+        assert_eq!(closest_key(&keys, "mesages.hello"), Some("messages.hello"));
+        assert_eq!(closest_key(&keys, "helo"), Some("hello"));
+        assert_eq!(closest_key(&keys, "something.else"), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        let locales_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests/locales");
+
+        let args: VerifyArgs =
+            syn::parse_str(&format!(r#""{}", "strict", "hello""#, locales_path)).unwrap();
+        assert_eq!(verify(args).to_string(), "\"hello\"");
+
+        let args: VerifyArgs =
+            syn::parse_str(&format!(r#""{}", "strict", "helo""#, locales_path)).unwrap();
+        let code = verify(args).to_string();
+        assert!(code.contains("compile_error"));
+        assert!(code
+            .contains("translation key `helo` is missing in all locales, did you mean `hello`?"));
+
+        let args: VerifyArgs =
+            syn::parse_str(&format!(r#""{}", "lenient", "helo""#, locales_path)).unwrap();
+        let code = verify(args).to_string();
+        assert!(code.contains("deprecated"));
+
+        let args: VerifyArgs =
+            syn::parse_str(&format!(r#""{}", "strict", &key"#, locales_path)).unwrap();
+        assert_eq!(verify(args).to_string(), "& key");
+
+        let args: VerifyArgs = syn::parse_str(r#""/not/exists", "strict", "helo""#).unwrap();
+        assert_eq!(verify(args).to_string(), "\"helo\"");
+
+        assert!(syn::parse_str::<VerifyArgs>(r#""/not/exists", "loose", "hello""#).is_err());
+
+        let args: VerifyArgs = syn::parse_str(&format!(
+            r#""{}", layout = "{{locale}}/{{namespace}}.yml", "strict", "hello""#,
+            locales_path
+        ))
+        .unwrap();
+        assert_eq!(args.layout, Layout::LocaleDirectory);
+        assert!(
+            syn::parse_str::<VerifyArgs>(r#""/not/exists", layout = "{x}", "strict", "a""#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_keys_reloaded() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-verify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let locales_path = dir.display().to_string();
+
+        std::fs::write(dir.join("en.yml"), "hello: Hello\n").unwrap();
+        assert!(super::keys(&locales_path, Layout::default()).contains("hello"));
+
+        // A changed file is loaded again, even within the same second
+        let file = std::fs::File::options()
+            .write(true)
+            .open(dir.join("en.yml"))
+            .unwrap();
+        std::fs::write(dir.join("en.yml"), "world: World\n").unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        let keys = super::keys(&locales_path, Layout::default());
+        assert!(keys.contains("world") && !keys.contains("hello"));
+
+        // So is a new file
+        std::fs::write(dir.join("de.yml"), "hallo: Hallo\n").unwrap();
+        assert!(super::keys(&locales_path, Layout::default()).contains("hallo"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
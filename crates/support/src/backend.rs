@@ -1,13 +1,22 @@
+use arc_swap::ArcSwap;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// I18n backend trait
 pub trait Backend: Send + Sync + 'static {
     /// Return the available locales
     fn available_locales(&self) -> Vec<String>;
-    /// Get the translation for the given locale and key
-    fn translate(&self, locale: &str, key: &str) -> Option<String>;
-    // Add translation for the given locale and key
+    /// Get the translation for the given locale and key, borrowed from the backend if possible
+    fn translate(&self, locale: &str, key: &str) -> Option<Cow<'_, str>>;
+    // Add translation for the given locale and key, not called by `t_add!` for the backend of `i18n!`
     fn add(&mut self, locale: &str, key: &str, value: &str);
+    /// Return the keys of the given locale, used by `t_map!` to find the keys of a prefix
+    ///
+    /// The default is no keys, so `t_map!` only has the keys of the other backends.
+    fn keys(&self, _locale: &str) -> Vec<String> {
+        vec![]
+    }
 }
 
 pub trait BackendExt: Backend {
@@ -38,7 +47,7 @@ where
     }
 
     #[inline]
-    fn translate(&self, locale: &str, key: &str) -> Option<String> {
+    fn translate(&self, locale: &str, key: &str) -> Option<Cow<'_, str>> {
         self.1
             .translate(locale, key)
             .or_else(|| self.0.translate(locale, key))
@@ -48,12 +57,22 @@ where
         self.0.add(locale, key, value);
         self.1.add(locale, key, value);
     }
+
+    fn keys(&self, locale: &str) -> Vec<String> {
+        let mut keys = self.0.keys(locale);
+        keys.extend(self.1.keys(locale));
+        keys.sort();
+        keys.dedup();
+        keys
+    }
 }
 
 /// Simple KeyValue storage backend
 pub struct SimpleBackend {
     /// All translations key is flatten key, like `en.hello.world`
-    translations: HashMap<String, HashMap<String, String>>,
+    ///
+    /// The translations embedded at compile time are kept as `&'static str` without copying.
+    translations: HashMap<String, HashMap<Cow<'static, str>, Cow<'static, str>>>,
 }
 
 impl SimpleBackend {
@@ -67,22 +86,20 @@ impl SimpleBackend {
     /// Add more translations for the given locale.
     ///
     /// ```ignore
-    /// let trs = HashMap::<String, String>::new();
-    /// trs.insert("hello".into(), "Hello".into());
-    /// trs.insert("foo".into(), "Foo bar".into());
+    /// let trs = HashMap::<&str, &str>::new();
+    /// trs.insert("hello", "Hello");
+    /// trs.insert("foo", "Foo bar");
     /// backend.add_translations("en", &data);
     /// ```
-    pub fn add_translations(&mut self, locale: &str, data: &HashMap<&str, &str>) {
+    pub fn add_translations(&mut self, locale: &str, data: &HashMap<&'static str, &'static str>) {
         let data = data
-            .clone()
-            .into_iter()
-            .map(|(k, v)| (k.into(), v.into()))
-            .collect::<HashMap<_, _>>();
+            .iter()
+            .map(|(k, v)| (Cow::Borrowed(*k), Cow::Borrowed(*v)));
 
         if let Some(trs) = self.translations.get_mut(locale) {
-            trs.extend(data.clone());
+            trs.extend(data);
         } else {
-            self.translations.insert(locale.into(), data.clone());
+            self.translations.insert(locale.into(), data.collect());
         }
     }
 }
@@ -98,9 +115,9 @@ impl Backend for SimpleBackend {
         locales
     }
 
-    fn translate(&self, locale: &str, key: &str) -> Option<String> {
+    fn translate(&self, locale: &str, key: &str) -> Option<Cow<'_, str>> {
         if let Some(trs) = self.translations.get(locale) {
-            return trs.get(key).cloned();
+            return trs.get(key).map(|value| Cow::Borrowed(value.as_ref()));
         }
 
         None
@@ -110,17 +127,90 @@ impl Backend for SimpleBackend {
         let locale = self.translations.entry(locale.to_string())
             .or_insert_with(HashMap::new);
 
-        locale.insert(key.to_string(), value.to_string());
+        locale.insert(key.to_string().into(), value.to_string().into());
+    }
+
+    fn keys(&self, locale: &str) -> Vec<String> {
+        self.translations
+            .get(locale)
+            .map(|trs| trs.keys().map(|key| key.to_string()).collect())
+            .unwrap_or_default()
     }
 }
 
 impl BackendExt for SimpleBackend {}
 
+/// Backend storage with lock-free reads, used by the code generated from `i18n!`.
+///
+/// The wrapped backend is read-only once created, and the translations added at runtime by `t_add!`
+/// are kept in a snapshot that is atomically swapped on each write, and take precedence over the backend.
+/// So reads never wait for a lock, and a panic in a backend can't poison the storage.
+///
+/// Since the wrapped backend is never borrowed mutably, its `Backend::add` is not called by `t_add!`,
+/// a custom backend that needs the added translations should load them itself.
+/// Each locale of the snapshot is shared, so a write only copies the added translations of its locale.
+pub struct AtomicBackend {
+    backend: Box<dyn Backend>,
+    added: ArcSwap<HashMap<String, Arc<HashMap<String, String>>>>,
+}
+
+impl AtomicBackend {
+    pub fn new(backend: Box<dyn Backend>) -> Self {
+        Self {
+            backend,
+            added: ArcSwap::from_pointee(HashMap::new()),
+        }
+    }
+
+    /// Return the available locales, including the locales added at runtime
+    pub fn available_locales(&self) -> Vec<String> {
+        let mut locales = self.backend.available_locales();
+        for locale in self.added.load().keys() {
+            if !locales.contains(locale) {
+                locales.push(locale.clone());
+            }
+        }
+        locales
+    }
+
+    /// Get the translation for the given locale and key, only the translations added at runtime are copied
+    #[inline]
+    pub fn translate(&self, locale: &str, key: &str) -> Option<Cow<'_, str>> {
+        if let Some(value) = self.added.load().get(locale).and_then(|trs| trs.get(key)) {
+            return Some(Cow::Owned(value.clone()));
+        }
+
+        self.backend.translate(locale, key)
+    }
+
+    /// Return the keys of the given locale, including the keys added at runtime
+    pub fn keys(&self, locale: &str) -> Vec<String> {
+        let mut keys = self.backend.keys(locale);
+        if let Some(added) = self.added.load().get(locale) {
+            keys.extend(added.keys().cloned());
+            keys.sort();
+            keys.dedup();
+        }
+        keys
+    }
+
+    /// Add translation for the given locale and key, concurrent reads see the previous or the new snapshot
+    pub fn add(&self, locale: &str, key: &str, value: &str) {
+        self.added.rcu(|added| {
+            let mut added = HashMap::clone(added);
+            let translations = added.entry(locale.to_string()).or_default();
+            Arc::make_mut(translations).insert(key.to_string(), value.to_string());
+            Arc::new(added)
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
     use std::collections::HashMap;
 
-    use super::SimpleBackend;
+    use super::{AtomicBackend, SimpleBackend};
     use super::{Backend, BackendExt};
 
     #[test]
@@ -136,10 +226,11 @@ mod tests {
         data_cn.insert("foo", "Foo 测试");
         backend.add_translations("zh-CN", &data_cn);
 
-        assert_eq!(backend.translate("en", "hello"), Some("Hello".to_owned()));
-        assert_eq!(backend.translate("en", "foo"), Some("Foo bar".to_owned()));
-        assert_eq!(backend.translate("zh-CN", "hello"), Some("你好".to_owned()));
-        assert_eq!(backend.translate("zh-CN", "foo"), Some("Foo 测试".to_owned()));
+        assert_eq!(backend.translate("en", "hello").as_deref(), Some("Hello"));
+        assert!(matches!(backend.translate("en", "hello"), Some(Cow::Borrowed(_))));
+        assert_eq!(backend.translate("en", "foo").as_deref(), Some("Foo bar"));
+        assert_eq!(backend.translate("zh-CN", "hello").as_deref(), Some("你好"));
+        assert_eq!(backend.translate("zh-CN", "foo").as_deref(), Some("Foo 测试"));
 
         assert_eq!(backend.available_locales(), vec!["en", "zh-CN"]);
     }
@@ -167,8 +258,8 @@ mod tests {
         backend2.add_translations("zh-CN", &data_cn2);
 
         let mut combined = backend.extend(backend2);
-        assert_eq!(combined.translate("en", "hello"), Some("Hello2".to_owned()));
-        assert_eq!(combined.translate("zh-CN", "hello"), Some("你好 2".to_owned()));
+        assert_eq!(combined.translate("en", "hello").as_deref(), Some("Hello2"));
+        assert_eq!(combined.translate("zh-CN", "hello").as_deref(), Some("你好 2"));
 
         assert_eq!(combined.available_locales(), vec!["en", "zh-CN"]);
 
@@ -191,4 +282,50 @@ mod tests {
 
         let _default = suitable.first().unwrap_or(&"en".to_owned()).to_string();  
     }
+
+    #[test]
+    fn test_atomic_backend() {
+        let mut backend = SimpleBackend::new();
+        let mut data = HashMap::<&str, &str>::new();
+        data.insert("hello", "Hello");
+        data.insert("foo", "Foo bar");
+        backend.add_translations("en", &data);
+
+        let backend = AtomicBackend::new(Box::new(backend));
+        assert_eq!(backend.translate("en", "hello").as_deref(), Some("Hello"));
+        assert_eq!(backend.translate("de", "hello"), None);
+
+        backend.add("en", "hello", "Hello2");
+        backend.add("de", "hello", "Hallo");
+        assert_eq!(backend.translate("en", "hello").as_deref(), Some("Hello2"));
+        assert_eq!(backend.translate("en", "foo").as_deref(), Some("Foo bar"));
+        assert_eq!(backend.translate("de", "hello").as_deref(), Some("Hallo"));
+        assert_eq!(backend.available_locales(), vec!["en", "de"]);
+
+        // Only the added translations of the written locale are copied
+        let added_de = backend.added.load()["de"].clone();
+        backend.add("en", "bar", "Bar");
+        assert!(std::sync::Arc::ptr_eq(&added_de, &backend.added.load()["de"]));
+        assert_eq!(backend.translate("en", "bar").as_deref(), Some("Bar"));
+
+        let backend = std::sync::Arc::new(backend);
+        let threads = (0..4)
+            .map(|i| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    backend.add("fr", &format!("key{}", i), "value");
+                    backend.translate("en", "foo").map(|value| value.into_owned())
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), Some("Foo bar".to_owned()));
+        }
+        for i in 0..4 {
+            assert_eq!(
+                backend.translate("fr", &format!("key{}", i)).as_deref(),
+                Some("value")
+            );
+        }
+    }
 }
@@ -0,0 +1,164 @@
+use crate::fluent::FluentError;
+use crate::gettext::GettextError;
+use std::fmt;
+use std::path::PathBuf;
+
+/// The format of a locale file, by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Yaml,
+    Json,
+    Toml,
+    /// A gettext `.po` file.
+    Po,
+    /// A compiled gettext `.mo` file.
+    Mo,
+    /// A Fluent `.ftl` file.
+    Fluent,
+}
+
+impl FileFormat {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "yml" | "yaml" => Some(FileFormat::Yaml),
+            "json" => Some(FileFormat::Json),
+            "toml" => Some(FileFormat::Toml),
+            "po" => Some(FileFormat::Po),
+            "mo" => Some(FileFormat::Mo),
+            "ftl" => Some(FileFormat::Fluent),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileFormat::Yaml => f.write_str("YAML"),
+            FileFormat::Json => f.write_str("JSON"),
+            FileFormat::Toml => f.write_str("TOML"),
+            FileFormat::Po => f.write_str("PO"),
+            FileFormat::Mo => f.write_str("MO"),
+            FileFormat::Fluent => f.write_str("Fluent"),
+        }
+    }
+}
+
+/// Why a locale file failed to load.
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    /// The locales path is not a valid glob pattern.
+    Pattern(Box<globwalk::GlobError>),
+    /// The file can't be listed or read.
+    Io(std::io::Error),
+    /// The file has no locale in its name, or an unsupported extension.
+    InvalidFileName,
+    Yaml(serde_yaml::Error),
+    Json(serde_json::Error),
+    Toml(Box<toml::de::Error>),
+    Po(GettextError),
+    Mo(GettextError),
+    Fluent(FluentError),
+}
+
+impl LoadErrorKind {
+    /// The 1-based line and column of a parse error, `content` is the parsed text.
+    fn location(&self, content: &str) -> Option<(usize, usize)> {
+        match self {
+            LoadErrorKind::Yaml(err) => err
+                .location()
+                .map(|location| (location.line(), location.column())),
+            LoadErrorKind::Json(err) => Some((err.line(), err.column())),
+            LoadErrorKind::Toml(err) => err.span().map(|span| {
+                let before = &content[..span.start.min(content.len())];
+                let line = before.matches('\n').count() + 1;
+                let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+                (line, column)
+            }),
+            LoadErrorKind::Po(err) => err.line.zip(err.column),
+            LoadErrorKind::Fluent(err) => Some((err.line, err.column)),
+            _ => None,
+        }
+    }
+}
+
+/// An error of loading locale files, located by the file path, and the line and column if it's a parse error.
+#[derive(Debug)]
+pub struct LoadError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub kind: LoadErrorKind,
+}
+
+impl LoadError {
+    pub(crate) fn new(path: impl Into<PathBuf>, kind: LoadErrorKind) -> Self {
+        Self {
+            path: path.into(),
+            line: None,
+            column: None,
+            kind,
+        }
+    }
+
+    /// An error of parsing `content`, located by the serde error.
+    pub(crate) fn parse(path: impl Into<PathBuf>, kind: LoadErrorKind, content: &str) -> Self {
+        let location = kind.location(content);
+        Self {
+            path: path.into(),
+            line: location.map(|(line, _)| line),
+            column: location.map(|(_, column)| column),
+            kind,
+        }
+    }
+
+    /// The format of the file failed to parse.
+    pub fn format(&self) -> Option<FileFormat> {
+        match self.kind {
+            LoadErrorKind::Yaml(_) => Some(FileFormat::Yaml),
+            LoadErrorKind::Json(_) => Some(FileFormat::Json),
+            LoadErrorKind::Toml(_) => Some(FileFormat::Toml),
+            LoadErrorKind::Po(_) => Some(FileFormat::Po),
+            LoadErrorKind::Mo(_) => Some(FileFormat::Mo),
+            LoadErrorKind::Fluent(_) => Some(FileFormat::Fluent),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, ":{}:{}", line, column)?;
+        }
+
+        match &self.kind {
+            LoadErrorKind::Pattern(err) => write!(f, ": invalid locales path, {}", err),
+            LoadErrorKind::Io(err) => write!(f, ": read file failed, {}", err),
+            LoadErrorKind::InvalidFileName => write!(f, ": invalid locale file name"),
+            LoadErrorKind::Yaml(err) => write!(f, ": invalid YAML format, {}", err),
+            LoadErrorKind::Json(err) => write!(f, ": invalid JSON format, {}", err),
+            LoadErrorKind::Toml(err) => write!(f, ": invalid TOML format, {}", err.message()),
+            LoadErrorKind::Po(err) => write!(f, ": invalid PO format, {}", err),
+            LoadErrorKind::Mo(err) => write!(f, ": invalid MO format, {}", err),
+            LoadErrorKind::Fluent(err) => write!(f, ": invalid Fluent format, {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Pattern(err) => Some(err.as_ref()),
+            LoadErrorKind::Io(err) => Some(err),
+            LoadErrorKind::InvalidFileName => None,
+            LoadErrorKind::Yaml(err) => Some(err),
+            LoadErrorKind::Json(err) => Some(err),
+            LoadErrorKind::Toml(err) => Some(err.as_ref()),
+            LoadErrorKind::Po(err) => Some(err),
+            LoadErrorKind::Mo(err) => Some(err),
+            LoadErrorKind::Fluent(err) => Some(err),
+        }
+    }
+}
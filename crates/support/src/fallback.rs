@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Limit of cached chains, for avoid unbounded growth with locales from user input.
+const MAX_CACHED_CHAINS: usize = 1024;
+
+/// Resolve the locales to lookup for a requested locale.
+///
+/// The chain is the BCP-47 truncation of the requested locale, then the fallback locales and their truncation.
+/// For example, with fallback `en-US`, the chain of `zh-Hant-TW` is `zh-Hant-TW`, `zh-Hant`, `zh`, `en-US`, `en`.
+///
+/// A locale can have its own fallback locales, which are visited right after it,
+/// e.g. with `pt-BR => [pt-PT, en]` the chain of `pt-BR` is `pt-BR`, `pt-PT`, `pt`, `en`.
+///
+/// Chains are cached per requested locale, so resolving is cheap after the first lookup.
+pub struct LocaleFallback {
+    fallback: Vec<String>,
+    locales: HashMap<String, Vec<String>>,
+    cache: RwLock<HashMap<String, Arc<[String]>>>,
+}
+
+impl LocaleFallback {
+    /// Create with the fallback locales of all locales, in order.
+    pub fn new(fallback: &[&str]) -> Self {
+        Self {
+            fallback: fallback.iter().map(|locale| locale.to_string()).collect(),
+            locales: HashMap::new(),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Add the fallback locales of the given locale, in order.
+    ///
+    /// ```ignore
+    /// fallback.add_locale_fallback("pt-BR", &["pt-PT", "en"]);
+    /// ```
+    pub fn add_locale_fallback(&mut self, locale: &str, fallback: &[&str]) {
+        self.locales.insert(
+            locale.to_string(),
+            fallback.iter().map(|locale| locale.to_string()).collect(),
+        );
+        self.cache.get_mut().unwrap().clear();
+    }
+
+    /// Get the ordered locales to lookup for `locale`.
+    pub fn chain(&self, locale: &str) -> Arc<[String]> {
+        if let Some(chain) = self.cache.read().unwrap().get(locale) {
+            return chain.clone();
+        }
+
+        let chain: Arc<[String]> = self.build_chain(locale).into();
+
+        let mut cache = self.cache.write().unwrap();
+        if cache.len() < MAX_CACHED_CHAINS {
+            cache.insert(locale.to_string(), chain.clone());
+        }
+
+        chain
+    }
+
+    fn build_chain(&self, locale: &str) -> Vec<String> {
+        let mut chain: Vec<String> = vec![];
+
+        self.visit(locale, &mut chain);
+        for fallback in &self.fallback {
+            self.visit(fallback, &mut chain);
+        }
+
+        chain
+    }
+
+    fn visit(&self, locale: &str, chain: &mut Vec<String>) {
+        for tag in truncate_locale(locale) {
+            // Skip visited locales, this also breaks the cycles of fallback locales
+            if chain.iter().any(|existing| existing == tag) {
+                continue;
+            }
+            chain.push(tag.to_string());
+
+            if let Some(fallback) = self.locales.get(tag) {
+                for locale in fallback {
+                    self.visit(locale, chain);
+                }
+            }
+        }
+    }
+}
+
+/// Truncate a BCP-47 language tag by removing subtags from the end.
+///
+/// A single-character subtag (extension or private use singleton) is removed together with the subtag after it,
+/// see [RFC 4647 section 3.4](https://www.rfc-editor.org/rfc/rfc4647#section-3.4).
+///
+/// ```ignore
+/// truncate_locale("zh-Hant-TW") // => ["zh-Hant-TW", "zh-Hant", "zh"]
+/// ```
+pub fn truncate_locale(locale: &str) -> Vec<&str> {
+    let mut tags = vec![];
+    let mut tag = locale;
+
+    while !tag.is_empty() {
+        tags.push(tag);
+
+        tag = match tag.rfind(['-', '_']) {
+            Some(pos) => &tag[..pos],
+            None => "",
+        };
+
+        // Drop the dangling singleton, e.g. `de-x` from `de-x-foo`
+        if let Some(pos) = tag.rfind(['-', '_']) {
+            if tag.len() - pos == 2 {
+                tag = &tag[..pos];
+            }
+        }
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_locale() {
+        assert_eq!(
+            truncate_locale("zh-Hant-TW"),
+            vec!["zh-Hant-TW", "zh-Hant", "zh"]
+        );
+        assert_eq!(truncate_locale("en_GB"), vec!["en_GB", "en"]);
+        assert_eq!(truncate_locale("en"), vec!["en"]);
+        assert_eq!(
+            truncate_locale("de-CH-x-phonebk"),
+            vec!["de-CH-x-phonebk", "de-CH", "de"]
+        );
+        assert!(truncate_locale("").is_empty());
+    }
+
+    #[test]
+    fn test_chain() {
+        let fallback = LocaleFallback::new(&["en-US"]);
+        assert_eq!(
+            fallback.chain("zh-Hant-TW").to_vec(),
+            vec!["zh-Hant-TW", "zh-Hant", "zh", "en-US", "en"]
+        );
+        assert_eq!(
+            fallback.chain("en-GB").to_vec(),
+            vec!["en-GB", "en", "en-US"]
+        );
+        assert_eq!(fallback.chain("en-US").to_vec(), vec!["en-US", "en"]);
+
+        let chain = fallback.chain("zh-Hant-TW");
+        assert!(Arc::ptr_eq(&chain, &fallback.chain("zh-Hant-TW")));
+
+        let fallback = LocaleFallback::new(&[]);
+        assert_eq!(fallback.chain("pt-BR").to_vec(), vec!["pt-BR", "pt"]);
+    }
+
+    #[test]
+    fn test_chain_with_locale_fallback() {
+        let mut fallback = LocaleFallback::new(&["pt-PT", "es", "en"]);
+        assert_eq!(
+            fallback.chain("de-AT").to_vec(),
+            vec!["de-AT", "de", "pt-PT", "pt", "es", "en"]
+        );
+
+        fallback.add_locale_fallback("pt-BR", &["pt-PT", "en"]);
+        fallback.add_locale_fallback("gl", &["es", "en"]);
+        assert_eq!(
+            fallback.chain("pt-BR").to_vec(),
+            vec!["pt-BR", "pt-PT", "pt", "en", "es"]
+        );
+        assert_eq!(
+            fallback.chain("gl-ES").to_vec(),
+            vec!["gl-ES", "gl", "es", "en", "pt-PT", "pt"]
+        );
+
+        // Cycles are visited once
+        fallback.add_locale_fallback("es", &["gl"]);
+        assert_eq!(
+            fallback.chain("es").to_vec(),
+            vec!["es", "gl", "en", "pt-PT", "pt"]
+        );
+    }
+}
@@ -0,0 +1,917 @@
+//! [Project Fluent](https://projectfluent.org/) resources, the `.ftl` files.
+//!
+//! A message `hello = Hello, { $name }!` is the key `hello`, its attribute `.title = Title` is
+//! `hello.title`, and a term `-brand = Firefox` is `-brand`. The patterns are kept in Fluent syntax,
+//! and evaluated by [`Message`](crate::message_format::Message) with the arguments of `t!`.
+use crate::plural::{plural_category, PluralOperands};
+use crate::MESSAGE_FORMAT_KEY;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// An error of parsing a Fluent resource, with the 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FluentError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FluentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for FluentError {}
+
+/// Parse a `.ftl` resource into a map of key => pattern, marked as the `fluent` message format.
+pub fn parse_ftl(content: &str) -> Result<Value, FluentError> {
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut map = Map::new();
+    map.insert(MESSAGE_FORMAT_KEY.into(), Value::String("fluent".into()));
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() || line.starts_with('#') {
+            i += 1;
+            continue;
+        }
+
+        let error = |column: usize, message: &str| FluentError {
+            line: i + 1,
+            column,
+            message: message.to_string(),
+        };
+
+        let id_len = entry_id_len(line);
+        if id_len == 0 {
+            return Err(error(1, "expected a message or term"));
+        }
+        let id = &line[..id_len];
+        let rest = line[id_len..].trim_start_matches([' ', '\t']);
+        let Some(value) = rest.strip_prefix('=') else {
+            return Err(error(line.len() - rest.len() + 1, "expected `=`"));
+        };
+
+        // The lines of the entry, the indented lines and the lines inside a placeable
+        let mut depth = brace_depth(value, 0);
+        let mut end = i + 1;
+        while end < lines.len() {
+            let line = lines[end];
+            if depth == 0 && !line.trim().is_empty() && !line.starts_with([' ', '\t']) {
+                break;
+            }
+            depth = brace_depth(line, depth);
+            end += 1;
+        }
+
+        let mut patterns = vec![(id.to_string(), value.to_string(), vec![])];
+        let mut depth = brace_depth(value, 0);
+        for line in &lines[i + 1..end] {
+            let trimmed = line.trim_start();
+            match trimmed.strip_prefix('.') {
+                Some(attribute) if depth == 0 => {
+                    let name_len = identifier_len(attribute);
+                    let value = attribute[name_len..]
+                        .trim_start_matches([' ', '\t'])
+                        .strip_prefix('=')
+                        .filter(|_| name_len > 0)
+                        .ok_or_else(|| error(1, "expected an attribute `.name = value`"))?;
+                    let key = format!("{}.{}", id, &attribute[..name_len]);
+                    patterns.push((key, value.to_string(), vec![]));
+                    depth = brace_depth(value, depth);
+                }
+                _ => {
+                    let last = patterns.last_mut().unwrap();
+                    last.2.push((*line, depth > 0));
+                    depth = brace_depth(line, depth);
+                }
+            }
+        }
+
+        for (n, (key, first, rest)) in patterns.into_iter().enumerate() {
+            let pattern = normalize_pattern(&first, &rest);
+            if pattern.is_empty() {
+                // A message can have attributes only
+                if n == 0 && (id.starts_with('-') || end == i + 1) {
+                    return Err(error(1, &format!("expected a value of `{}`", key)));
+                }
+                continue;
+            }
+
+            if let Err(err) = parse_pattern(&pattern) {
+                return Err(error(1, &format!("invalid pattern of `{}`, {}", key, err)));
+            }
+            map.insert(key, Value::String(pattern));
+        }
+
+        i = end;
+    }
+
+    Ok(Value::Object(map))
+}
+
+/// The length of a message or term id at the start of a line, `-` is the prefix of a term.
+fn entry_id_len(line: &str) -> usize {
+    match line.strip_prefix('-') {
+        Some(rest) => match identifier_len(rest) {
+            0 => 0,
+            len => len + 1,
+        },
+        None => identifier_len(line),
+    }
+}
+
+/// The length of an identifier `[a-zA-Z][a-zA-Z0-9_-]*` at the start of `s`.
+fn identifier_len(s: &str) -> usize {
+    if !s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return 0;
+    }
+    s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(s.len())
+}
+
+/// The depth of placeables after a line, skipping the braces in string literals.
+fn brace_depth(line: &str, mut depth: usize) -> usize {
+    let mut in_string = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' if depth > 0 => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// Join the lines of a pattern, without the common indent of the text lines, and the blank lines around.
+fn normalize_pattern(first: &str, rest: &[(&str, bool)]) -> String {
+    let indent = rest
+        .iter()
+        .filter(|(line, in_placeable)| !in_placeable && !line.trim().is_empty())
+        .map(|(line, _)| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut lines = vec![first.trim_start()];
+    for (line, in_placeable) in rest {
+        if *in_placeable || line.trim().is_empty() {
+            lines.push(line.trim_end());
+        } else {
+            lines.push(&line[indent..]);
+        }
+    }
+
+    lines.join("\n").trim().to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Part {
+    Text(String),
+    Expression(Expression),
+    Select {
+        selector: Expression,
+        variants: Vec<(VariantKey, Vec<Part>)>,
+        default: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expression {
+    String(String),
+    Number(String),
+    Variable(String),
+    Message {
+        id: String,
+        attribute: Option<String>,
+    },
+    Term {
+        id: String,
+        attribute: Option<String>,
+        args: Vec<(String, Expression)>,
+    },
+    /// `NUMBER($count)` or `DATETIME($date)`, formatted as the argument itself
+    Function(Box<Expression>),
+    Placeable(Vec<Part>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum VariantKey {
+    Identifier(String),
+    Number(f64),
+}
+
+/// Parse a pattern of a message, the error contains the byte position of the problem.
+pub(crate) fn parse_pattern(source: &str) -> Result<Vec<Part>, String> {
+    let mut parser = Parser { source, pos: 0 };
+    let parts = parser.parse_pattern(false)?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unmatched `}`"));
+    }
+    Ok(parts)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at position {}", message, self.pos)
+    }
+
+    fn skip_blank(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_blank();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected `{}`", expected)));
+        }
+        self.bump();
+        Ok(())
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, String> {
+        let len = identifier_len(&self.source[self.pos..]);
+        if len == 0 {
+            return Err(self.error("expected an identifier"));
+        }
+        let identifier = self.source[self.pos..self.pos + len].to_string();
+        self.pos += len;
+        Ok(identifier)
+    }
+
+    /// The start of a variant, or the end of a select expression, after a line break.
+    fn at_variant_end(&self) -> bool {
+        let rest = self.source[self.pos..].trim_start();
+        rest.starts_with(['[', '}']) || rest.starts_with("*[")
+    }
+
+    /// Parse the text and placeables, until the end of a variant if `in_variant`.
+    fn parse_pattern(&mut self, in_variant: bool) -> Result<Vec<Part>, String> {
+        let mut parts = vec![];
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    self.bump();
+                    parts.push(self.parse_placeable()?);
+                }
+                '}' => break,
+                '\n' if in_variant => {
+                    self.bump();
+                    if self.at_variant_end() {
+                        break;
+                    }
+                    // The continuation lines of a variant are dedented
+                    text.push('\n');
+                    while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+                        self.bump();
+                    }
+                }
+                _ => {
+                    text.push(c);
+                    self.bump();
+                }
+            }
+        }
+
+        if in_variant {
+            let len = text.trim_end().len();
+            text.truncate(len);
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(parts)
+    }
+
+    /// Parse a placeable after `{`, an expression or a select expression.
+    fn parse_placeable(&mut self) -> Result<Part, String> {
+        self.skip_blank();
+        let expression = self.parse_expression()?;
+        self.skip_blank();
+
+        if !self.source[self.pos..].starts_with("->") {
+            self.expect('}')?;
+            return Ok(Part::Expression(expression));
+        }
+        self.pos += 2;
+
+        let mut variants = vec![];
+        let mut default = None;
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                Some('}') => break,
+                Some('*') => {
+                    if default.is_some() {
+                        return Err(self.error("duplicate default variant"));
+                    }
+                    default = Some(variants.len());
+                    self.bump();
+                }
+                Some('[') => {}
+                _ => return Err(self.error("expected a variant `[key]`")),
+            }
+
+            self.expect('[')?;
+            self.skip_blank();
+            let key = match self.peek() {
+                Some(c) if c.is_ascii_digit() || c == '-' => {
+                    let number = self.parse_number()?;
+                    VariantKey::Number(number.parse().unwrap_or_default())
+                }
+                _ => VariantKey::Identifier(self.parse_identifier()?),
+            };
+            self.expect(']')?;
+            // The value can start on the next line
+            self.skip_blank();
+            if self.peek().is_none() || self.at_variant_end() {
+                return Err(self.error("expected a variant value"));
+            }
+
+            let parts = self.parse_pattern(true)?;
+            variants.push((key, parts));
+        }
+
+        let Some(default) = default else {
+            return Err(self.error("expected a default variant `*[key]`"));
+        };
+        self.expect('}')?;
+
+        Ok(Part::Select {
+            selector: expression,
+            variants,
+            default,
+        })
+    }
+
+    fn parse_number(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.bump();
+        }
+        let digits = |parser: &mut Self| {
+            let start = parser.pos;
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.bump();
+            }
+            parser.pos > start
+        };
+        if !digits(self) {
+            return Err(self.error("expected a number"));
+        }
+        if self.peek() == Some('.') {
+            self.bump();
+            if !digits(self) {
+                return Err(self.error("expected a number"));
+            }
+        }
+        Ok(self.source[start..self.pos].to_string())
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.bump();
+        let mut string = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.bump() {
+                    Some(c @ ('"' | '\\')) => string.push(c),
+                    Some(c @ ('u' | 'U')) => {
+                        let len = if c == 'u' { 4 } else { 6 };
+                        let hex = self
+                            .source
+                            .get(self.pos..self.pos + len)
+                            .unwrap_or_default();
+                        let c = u32::from_str_radix(hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("invalid unicode escape"))?;
+                        self.pos += len;
+                        string.push(c);
+                    }
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn parse_attribute(&mut self) -> Result<Option<String>, String> {
+        if self.peek() != Some('.') {
+            return Ok(None);
+        }
+        self.bump();
+        Ok(Some(self.parse_identifier()?))
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        match self.peek() {
+            Some('"') => Ok(Expression::String(self.parse_string()?)),
+            Some('$') => {
+                self.bump();
+                Ok(Expression::Variable(self.parse_identifier()?))
+            }
+            Some('{') => {
+                self.bump();
+                match self.parse_placeable()? {
+                    Part::Expression(expression) => Ok(expression),
+                    part => Ok(Expression::Placeable(vec![part])),
+                }
+            }
+            Some(c) if c.is_ascii_digit() => Ok(Expression::Number(self.parse_number()?)),
+            Some('-') => {
+                if self.source[self.pos + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+                    return Ok(Expression::Number(self.parse_number()?));
+                }
+                self.bump();
+                let id = format!("-{}", self.parse_identifier()?);
+                let attribute = self.parse_attribute()?;
+                let args = self.parse_call_args()?;
+                Ok(Expression::Term {
+                    id,
+                    attribute,
+                    args,
+                })
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.pos;
+                let id = self.parse_identifier()?;
+                if self.peek() != Some('(') {
+                    let attribute = self.parse_attribute()?;
+                    return Ok(Expression::Message { id, attribute });
+                }
+
+                if !matches!(id.as_str(), "NUMBER" | "DATETIME") {
+                    self.pos = start;
+                    return Err(self.error(&format!("unknown function `{}`", id)));
+                }
+                let mut args = self.parse_call_args()?.into_iter();
+                match args.next() {
+                    Some((name, expression)) if name.is_empty() => {
+                        Ok(Expression::Function(Box::new(expression)))
+                    }
+                    _ => Err(self.error(&format!("expected an argument of `{}`", id))),
+                }
+            }
+            _ => Err(self.error("expected an expression")),
+        }
+    }
+
+    /// Parse the arguments `(positional, name: "value")` of a call, positional arguments have an empty name.
+    fn parse_call_args(&mut self) -> Result<Vec<(String, Expression)>, String> {
+        self.skip_blank();
+        if self.peek() != Some('(') {
+            return Ok(vec![]);
+        }
+        self.bump();
+
+        let mut args = vec![];
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(')') {
+                self.bump();
+                return Ok(args);
+            }
+
+            let start = self.pos;
+            let len = identifier_len(&self.source[self.pos..]);
+            self.pos += len;
+            self.skip_blank();
+            if len > 0 && self.peek() == Some(':') {
+                let name = self.source[start..start + len].to_string();
+                self.bump();
+                self.skip_blank();
+                let value = match self.peek() {
+                    Some('"') => Expression::String(self.parse_string()?),
+                    _ => Expression::Number(self.parse_number()?),
+                };
+                args.push((name, value));
+            } else {
+                self.pos = start;
+                args.push((String::new(), self.parse_expression()?));
+            }
+
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(')') => {}
+                _ => return Err(self.error("expected `,` or `)`")),
+            }
+        }
+    }
+}
+
+/// Resolve a message or term reference by its key, with the arguments it's formatted with.
+pub type Resolve<'a> = &'a dyn Fn(&str, &[(&str, String)]) -> Option<String>;
+
+thread_local! {
+    /// The keys of the references being resolved, for stop a cyclic reference.
+    static RESOLVING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) fn format_parts(
+    parts: &[Part],
+    locale: &str,
+    args: &[(&str, String)],
+    resolve: Resolve,
+    output: &mut String,
+) {
+    for part in parts {
+        match part {
+            Part::Text(text) => output.push_str(text),
+            Part::Expression(expression) => {
+                output.push_str(&evaluate(expression, locale, args, resolve))
+            }
+            Part::Select {
+                selector,
+                variants,
+                default,
+            } => {
+                let value = evaluate(selector, locale, args, resolve);
+                let number = value.parse::<f64>().ok();
+                let category = value
+                    .parse::<PluralOperands>()
+                    .ok()
+                    .map(|operands| plural_category(locale, &operands).as_str());
+
+                let variant = variants
+                    .iter()
+                    .find(|(key, _)| match key {
+                        VariantKey::Number(key) => number == Some(*key),
+                        VariantKey::Identifier(key) => {
+                            *key == value || category == Some(key.as_str())
+                        }
+                    })
+                    .unwrap_or(&variants[*default]);
+
+                format_parts(&variant.1, locale, args, resolve, output);
+            }
+        }
+    }
+}
+
+fn evaluate(
+    expression: &Expression,
+    locale: &str,
+    args: &[(&str, String)],
+    resolve: Resolve,
+) -> String {
+    match expression {
+        Expression::String(string) | Expression::Number(string) => string.clone(),
+        Expression::Variable(name) => args
+            .iter()
+            .find(|(arg, _)| arg == name)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| format!("{{${}}}", name)),
+        Expression::Function(expression) => evaluate(expression, locale, args, resolve),
+        Expression::Placeable(parts) => {
+            let mut output = String::new();
+            format_parts(parts, locale, args, resolve, &mut output);
+            output
+        }
+        Expression::Message { id, attribute } => {
+            resolve_reference(&reference_key(id, attribute), args, resolve)
+        }
+        Expression::Term {
+            id,
+            attribute,
+            args: term_args,
+        } => {
+            // A term only has the arguments of the reference
+            let term_args = term_args
+                .iter()
+                .map(|(name, value)| (name.as_str(), evaluate(value, locale, args, resolve)))
+                .collect::<Vec<_>>();
+            resolve_reference(&reference_key(id, attribute), &term_args, resolve)
+        }
+    }
+}
+
+fn reference_key(id: &str, attribute: &Option<String>) -> String {
+    match attribute {
+        Some(attribute) => format!("{}.{}", id, attribute),
+        None => id.to_string(),
+    }
+}
+
+/// Resolve a reference, a key referenced again while it's being resolved is kept as `{key}`.
+fn resolve_reference(key: &str, args: &[(&str, String)], resolve: Resolve) -> String {
+    if RESOLVING.with(|keys| keys.borrow().iter().any(|resolving| resolving == key)) {
+        return format!("{{{}}}", key);
+    }
+
+    RESOLVING.with(|keys| keys.borrow_mut().push(key.to_string()));
+    let value = resolve(key, args);
+    RESOLVING.with(|keys| keys.borrow_mut().pop());
+
+    value.unwrap_or_else(|| format!("{{{}}}", key))
+}
+
+/// Collect the keys of the messages and terms referenced by the parts, like `-brand.gender` of `{ -brand.gender }`.
+pub(crate) fn references(parts: &[Part], keys: &mut Vec<String>) {
+    for part in parts {
+        match part {
+            Part::Text(_) => {}
+            Part::Expression(expression) => expression_references(expression, keys),
+            Part::Select {
+                selector, variants, ..
+            } => {
+                expression_references(selector, keys);
+                for (_, parts) in variants {
+                    references(parts, keys);
+                }
+            }
+        }
+    }
+}
+
+fn expression_references(expression: &Expression, keys: &mut Vec<String>) {
+    match expression {
+        Expression::String(_) | Expression::Number(_) | Expression::Variable(_) => {}
+        Expression::Message { id, attribute } => keys.push(reference_key(id, attribute)),
+        Expression::Term {
+            id,
+            attribute,
+            args,
+        } => {
+            keys.push(reference_key(id, attribute));
+            for (_, arg) in args {
+                expression_references(arg, keys);
+            }
+        }
+        Expression::Function(expression) => expression_references(expression, keys),
+        Expression::Placeable(parts) => references(parts, keys),
+    }
+}
+
+/// The keys of the messages that reference themselves, directly or through the other messages, by the references
+/// of each message.
+pub fn cyclic_keys(references: &HashMap<String, Vec<String>>) -> Vec<&str> {
+    let mut keys = references
+        .keys()
+        .filter(|&key| {
+            let mut visited = HashSet::new();
+            let mut pending = vec![key];
+            while let Some(next) = pending.pop() {
+                for reference in references.get(next).into_iter().flatten() {
+                    if reference == key {
+                        return true;
+                    }
+                    if visited.insert(reference) {
+                        pending.push(reference);
+                    }
+                }
+            }
+            false
+        })
+        .map(|key| key.as_str())
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_format::Message;
+
+    #[test]
+    fn test_parse_ftl() {
+        let content = r#"
+# A comment
+hello = Hello, { $name }!
+-brand = Firefox
+    .gender = masculine
+
+login =
+    .placeholder = Email
+    .title = Log in
+
+multiline =
+    First line
+      indented
+    Last line
+
+emails = { $count ->
+    [0] No emails
+    [one] One email
+   *[other] { $count } emails
+}
+"#;
+        let value = parse_ftl(content).unwrap();
+        assert_eq!(value[MESSAGE_FORMAT_KEY], "fluent");
+        assert_eq!(value["hello"], "Hello, { $name }!");
+        assert_eq!(value["-brand"], "Firefox");
+        assert_eq!(value["-brand.gender"], "masculine");
+        assert!(value.get("login").is_none());
+        assert_eq!(value["login.placeholder"], "Email");
+        assert_eq!(value["login.title"], "Log in");
+        assert_eq!(value["multiline"], "First line\n  indented\nLast line");
+        assert_eq!(
+            value["emails"],
+            "{ $count ->\n    [0] No emails\n    [one] One email\n   *[other] { $count } emails\n}"
+        );
+    }
+
+    #[test]
+    fn test_parse_ftl_errors() {
+        let error = |content: &str| parse_ftl(content).unwrap_err();
+
+        let err = error("hello = Hello\n  world\n= oops");
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.message, "expected a message or term");
+
+        let err = error("\nhello Hello");
+        assert_eq!((err.line, err.column), (2, 7));
+        assert_eq!(err.message, "expected `=`");
+
+        let err = error("hello = { $count ->\n  [one] One\n}");
+        assert_eq!(err.line, 1);
+        assert!(err
+            .message
+            .starts_with("invalid pattern of `hello`, expected a default variant"));
+
+        let err = error("-brand =\n    .gender = masculine");
+        assert_eq!(err.message, "expected a value of `-brand`");
+    }
+
+    fn resolve(key: &str, args: &[(&str, String)]) -> Option<String> {
+        let source = match key {
+            "-brand" => "{ $case ->\n [genitive] Firefoxa\n *[nominative] Firefox\n}",
+            "-brand.gender" => "masculine",
+            "title" => "Welcome, { $name }",
+            "cycle" => "{ cycle }",
+            "twice" => "{ twice }{ twice }",
+            "ping" => "{ pong }{ pong }",
+            "pong" => "{ ping }{ ping }",
+            _ => return None,
+        };
+        let message = Message::parse_fluent(source).unwrap();
+        Some(message.format_with("en", args, &resolve).into_owned())
+    }
+
+    fn format(source: &str, locale: &str, args: &[(&str, &str)]) -> String {
+        let args = args
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect::<Vec<_>>();
+        Message::parse_fluent(source)
+            .unwrap()
+            .format_with(locale, &args, &resolve)
+            .into_owned()
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            format("Hello, { $name }!", "en", &[("name", "Ann")]),
+            "Hello, Ann!"
+        );
+        assert_eq!(format("Hello, { $name }!", "en", &[]), "Hello, {$name}!");
+        assert_eq!(
+            format("{ \"{\" } and { 42 } { \"\\u0041\" }", "en", &[]),
+            "{ and 42 A"
+        );
+        assert_eq!(
+            format(
+                "Total { NUMBER($n, minimumFractionDigits: 2) }",
+                "en",
+                &[("n", "3")]
+            ),
+            "Total 3"
+        );
+        assert_eq!(format("{ { $name } }", "en", &[("name", "x")]), "x");
+    }
+
+    #[test]
+    fn test_format_select() {
+        let source =
+            "{ $count ->\n    [0] No emails\n    [one] One email\n   *[other] { $count } emails\n}";
+        assert_eq!(format(source, "en", &[("count", "0")]), "No emails");
+        assert_eq!(format(source, "en", &[("count", "1")]), "One email");
+        assert_eq!(format(source, "en", &[("count", "5")]), "5 emails");
+        assert_eq!(format(source, "en", &[]), "{$count} emails");
+        assert_eq!(format(source, "ja", &[("count", "1")]), "1 emails");
+
+        let source = "{ $gender ->\n  [female] Her\n  [male] His\n *[other] Their\n} profile";
+        assert_eq!(format(source, "en", &[("gender", "female")]), "Her profile");
+        assert_eq!(
+            format(source, "en", &[("gender", "unknown")]),
+            "Their profile"
+        );
+
+        let source = "{ $n ->\n *[other]\n    Many\n    lines\n}";
+        assert_eq!(format(source, "en", &[("n", "2")]), "Many\nlines");
+    }
+
+    #[test]
+    fn test_format_references() {
+        assert_eq!(format("About { -brand }", "en", &[]), "About Firefox");
+        assert_eq!(
+            format("{ -brand(case: \"genitive\") }", "en", &[]),
+            "Firefoxa"
+        );
+        assert_eq!(
+            format(
+                "{ -brand.gender ->\n [masculine] He\n *[other] It\n}",
+                "en",
+                &[]
+            ),
+            "He"
+        );
+        assert_eq!(
+            format("{ title }!", "en", &[("name", "Ann")]),
+            "Welcome, Ann!"
+        );
+        assert_eq!(
+            format("{ missing } { -missing }", "en", &[]),
+            "{missing} {-missing}"
+        );
+        assert!(format("{ cycle }", "en", &[]).contains("{cycle}"));
+        // A cycle is stopped at the first repeated key, not after an exponential number of references
+        assert_eq!(format("{ twice }", "en", &[]), "{twice}{twice}");
+        assert_eq!(format("{ ping }", "en", &[]), "{ping}{ping}{ping}{ping}");
+    }
+
+    #[test]
+    fn test_cyclic_keys() {
+        let message = Message::parse_fluent(
+            "{ hello } { -brand(case: \"genitive\") } { $n ->\n [one] { login.placeholder }\n *[other] { title }\n}",
+        )
+        .unwrap();
+        assert_eq!(
+            message.references(),
+            vec!["hello", "-brand", "login.placeholder", "title"]
+        );
+
+        let references = HashMap::from(
+            [
+                ("twice", vec!["twice", "twice"]),
+                ("ping", vec!["pong"]),
+                ("pong", vec!["-brand", "ping"]),
+                ("-brand", vec![]),
+                ("hello", vec!["ping", "-brand"]),
+            ]
+            .map(|(key, references)| {
+                (
+                    key.to_string(),
+                    references.into_iter().map(String::from).collect(),
+                )
+            }),
+        );
+        assert_eq!(cyclic_keys(&references), vec!["ping", "pong", "twice"]);
+    }
+
+    #[test]
+    fn test_parse_pattern_error() {
+        let error = |source: &str| Message::parse_fluent(source).unwrap_err();
+
+        assert_eq!(error("Hello { $name"), "expected `}` at position 13");
+        assert_eq!(error("Hello }"), "unmatched `}` at position 6");
+        assert_eq!(error("{ }"), "expected an expression at position 2");
+        assert_eq!(error("{ FOO($x) }"), "unknown function `FOO` at position 2");
+        assert_eq!(
+            error("{ $n ->\n [one] One\n *[other] Other\n *[many] Many\n}"),
+            "duplicate default variant at position 36"
+        );
+        assert_eq!(error("{ \"abc }"), "unterminated string at position 8");
+        assert_eq!(
+            error("{ $n ->\n *[other]\n}"),
+            "expected a variant value at position 18"
+        );
+    }
+}
@@ -0,0 +1,733 @@
+//! GNU gettext catalogs, the `.po` text files and the compiled `.mo` files.
+//!
+//! An entry is keyed by its `msgid`, prefixed by the `msgctxt` if any, e.g. `messages.hello` for
+//! `msgctxt "messages"` and `msgid "hello"`. The header, the fuzzy and the untranslated entries
+//! are skipped. The `msgstr[N]` forms of a plural entry are mapped to the plural sub-keys by the
+//! `Plural-Forms` header and the CLDR rules of the locale, or by [`plural_keys`] without the header.
+use crate::plural::{plural_category, PluralCategory, PluralOperands};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// An error of parsing a gettext catalog, with the 1-based line and column in a `.po` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GettextError {
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl GettextError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    fn at(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line: Some(line),
+            column: Some(column),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for GettextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for GettextError {}
+
+/// The plural sub-keys of the `msgstr[N]` forms by the number of forms, in the CLDR order,
+/// for the catalogs without a `Plural-Forms` header.
+///
+/// The last form is always `other`, a language with 3 forms like `ru` or `pl` has `one`, `few` and `many`,
+/// and the `many` form is also used for `other`.
+pub fn plural_keys(forms: usize) -> &'static [&'static str] {
+    match forms {
+        0 => &[],
+        1 => &["other"],
+        2 => &["one", "other"],
+        3 => &["one", "few", "many"],
+        4 => &["one", "two", "few", "other"],
+        5 => &["one", "two", "few", "many", "other"],
+        _ => &["zero", "one", "two", "few", "many", "other"],
+    }
+}
+
+/// The CLDR plural categories of the `msgstr[N]` forms of a catalog, by its `Plural-Forms` header.
+///
+/// Each category is the form of its smallest integer, e.g. `zero` of `0` and `one` of `1`,
+/// and the last form is `other` if the locale has no integer of it, like the fractions of `ru`.
+fn plural_forms(
+    header: &str,
+    locale: &str,
+) -> Result<Option<Vec<Vec<PluralCategory>>>, GettextError> {
+    let Some(value) = header
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Plural-Forms:"))
+    else {
+        return Ok(None);
+    };
+    let invalid = || GettextError::new(format!("invalid Plural-Forms header `{}`", value.trim()));
+
+    let field = |name: &str| {
+        value.split(';').find_map(|part| {
+            let (key, value) = part.split_once('=')?;
+            (key.trim() == name).then(|| value.trim())
+        })
+    };
+    let count = field("nplurals")
+        .and_then(|count| count.parse::<usize>().ok())
+        .filter(|count| *count > 0)
+        .ok_or_else(invalid)?;
+    let expr = field("plural")
+        .and_then(PluralExpr::parse)
+        .ok_or_else(invalid)?;
+
+    let mut forms = vec![vec![]; count];
+    let mut seen = vec![];
+    for n in 0..=1000u64 {
+        let category = plural_category(locale, &PluralOperands::from(n));
+        if seen.contains(&category) {
+            continue;
+        }
+        seen.push(category);
+        let index = usize::try_from(expr.eval(n)).map_err(|_| invalid())?;
+        forms.get_mut(index).ok_or_else(invalid)?.push(category);
+    }
+    if !seen.contains(&PluralCategory::Other) {
+        forms[count - 1].push(PluralCategory::Other);
+    }
+
+    Ok(Some(forms))
+}
+
+/// The C expression of the `plural=` of a `Plural-Forms` header, e.g. `(n != 1)`.
+enum PluralExpr {
+    N,
+    Number(u64),
+    Not(Box<PluralExpr>),
+    Binary(Box<PluralExpr>, &'static str, Box<PluralExpr>),
+    Condition(Box<PluralExpr>, Box<PluralExpr>, Box<PluralExpr>),
+}
+
+/// The binary operators of the plural expressions, from the lowest precedence.
+const PLURAL_OPERATORS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["==", "!="],
+    &["<=", ">=", "<", ">"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl PluralExpr {
+    fn parse(source: &str) -> Option<Self> {
+        let mut tokens = vec![];
+        let mut rest = source.trim_start();
+        while !rest.is_empty() {
+            let len = if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                rest.find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len())
+            } else if ["==", "!=", "<=", ">=", "&&", "||"]
+                .iter()
+                .any(|op| rest.starts_with(op))
+            {
+                2
+            } else if rest.starts_with(|c: char| "n!<>?:()+-*/%".contains(c)) {
+                1
+            } else {
+                return None;
+            };
+            tokens.push(&rest[..len]);
+            rest = rest[len..].trim_start();
+        }
+
+        let mut tokens = tokens.into_iter().peekable();
+        let expr = Self::condition(&mut tokens)?;
+        tokens.next().is_none().then_some(expr)
+    }
+
+    fn condition<'a>(
+        tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>,
+    ) -> Option<Self> {
+        let condition = Self::binary(tokens, 0)?;
+        if tokens.next_if_eq(&"?").is_none() {
+            return Some(condition);
+        }
+        let then = Self::condition(tokens)?;
+        tokens.next_if_eq(&":")?;
+        let otherwise = Self::condition(tokens)?;
+        Some(Self::Condition(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn binary<'a>(
+        tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>,
+        level: usize,
+    ) -> Option<Self> {
+        let Some(operators) = PLURAL_OPERATORS.get(level) else {
+            return Self::unary(tokens);
+        };
+
+        let mut left = Self::binary(tokens, level + 1)?;
+        while let Some(op) = operators.iter().find(|op| tokens.peek() == Some(op)) {
+            tokens.next();
+            let right = Self::binary(tokens, level + 1)?;
+            left = Self::Binary(Box::new(left), op, Box::new(right));
+        }
+        Some(left)
+    }
+
+    fn unary<'a>(tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>) -> Option<Self> {
+        match tokens.next()? {
+            "!" => Some(Self::Not(Box::new(Self::unary(tokens)?))),
+            "n" => Some(Self::N),
+            "(" => {
+                let expr = Self::condition(tokens)?;
+                tokens.next_if_eq(&")")?;
+                Some(expr)
+            }
+            number => number.parse().ok().map(Self::Number),
+        }
+    }
+
+    fn eval(&self, n: u64) -> u64 {
+        match self {
+            Self::N => n,
+            Self::Number(number) => *number,
+            Self::Not(expr) => (expr.eval(n) == 0) as u64,
+            Self::Condition(condition, then, otherwise) => {
+                if condition.eval(n) != 0 {
+                    then.eval(n)
+                } else {
+                    otherwise.eval(n)
+                }
+            }
+            Self::Binary(left, op, right) => {
+                let (a, b) = (left.eval(n), right.eval(n));
+                match *op {
+                    "||" => (a != 0 || b != 0) as u64,
+                    "&&" => (a != 0 && b != 0) as u64,
+                    "==" => (a == b) as u64,
+                    "!=" => (a != b) as u64,
+                    "<=" => (a <= b) as u64,
+                    ">=" => (a >= b) as u64,
+                    "<" => (a < b) as u64,
+                    ">" => (a > b) as u64,
+                    "+" => a.wrapping_add(b),
+                    "-" => a.wrapping_sub(b),
+                    "*" => a.wrapping_mul(b),
+                    "/" => a.checked_div(b).unwrap_or_default(),
+                    _ => a.checked_rem(b).unwrap_or_default(),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    context: Option<String>,
+    id: Option<String>,
+    plural: bool,
+    strs: Vec<String>,
+    fuzzy: bool,
+}
+
+/// The translations of a catalog, and the categories of the plural forms by its header.
+struct Catalog<'a> {
+    locale: &'a str,
+    map: Map<String, Value>,
+    plural_forms: Option<Vec<Vec<PluralCategory>>>,
+}
+
+impl<'a> Catalog<'a> {
+    fn new(locale: &'a str) -> Self {
+        Self {
+            locale,
+            map: Map::new(),
+            plural_forms: None,
+        }
+    }
+
+    fn insert(&mut self, entry: Entry) -> Result<(), GettextError> {
+        let Some(id) = entry.id else {
+            return Ok(());
+        };
+        if id.is_empty() && !entry.plural {
+            if let Some(header) = entry.strs.first() {
+                self.plural_forms = plural_forms(header, self.locale)?;
+            }
+            return Ok(());
+        }
+        // An untranslated entry
+        if id.is_empty() || entry.fuzzy || entry.strs.iter().all(|s| s.is_empty()) {
+            return Ok(());
+        }
+
+        let key = match entry.context {
+            Some(context) => format!("{}.{}", context, id),
+            None => id,
+        };
+
+        if !entry.plural {
+            let value = entry.strs.into_iter().next().unwrap_or_default();
+            self.map.insert(key, Value::String(value));
+            return Ok(());
+        }
+
+        let mut forms = Map::new();
+        match &self.plural_forms {
+            Some(plural_forms) => {
+                for (categories, value) in plural_forms.iter().zip(&entry.strs) {
+                    for category in categories {
+                        forms.insert(category.as_str().to_string(), Value::String(value.clone()));
+                    }
+                }
+            }
+            None => {
+                let keys = plural_keys(entry.strs.len());
+                for (key, value) in keys.iter().zip(&entry.strs) {
+                    forms.insert(key.to_string(), Value::String(value.clone()));
+                }
+                if keys.len() == 3 {
+                    forms.insert("other".into(), Value::String(entry.strs[2].clone()));
+                }
+            }
+        }
+        self.map.insert(key, Value::Object(forms));
+        Ok(())
+    }
+}
+
+/// The field of the current line, for append the continuation strings.
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Str(usize),
+}
+
+/// Parse a `.po` file of the locale into a map of key => translation, or key => plural forms.
+pub fn parse_po(content: &str, locale: &str) -> Result<Value, GettextError> {
+    let mut catalog = Catalog::new(locale);
+    let mut entry = Entry::default();
+    let mut field = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let line_no = index + 1;
+        let trimmed = line.trim_start();
+        let column = line.len() - trimmed.len() + 1;
+        let trimmed = trimmed.trim_end();
+
+        if trimmed.is_empty() || trimmed.starts_with("#~") {
+            continue;
+        }
+
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            if !entry.strs.is_empty() {
+                catalog.insert(std::mem::take(&mut entry))?;
+            }
+            if let Some(flags) = comment.strip_prefix(',') {
+                entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            field = None;
+            continue;
+        }
+
+        if trimmed.starts_with('"') {
+            let Some(field) = field else {
+                return Err(GettextError::at(line_no, column, "unexpected string"));
+            };
+            let value = unquote(trimmed, line_no, column)?;
+            match field {
+                Field::Context => entry
+                    .context
+                    .get_or_insert_with(String::new)
+                    .push_str(&value),
+                Field::Id => entry.id.get_or_insert_with(String::new).push_str(&value),
+                Field::IdPlural => {}
+                Field::Str(index) => entry.strs[index].push_str(&value),
+            }
+            continue;
+        }
+
+        let (keyword, rest) = trimmed.split_once([' ', '\t']).unwrap_or((trimmed, ""));
+        let rest = rest.trim_start();
+        let rest_column = column + trimmed.len() - rest.len();
+        let value = unquote(rest, line_no, rest_column)?;
+
+        if matches!(keyword, "msgctxt" | "msgid") && !entry.strs.is_empty() {
+            catalog.insert(std::mem::take(&mut entry))?;
+        }
+
+        field = Some(match keyword {
+            "msgctxt" => {
+                entry.context = Some(value);
+                Field::Context
+            }
+            "msgid" => {
+                entry.id = Some(value);
+                Field::Id
+            }
+            "msgid_plural" => {
+                entry.plural = true;
+                Field::IdPlural
+            }
+            "msgstr" => {
+                entry.strs.push(value);
+                Field::Str(entry.strs.len() - 1)
+            }
+            _ => {
+                let index = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|index| index.strip_suffix(']'))
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .ok_or_else(|| {
+                        GettextError::at(line_no, column, format!("unknown keyword `{}`", keyword))
+                    })?;
+                if index != entry.strs.len() {
+                    return Err(GettextError::at(
+                        line_no,
+                        column,
+                        format!("expected `msgstr[{}]`", entry.strs.len()),
+                    ));
+                }
+                entry.strs.push(value);
+                Field::Str(index)
+            }
+        });
+    }
+
+    catalog.insert(entry)?;
+    Ok(Value::Object(catalog.map))
+}
+
+/// Parse a quoted and escaped string of a `.po` file.
+fn unquote(s: &str, line: usize, column: usize) -> Result<String, GettextError> {
+    let inner = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| GettextError::at(line, column, "expected a quoted string"))?;
+
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(c @ ('"' | '\\')) => result.push(c),
+            _ => return Err(GettextError::at(line, column, "invalid escape sequence")),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Escape and quote a string for a `.po` file.
+pub fn quote(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            _ => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Parse a compiled `.mo` file of the locale into a map of key => translation, or key => plural forms.
+pub fn parse_mo(bytes: &[u8], locale: &str) -> Result<Value, GettextError> {
+    let read_u32 = |offset: usize, big_endian: bool| -> Result<usize, GettextError> {
+        let bytes: [u8; 4] = bytes
+            .get(offset..offset + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| GettextError::new("unexpected end of file"))?;
+        Ok(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        } as usize)
+    };
+
+    let big_endian = match read_u32(0, false)? {
+        0x950412de => false,
+        0xde120495 => true,
+        _ => return Err(GettextError::new("invalid magic number")),
+    };
+    if read_u32(4, big_endian)? >> 16 > 1 {
+        return Err(GettextError::new("unsupported revision"));
+    }
+
+    let count = read_u32(8, big_endian)?;
+    let originals = read_u32(12, big_endian)?;
+    let translations = read_u32(16, big_endian)?;
+
+    let read_string = |table: usize, index: usize| -> Result<&str, GettextError> {
+        let len = read_u32(table + index * 8, big_endian)?;
+        let offset = read_u32(table + index * 8 + 4, big_endian)?;
+        let bytes = bytes
+            .get(offset..offset + len)
+            .ok_or_else(|| GettextError::new("unexpected end of file"))?;
+        std::str::from_utf8(bytes).map_err(|_| GettextError::new("invalid UTF-8 string"))
+    };
+
+    // The header is the first entry, since the entries are sorted by the original strings
+    let mut catalog = Catalog::new(locale);
+    for index in 0..count {
+        let original = read_string(originals, index)?;
+        let translation = read_string(translations, index)?;
+
+        let (context, original) = match original.split_once('\u{4}') {
+            Some((context, original)) => (Some(context.to_string()), original),
+            None => (None, original),
+        };
+        let mut ids = original.split('\0');
+
+        catalog.insert(Entry {
+            context,
+            id: ids.next().map(|id| id.to_string()),
+            plural: ids.next().is_some(),
+            strs: translation.split('\0').map(|s| s.to_string()).collect(),
+            fuzzy: false,
+        })?;
+    }
+
+    Ok(Value::Object(catalog.map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_po() {
+        let content = r#"
+# Translations of the app
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=(n != 1);\n"
+
+#: src/main.rs:10
+msgid "hello"
+msgstr "Hello world"
+
+msgctxt "messages"
+msgid "hello"
+msgstr ""
+"Hello, "
+"%{name}"
+
+#, fuzzy
+msgid "fuzzy"
+msgstr "Fuzzy"
+
+msgid "untranslated"
+msgstr ""
+
+msgid "inbox"
+msgid_plural "inbox"
+msgstr[0] "%{count} message"
+msgstr[1] "%{count} messages"
+
+msgid "escaped"
+msgstr "Say \"hi\"\n\ttab \\ backslash"
+
+#~ msgid "obsolete"
+#~ msgstr "Obsolete"
+"#;
+        let value = parse_po(content, "en").unwrap();
+        assert_eq!(value["hello"], "Hello world");
+        assert_eq!(value["messages.hello"], "Hello, %{name}");
+        assert_eq!(value["inbox"]["one"], "%{count} message");
+        assert_eq!(value["inbox"]["other"], "%{count} messages");
+        assert_eq!(value["escaped"], "Say \"hi\"\n\ttab \\ backslash");
+        assert!(value.get("").is_none());
+        assert!(value.get("fuzzy").is_none());
+        assert!(value.get("untranslated").is_none());
+        assert!(value.get("obsolete").is_none());
+    }
+
+    #[test]
+    fn test_parse_po_plural_forms() {
+        let content = r#"
+msgid "apple"
+msgid_plural "apples"
+msgstr[0] "яблоко"
+msgstr[1] "яблока"
+msgstr[2] "яблок"
+"#;
+        let value = parse_po(content, "ru").unwrap();
+        assert_eq!(value["apple"]["one"], "яблоко");
+        assert_eq!(value["apple"]["few"], "яблока");
+        assert_eq!(value["apple"]["many"], "яблок");
+        assert_eq!(value["apple"]["other"], "яблок");
+    }
+
+    #[test]
+    fn test_parse_po_plural_forms_header() {
+        // Welsh with 4 forms, the third form is used for 0, 3, 6 and the others but 8 and 11
+        let content = r#"
+msgid ""
+msgstr ""
+"Plural-Forms: nplurals=4; plural=(n==1) ? 0 : (n==2) ? 1 : (n != 8 && n != 11) ? 2 : 3;\n"
+
+msgid "dog"
+msgid_plural "dogs"
+msgstr[0] "ci"
+msgstr[1] "gi"
+msgstr[2] "chi"
+msgstr[3] "ci8"
+"#;
+        let value = parse_po(content, "cy").unwrap();
+        assert_eq!(value["dog"]["zero"], "chi");
+        assert_eq!(value["dog"]["one"], "ci");
+        assert_eq!(value["dog"]["two"], "gi");
+        assert_eq!(value["dog"]["few"], "chi");
+        assert_eq!(value["dog"]["many"], "chi");
+        assert_eq!(value["dog"]["other"], "chi");
+
+        // Czech, the `many` category is only for the fractions
+        let content = r#"
+msgid ""
+msgstr "Plural-Forms: nplurals=3; plural=(n==1) ? 0 : (n>=2 && n<=4) ? 1 : 2;\n"
+
+msgid "file"
+msgid_plural "files"
+msgstr[0] "soubor"
+msgstr[1] "soubory"
+msgstr[2] "souborů"
+"#;
+        let value = parse_po(content, "cs").unwrap();
+        assert_eq!(value["file"]["one"], "soubor");
+        assert_eq!(value["file"]["few"], "soubory");
+        assert_eq!(value["file"]["other"], "souborů");
+        assert!(value["file"].get("many").is_none());
+
+        for header in [
+            "nplurals=2; plural=n +;",
+            "nplurals=2; plural=n + 5;",
+            "nplurals=0; plural=0;",
+        ] {
+            let content = format!("msgid \"\"\nmsgstr \"Plural-Forms: {}\\n\"\n", header);
+            let err = parse_po(&content, "en").unwrap_err();
+            assert!(
+                err.message.starts_with("invalid Plural-Forms header"),
+                "{}",
+                header
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_po_errors() {
+        let err = parse_po("msgid \"hello\"\nmsgstr \"Hello", "en").unwrap_err();
+        assert_eq!((err.line, err.column), (Some(2), Some(8)));
+
+        let err = parse_po("msgid \"hello\"\n  msgfoo \"Hello\"", "en").unwrap_err();
+        assert_eq!((err.line, err.column), (Some(2), Some(3)));
+        assert_eq!(err.message, "unknown keyword `msgfoo`");
+
+        let err = parse_po("msgid \"a\"\nmsgid_plural \"b\"\nmsgstr[1] \"B\"", "en").unwrap_err();
+        assert_eq!(err.message, "expected `msgstr[0]`");
+
+        let err = parse_po("\"orphan\"", "en").unwrap_err();
+        assert_eq!(err.line, Some(1));
+    }
+
+    /// Build a little-endian `.mo` file of the entries.
+    fn mo(entries: &[(&str, &str)]) -> Vec<u8> {
+        let header_len = 28 + entries.len() * 16;
+        let mut strings = vec![];
+        let mut originals = vec![];
+        let mut translations = vec![];
+        for (original, _) in entries {
+            originals.push((original.len(), header_len + strings.len()));
+            strings.extend_from_slice(original.as_bytes());
+            strings.push(0);
+        }
+        for (_, translation) in entries {
+            translations.push((translation.len(), header_len + strings.len()));
+            strings.extend_from_slice(translation.as_bytes());
+            strings.push(0);
+        }
+
+        let mut bytes = vec![];
+        for n in [
+            0x950412de,
+            0,
+            entries.len(),
+            28,
+            28 + entries.len() * 8,
+            0,
+            0,
+        ] {
+            bytes.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        for (len, offset) in originals.into_iter().chain(translations) {
+            bytes.extend_from_slice(&(len as u32).to_le_bytes());
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+        }
+        bytes.extend(strings);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_mo() {
+        let bytes = mo(&[
+            (
+                "",
+                "Content-Type: text/plain; charset=UTF-8\nPlural-Forms: nplurals=2; plural=n != 1;\n",
+            ),
+            ("hello", "Hello world"),
+            ("inbox\0inboxes", "%{count} message\0%{count} messages"),
+            ("messages\u{4}hello", "Hello, %{name}"),
+        ]);
+        let value = parse_mo(&bytes, "en").unwrap();
+        assert_eq!(value["hello"], "Hello world");
+        assert_eq!(value["inbox"]["one"], "%{count} message");
+        assert_eq!(value["inbox"]["other"], "%{count} messages");
+        assert_eq!(value["messages.hello"], "Hello, %{name}");
+        assert!(value.get("").is_none());
+
+        assert_eq!(
+            parse_mo(&[0, 1, 2, 3, 4], "en").unwrap_err().message,
+            "invalid magic number"
+        );
+        assert_eq!(
+            parse_mo(&bytes[..40], "en").unwrap_err().message,
+            "unexpected end of file"
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("Say \"hi\"\n\\"), r#""Say \"hi\"\n\\""#);
+        assert_eq!(
+            unquote(&quote("a\tb\r\n\"c\"\\"), 1, 1).unwrap(),
+            "a\tb\r\n\"c\"\\"
+        );
+    }
+}
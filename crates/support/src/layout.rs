@@ -0,0 +1,400 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// How the locale, and the namespace of the keys, are taken from the path of a locale file.
+///
+/// The namespace is the prefix of all keys of the file, like `common.hello` of `hello` in
+/// `de/common.yml`. The extension of the patterns is any of the supported formats.
+///
+/// The `rails` and `leaf` layouts keep all locales in a file, a YAML, JSON or TOML file not in that
+/// structure, like a `TODO.en.yml`, is loaded by the locale of its file name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// The locale is the last part of the file stem, like `en` of `app.en.yml`, without a namespace.
+    #[default]
+    LocaleSuffix,
+    /// `{locale}.yml`, the file stem is the locale, like `zh-CN.yml`.
+    Locale,
+    /// `{namespace}.{locale}.yml`, like `admin.en.yml`, a file without a namespace is `{locale}.yml`.
+    NamespaceLocale,
+    /// `{locale}/{namespace}.yml`, like `de/common.yml`, the nested directories are parts of the
+    /// namespace, like `admin.users` of `de/admin/users.yml`. A file in the locales path is `{locale}.yml`.
+    LocaleDirectory,
+    /// `rails`, the top-level keys of a file are the locales, like `en: { hello: Hello }` of Rails.
+    Rails,
+    /// `leaf`, the locales are the keys of the maps of texts, like `hello: { en: Hello, de: Hallo }`.
+    Leaf,
+}
+
+impl Layout {
+    /// The patterns of the layouts, as written in `i18n!` and Cargo.toml.
+    const PATTERNS: [(&'static str, Layout); 5] = [
+        ("{locale}", Layout::Locale),
+        ("{namespace}.{locale}", Layout::NamespaceLocale),
+        ("{locale}/{namespace}", Layout::LocaleDirectory),
+        ("rails", Layout::Rails),
+        ("leaf", Layout::Leaf),
+    ];
+
+    /// Whether a file has all locales, by the `rails` or `leaf` layout.
+    pub fn is_multi_locale(&self) -> bool {
+        matches!(self, Layout::Rails | Layout::Leaf)
+    }
+
+    /// Split a parsed file of the `rails` or `leaf` layout into its locales, `None` if the file is not
+    /// in the structure of the layout. The top-level keys like `_message_format` are kept in every locale.
+    pub fn split_locales(&self, value: &Value) -> Option<HashMap<String, Value>> {
+        let Value::Object(object) = value else {
+            return None;
+        };
+        let (options, object): (Map<String, Value>, Map<String, Value>) = object
+            .clone()
+            .into_iter()
+            .partition(|(key, _)| key.starts_with('_'));
+
+        let mut result = HashMap::new();
+        match self {
+            Layout::Rails => {
+                for (locale, value) in object {
+                    if !value.is_object() {
+                        return None;
+                    }
+                    result.insert(locale, value);
+                }
+            }
+            Layout::Leaf => {
+                for (key, value) in &object {
+                    split_leaf(&mut result, &mut vec![key], value)?;
+                }
+            }
+            _ => return None,
+        }
+
+        for value in result.values_mut() {
+            if let Value::Object(value) = value {
+                value.extend(options.clone());
+            }
+        }
+        Some(result)
+    }
+
+    /// Write the translations of a locale into a file of the `rails` or `leaf` layout, the other locales
+    /// are kept. The top-level keys like `_message_format` of the translations are not written.
+    pub fn merge_locale(&self, file: &mut Value, locale: &str, value: &Value) {
+        let mut value = value.clone();
+        if let Value::Object(value) = &mut value {
+            value.retain(|key, _| !key.starts_with('_'));
+        }
+        if !file.is_object() {
+            *file = Value::Object(Map::new());
+        }
+
+        match self {
+            Layout::Rails => {
+                if let Value::Object(file) = file {
+                    file.insert(locale.to_string(), value);
+                }
+            }
+            Layout::Leaf => merge_leaf(file, locale, &value),
+            _ => *file = value,
+        }
+    }
+
+    /// The locale and the namespace of a locale file in the locales path.
+    pub fn locale_and_namespace(
+        &self,
+        locales_path: &Path,
+        path: &Path,
+    ) -> Option<(String, Option<String>)> {
+        let stem = path.file_stem()?.to_str()?;
+
+        match self {
+            Layout::LocaleSuffix | Layout::Rails | Layout::Leaf => {
+                Some((stem.split('.').next_back()?.to_string(), None))
+            }
+            Layout::Locale => Some((stem.to_string(), None)),
+            Layout::NamespaceLocale => match stem.rsplit_once('.') {
+                Some((namespace, locale)) => {
+                    Some((locale.to_string(), Some(namespace.to_string())))
+                }
+                None => Some((stem.to_string(), None)),
+            },
+            Layout::LocaleDirectory => {
+                // The glob may list `./locales/de/common.yml` of `locales`
+                let without_cur_dir = |path: &Path| {
+                    path.components()
+                        .filter(|component| *component != Component::CurDir)
+                        .collect::<PathBuf>()
+                };
+                let (path, locales_path) = (without_cur_dir(path), without_cur_dir(locales_path));
+                let relative = path.strip_prefix(&locales_path).unwrap_or(&path);
+                let mut dirs = relative
+                    .parent()
+                    .into_iter()
+                    .flat_map(|dir| dir.components())
+                    .filter_map(|component| match component {
+                        Component::Normal(dir) => dir.to_str(),
+                        _ => None,
+                    });
+                let Some(locale) = dirs.next() else {
+                    return Some((stem.to_string(), None));
+                };
+
+                let namespace = dirs.chain([stem]).collect::<Vec<_>>().join(".");
+                Some((locale.to_string(), Some(namespace)))
+            }
+        }
+    }
+}
+
+/// Move the texts of a map of locales at the path into the locales, `None` if a text has no locales.
+fn split_leaf<'a>(
+    result: &mut HashMap<String, Value>,
+    path: &mut Vec<&'a String>,
+    value: &'a Value,
+) -> Option<()> {
+    let Value::Object(object) = value else {
+        return None;
+    };
+
+    if !object.is_empty() && object.values().all(|value| !value.is_object()) {
+        for (locale, text) in object {
+            let mut target = result
+                .entry(locale.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            for key in path.iter() {
+                target = target
+                    .as_object_mut()?
+                    .entry(key.to_string())
+                    .or_insert_with(|| Value::Object(Map::new()));
+            }
+            *target = text.clone();
+        }
+        return Some(());
+    }
+
+    for (key, value) in object {
+        path.push(key);
+        split_leaf(result, path, value)?;
+        path.pop();
+    }
+    Some(())
+}
+
+/// Set the texts of the translations as the locale of the maps of locales.
+fn merge_leaf(file: &mut Value, locale: &str, value: &Value) {
+    if !file.is_object() {
+        *file = Value::Object(Map::new());
+    }
+    let Value::Object(file) = file else {
+        return;
+    };
+
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let child = file
+                    .entry(key.clone())
+                    .or_insert_with(|| Value::Object(Map::new()));
+                merge_leaf(child, locale, value);
+            }
+        }
+        text => {
+            file.insert(locale.to_string(), text.clone());
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    /// Parse a pattern like `{locale}/{namespace}.yml`, the extension is optional.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s.trim();
+        let pattern = match pattern.rsplit_once('.') {
+            Some((head, ext)) if !ext.contains(['{', '}', '/']) => head,
+            _ => pattern,
+        };
+
+        Self::PATTERNS
+            .iter()
+            .find(|(p, _)| *p == pattern)
+            .map(|(_, layout)| *layout)
+            .ok_or_else(|| {
+                format!(
+                    "unknown layout `{}`, expected \"{{locale}}/{{namespace}}.yml\", \"{{namespace}}.{{locale}}.yml\", \"{{locale}}.yml\", \"rails\" or \"leaf\"",
+                    s
+                )
+            })
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Self::PATTERNS.iter().find(|(_, layout)| layout == self) {
+            Some((pattern, _)) if self.is_multi_locale() => f.write_str(pattern),
+            Some((pattern, _)) => write!(f, "{}.yml", pattern),
+            None => f.write_str("**/*.{locale}.yml"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn locale_and_namespace(layout: Layout, path: &str) -> Option<(String, Option<String>)> {
+        layout.locale_and_namespace(Path::new("locales"), &Path::new("locales").join(path))
+    }
+
+    #[test]
+    fn test_locale_and_namespace() {
+        let some = |locale: &str, namespace: Option<&str>| {
+            Some((locale.to_string(), namespace.map(|ns| ns.to_string())))
+        };
+
+        assert_eq!(
+            locale_and_namespace(Layout::LocaleSuffix, "nested/app.en.yml"),
+            some("en", None)
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::LocaleSuffix, "de/common.yml"),
+            some("common", None)
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::Locale, "zh-CN.yml"),
+            some("zh-CN", None)
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::NamespaceLocale, "admin.en.yml"),
+            some("en", Some("admin"))
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::NamespaceLocale, "admin.users.en.json"),
+            some("en", Some("admin.users"))
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::NamespaceLocale, "en.yml"),
+            some("en", None)
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::LocaleDirectory, "de/common.yml"),
+            some("de", Some("common"))
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::LocaleDirectory, "de/admin/users.toml"),
+            some("de", Some("admin.users"))
+        );
+        assert_eq!(
+            locale_and_namespace(Layout::LocaleDirectory, "de.yml"),
+            some("de", None)
+        );
+        assert_eq!(
+            Layout::LocaleDirectory
+                .locale_and_namespace(Path::new("locales"), Path::new("./locales/de/common.yml")),
+            some("de", Some("common"))
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "{locale}/{namespace}.yml".parse(),
+            Ok(Layout::LocaleDirectory)
+        );
+        assert_eq!(
+            "{namespace}.{locale}.json".parse(),
+            Ok(Layout::NamespaceLocale)
+        );
+        assert_eq!("{locale}.yml".parse(), Ok(Layout::Locale));
+        assert_eq!("{locale}".parse(), Ok(Layout::Locale));
+        assert!("{namespace}/{locale}.yml".parse::<Layout>().is_err());
+
+        assert_eq!("rails".parse(), Ok(Layout::Rails));
+        assert_eq!("leaf".parse(), Ok(Layout::Leaf));
+
+        for layout in [
+            Layout::Locale,
+            Layout::NamespaceLocale,
+            Layout::LocaleDirectory,
+            Layout::Rails,
+            Layout::Leaf,
+        ] {
+            assert_eq!(layout.to_string().parse(), Ok(layout));
+        }
+    }
+
+    #[test]
+    fn test_split_locales() {
+        let rails = json!({
+            "_message_format": "icu",
+            "en": { "hello": "Hello", "messages": { "hi": "Hi" } },
+            "de": { "hello": "Hallo" },
+        });
+        let locales = Layout::Rails.split_locales(&rails).unwrap();
+        assert_eq!(
+            locales["en"],
+            json!({ "hello": "Hello", "messages": { "hi": "Hi" }, "_message_format": "icu" })
+        );
+        assert_eq!(
+            locales["de"],
+            json!({ "hello": "Hallo", "_message_format": "icu" })
+        );
+        assert_eq!(
+            Layout::Rails.split_locales(&json!({ "hello": "Hello" })),
+            None
+        );
+
+        let leaf = json!({
+            "hello": { "en": "Hello", "de": "Hallo" },
+            "messages": { "hi": { "en": "Hi" } },
+        });
+        let locales = Layout::Leaf.split_locales(&leaf).unwrap();
+        assert_eq!(
+            locales["en"],
+            json!({ "hello": "Hello", "messages": { "hi": "Hi" } })
+        );
+        assert_eq!(locales["de"], json!({ "hello": "Hallo" }));
+        assert_eq!(
+            Layout::Leaf.split_locales(&json!({ "hello": "Hello", "bye": "Bye" })),
+            None
+        );
+        assert_eq!(
+            Layout::Leaf.split_locales(&json!({ "a": { "en": "A", "b": { "en": "B" } } })),
+            None
+        );
+        assert_eq!(Layout::Locale.split_locales(&rails), None);
+    }
+
+    #[test]
+    fn test_merge_locale() {
+        let mut rails = json!({ "en": { "hello": "Hello" }, "de": { "hello": "Hallo" } });
+        Layout::Rails.merge_locale(
+            &mut rails,
+            "de",
+            &json!({ "hello": "Hallo!", "_message_format": "icu" }),
+        );
+        assert_eq!(
+            rails,
+            json!({ "en": { "hello": "Hello" }, "de": { "hello": "Hallo!" } })
+        );
+
+        let mut leaf = json!({ "hello": { "en": "Hello" } });
+        Layout::Leaf.merge_locale(
+            &mut leaf,
+            "de",
+            &json!({ "hello": "Hallo", "messages": { "hi": "Hi" } }),
+        );
+        assert_eq!(
+            leaf,
+            json!({
+                "hello": { "en": "Hello", "de": "Hallo" },
+                "messages": { "hi": { "de": "Hi" } },
+            })
+        );
+    }
+}
@@ -1,10 +1,24 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+pub use error::{FileFormat, LoadError, LoadErrorKind};
+pub use layout::Layout;
 pub use sys_locale::get_locale;
 pub mod backend;
+mod error;
+pub mod fallback;
+pub mod fluent;
+pub mod gettext;
+pub mod layout;
+pub mod message_format;
+pub mod missing;
+pub mod placeholder;
+pub mod plural;
+pub mod tree;
+#[cfg(feature = "watch")]
+pub mod watching;
 
 type Locale = String;
 type Value = serde_json::Value;
@@ -14,15 +28,96 @@ pub fn is_debug() -> bool {
     std::env::var("RUST_I18N_DEBUG").unwrap_or_else(|_| "0".to_string()) == "1"
 }
 
+/// Top-level key of a locale file for set the message format of its translations, e.g. `_message_format: icu`
+pub const MESSAGE_FORMAT_KEY: &str = "_message_format";
+
 // Load locales into flatten key, value HashMap
+//
+// Panics if a locale file failed to load, use `try_load_locales` for get the error.
 pub fn load_locales<F: Fn(&str) -> bool>(
     locales_path: &str,
     ignore_if: F,
+) -> HashMap<String, HashMap<String, String>> {
+    try_load_locales(locales_path, Layout::default(), ignore_if)
+        .unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Load locales of the layout into flatten key, value HashMap, or the error of the first locale file failed to load.
+pub fn try_load_locales<F: Fn(&str) -> bool>(
+    locales_path: &str,
+    layout: Layout,
+    ignore_if: F,
+) -> Result<HashMap<String, HashMap<String, String>>, LoadError> {
+    let files = load_files(locales_path, layout, ignore_if)?;
+    Ok(merge_translations(files.into_iter().map(|(_, trs)| trs)))
+}
+
+/// Merge the translations of the locale files in order, into flatten key, value HashMap
+fn merge_translations<I: IntoIterator<Item = Translations>>(
+    files: I,
 ) -> HashMap<String, HashMap<String, String>> {
     let mut result: HashMap<String, HashMap<String, String>> = HashMap::new();
     let mut translations = HashMap::new();
 
-    let path_pattern = format!("{locales_path}/**/*.{{yml,yaml,json,toml}}");
+    for trs in files {
+        trs.into_iter().for_each(|(k, mut new_value)| {
+            if let Value::Object(object) = &mut new_value {
+                object.remove(MESSAGE_FORMAT_KEY);
+            }
+
+            translations
+                .entry(k)
+                .and_modify(|old_value| merge_value(old_value, &new_value))
+                .or_insert(new_value);
+        });
+    }
+
+    translations.iter().for_each(|(locale, trs)| {
+        result.insert(locale.to_string(), flatten_keys("", trs));
+    });
+
+    result
+}
+
+/// Load the message formats set by the locale files, as locale => key => format.
+///
+/// Only the keys of the files with a top-level `_message_format` key are included, e.g. `_message_format: icu`.
+pub fn load_message_formats<F: Fn(&str) -> bool>(
+    locales_path: &str,
+    layout: Layout,
+    ignore_if: F,
+) -> Result<HashMap<String, HashMap<String, String>>, LoadError> {
+    let mut result: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+    for (_, trs) in load_files(locales_path, layout, ignore_if)? {
+        for (locale, value) in trs {
+            let Some(format) = value.get(MESSAGE_FORMAT_KEY).and_then(|v| v.as_str()) else {
+                continue;
+            };
+
+            let formats = result.entry(locale).or_default();
+            for key in flatten_keys("", &value).into_keys() {
+                if key != MESSAGE_FORMAT_KEY {
+                    formats.insert(key, format.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Load and parse all locale files of the path, with the path of each file.
+///
+/// The translations of a file are not flatten, as locale => the parsed file, nested in its namespace if any.
+pub fn load_files<F: Fn(&str) -> bool>(
+    locales_path: &str,
+    layout: Layout,
+    ignore_if: F,
+) -> Result<Vec<(PathBuf, Translations)>, LoadError> {
+    let mut result = vec![];
+
+    let path_pattern = format!("{locales_path}/**/*.{{yml,yaml,json,toml,po,mo,ftl}}");
 
     if is_debug() {
         println!("cargo:i18n-locale={}", &path_pattern);
@@ -33,11 +128,18 @@ pub fn load_locales<F: Fn(&str) -> bool>(
         if is_debug() {
             println!("cargo:i18n-error=path not exists: {}", locales_path);
         }
-        return result;
+        return Ok(result);
     }
 
-    for entry in globwalk::glob(&path_pattern).expect("Failed to read glob pattern") {
-        let entry = entry.unwrap().into_path();
+    let entries = globwalk::glob(&path_pattern)
+        .map_err(|err| LoadError::new(locales_path, LoadErrorKind::Pattern(Box::new(err))))?;
+    for entry in entries {
+        let entry = entry
+            .map_err(|err| {
+                let path = err.path().unwrap_or(Path::new(locales_path)).to_path_buf();
+                LoadError::new(path, LoadErrorKind::Io(err.into()))
+            })?
+            .into_path();
         if is_debug() {
             println!("cargo:i18n-load={}", &entry.display());
         }
@@ -46,37 +148,94 @@ pub fn load_locales<F: Fn(&str) -> bool>(
             continue;
         }
 
-        let locale = entry
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .and_then(|s| s.split('.').last())
-            .unwrap();
+        let trs = load_file(Path::new(locales_path), &entry, layout)?;
+        result.push((entry, trs));
+    }
 
-        let ext = entry.extension().and_then(|s| s.to_str()).unwrap();
+    Ok(result)
+}
+
+/// Read and parse a locale file, the locale and namespace are taken from the path by the layout,
+/// or the locale is the directory of `LC_MESSAGES` for the gettext layout, like `en` of `en/LC_MESSAGES/app.po`.
+fn load_file(locales_path: &Path, path: &Path, layout: Layout) -> Result<Translations, LoadError> {
+    let gettext_locale = path
+        .parent()
+        .filter(|dir| dir.file_name() == Some("LC_MESSAGES".as_ref()))
+        .and_then(|dir| dir.parent())
+        .and_then(|dir| dir.file_name())
+        .and_then(|s| s.to_str())
+        .map(|locale| (locale.to_string(), None));
+    let (locale, namespace) = gettext_locale
+        .or_else(|| layout.locale_and_namespace(locales_path, path))
+        .filter(|(locale, _)| !locale.is_empty())
+        .ok_or_else(|| LoadError::new(path, LoadErrorKind::InvalidFileName))?;
+    let locale = locale.as_str();
+
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+
+    let mut bytes = vec![];
+    File::open(path)
+        .and_then(|file| std::io::BufReader::new(file).read_to_end(&mut bytes))
+        .map_err(|err| LoadError::new(path, LoadErrorKind::Io(err)))?;
+
+    // The compiled gettext catalogs are binary
+    if FileFormat::from_extension(ext) == Some(FileFormat::Mo) {
+        let value = gettext::parse_mo(&bytes, locale)
+            .map_err(|err| LoadError::new(path, LoadErrorKind::Mo(err)))?;
+        return Ok(Translations::from([(
+            locale.to_string(),
+            with_namespace(value, namespace.as_deref()),
+        )]));
+    }
 
-        let file = File::open(&entry).expect("Failed to open file");
-        let mut reader = std::io::BufReader::new(file);
-        let mut content = String::new();
+    let content = String::from_utf8(bytes).map_err(|err| {
+        let err = std::io::Error::new(std::io::ErrorKind::InvalidData, err);
+        LoadError::new(path, LoadErrorKind::Io(err))
+    })?;
+
+    let mut trs =
+        parse_file(&content, ext, locale).map_err(|kind| LoadError::parse(path, kind, &content))?;
+    let is_tree = matches!(
+        FileFormat::from_extension(ext),
+        Some(FileFormat::Yaml | FileFormat::Json | FileFormat::Toml)
+    );
+    if layout.is_multi_locale() && is_tree {
+        if let Some(locales) = trs.get(locale).and_then(|value| layout.split_locales(value)) {
+            return Ok(locales);
+        }
+    }
+    if namespace.is_some() {
+        trs = trs
+            .into_iter()
+            .map(|(locale, value)| (locale, with_namespace(value, namespace.as_deref())))
+            .collect();
+    }
 
-        reader
-            .read_to_string(&mut content)
-            .expect("Read file failed.");
+    Ok(trs)
+}
 
-        let trs = parse_file(&content, ext, locale).expect("Parse file failed.");
+/// Nest the translations of a file in its namespace, like `common: { ... }` of the `common` namespace,
+/// the `_message_format` key stays at the top-level.
+fn with_namespace(mut value: Value, namespace: Option<&str>) -> Value {
+    let Some(namespace) = namespace else {
+        return value;
+    };
 
-        trs.into_iter().for_each(|(k, new_value)| {
-            translations
-                .entry(k)
-                .and_modify(|old_value| merge_value(old_value, &new_value))
-                .or_insert(new_value);
-        });
+    let message_format = match &mut value {
+        Value::Object(object) => object.remove(MESSAGE_FORMAT_KEY),
+        _ => None,
+    };
+    for part in namespace.rsplit('.') {
+        value = Value::Object(serde_json::Map::from_iter([(part.to_string(), value)]));
+    }
+    if let (Value::Object(object), Some(format)) = (&mut value, message_format) {
+        object.insert(MESSAGE_FORMAT_KEY.to_string(), format);
     }
 
-    translations.iter().for_each(|(locale, trs)| {
-        result.insert(locale.to_string(), flatten_keys("", trs));
-    });
-
-    result
+    value
 }
 
 /// Merge JSON Values, merge b into a
@@ -94,15 +253,19 @@ fn merge_value(a: &mut Value, b: &Value) {
 }
 
 // Parse Translations from file to support multiple formats
-fn parse_file(content: &str, ext: &str, locale: &str) -> Result<Translations, String> {
-    let result = match ext {
-        "yml" | "yaml" => serde_yaml::from_str::<serde_json::Value>(content)
-            .map_err(|err| format!("Invalid YAML format, {}", err)),
-        "json" => serde_json::from_str::<serde_json::Value>(content)
-            .map_err(|err| format!("Invalid JSON format, {}", err)),
-        "toml" => toml::from_str::<serde_json::Value>(content)
-            .map_err(|err| format!("Invalid TOML format, {}", err)),
-        _ => Err("Invalid file extension".into()),
+fn parse_file(content: &str, ext: &str, locale: &str) -> Result<Translations, LoadErrorKind> {
+    let result = match FileFormat::from_extension(ext) {
+        Some(FileFormat::Yaml) => {
+            serde_yaml::from_str::<serde_json::Value>(content).map_err(LoadErrorKind::Yaml)
+        }
+        Some(FileFormat::Json) => {
+            serde_json::from_str::<serde_json::Value>(content).map_err(LoadErrorKind::Json)
+        }
+        Some(FileFormat::Toml) => toml::from_str::<serde_json::Value>(content)
+            .map_err(|err| LoadErrorKind::Toml(Box::new(err))),
+        Some(FileFormat::Po) => gettext::parse_po(content, locale).map_err(LoadErrorKind::Po),
+        Some(FileFormat::Fluent) => fluent::parse_ftl(content).map_err(LoadErrorKind::Fluent),
+        Some(FileFormat::Mo) | None => Err(LoadErrorKind::InvalidFileName),
     };
 
     match result {
@@ -138,8 +301,16 @@ fn flatten_keys(prefix: &str, trs: &Value) -> HashMap<String, String> {
         serde_json::Value::Number(s) => {
             v.insert(prefix, format!("{}", s));
         }
-        serde_json::Value::Array(_) => {
-            v.insert(prefix, "".into());
+        // The items of an array are indexed keys, like `days.0`
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let key = if prefix.is_empty() {
+                    i.to_string()
+                } else {
+                    format!("{}.{}", prefix, i)
+                };
+                v.extend(flatten_keys(key.as_str(), item));
+            }
         }
     }
 
@@ -148,7 +319,10 @@ fn flatten_keys(prefix: &str, trs: &Value) -> HashMap<String, String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{merge_value, parse_file};
+    use super::{
+        flatten_keys, merge_value, parse_file, try_load_locales, with_namespace, FileFormat,
+        Layout, LoadErrorKind,
+    };
 
     #[test]
     fn test_merge_value() {
@@ -170,6 +344,23 @@ mod tests {
         assert_eq!(c["dar"]["b"], "21");
     }
 
+    #[test]
+    fn test_flatten_keys() {
+        let value = serde_json::json!({
+            "days": ["Sunday", "Monday"],
+            "menu": { "items": [{ "label": "File" }, "Edit"], "title": "Menu" },
+            "empty": [],
+        });
+        let trs = flatten_keys("", &value);
+
+        assert_eq!(trs.len(), 5);
+        assert_eq!(trs["days.0"], "Sunday");
+        assert_eq!(trs["days.1"], "Monday");
+        assert_eq!(trs["menu.items.0.label"], "File");
+        assert_eq!(trs["menu.items.1"], "Edit");
+        assert_eq!(trs["menu.title"], "Menu");
+    }
+
     #[test]
     fn test_parse_file_in_yaml() {
         let content = "foo: Foo\nbar: Bar";
@@ -210,4 +401,105 @@ mod tests {
         assert_eq!(trs["en"]["foo"], "Foo");
         assert_eq!(trs["en"]["bar"], "Bar");
     }
+
+    #[test]
+    fn test_try_load_locales() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let locales_path = dir.display().to_string();
+
+        std::fs::write(dir.join("en.yml"), "foo: Foo\nbar:\n  baz: Baz").unwrap();
+        let trs = try_load_locales(&locales_path, Layout::default(), |_| false).unwrap();
+        assert_eq!(trs["en"]["bar.baz"], "Baz");
+
+        std::fs::write(dir.join("zh.yml"), "foo: Foo\nbar: [Bar\nbaz: Baz").unwrap();
+        let err = try_load_locales(&locales_path, Layout::default(), |_| false).unwrap_err();
+        assert_eq!(err.path, dir.join("zh.yml"));
+        assert_eq!(err.format(), Some(FileFormat::Yaml));
+        assert_eq!((err.line, err.column), (Some(3), Some(4)));
+        assert!(matches!(err.kind, LoadErrorKind::Yaml(_)));
+        assert!(err.to_string().starts_with(&format!(
+            "{}:3:4: invalid YAML format",
+            dir.join("zh.yml").display()
+        )));
+        std::fs::remove_file(dir.join("zh.yml")).unwrap();
+
+        std::fs::write(dir.join("fr.json"), "{\n  \"foo\": \"Foo\",\n  \"bar\"\n}").unwrap();
+        let err = try_load_locales(&locales_path, Layout::default(), |_| false).unwrap_err();
+        assert_eq!(err.format(), Some(FileFormat::Json));
+        assert_eq!((err.line, err.column), (Some(4), Some(1)));
+        std::fs::remove_file(dir.join("fr.json")).unwrap();
+
+        std::fs::write(dir.join("de.toml"), "foo = \"Foo\"\nbar = Bar").unwrap();
+        let err = try_load_locales(&locales_path, Layout::default(), |_| false).unwrap_err();
+        assert_eq!(err.format(), Some(FileFormat::Toml));
+        assert_eq!((err.line, err.column), (Some(2), Some(7)));
+        std::fs::remove_file(dir.join("de.toml")).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_with_namespace() {
+        let value = serde_json::json!({ "hello": "Hello", "_message_format": "icu" });
+        assert_eq!(with_namespace(value.clone(), None), value);
+        assert_eq!(
+            with_namespace(value, Some("admin.users")),
+            serde_json::json!({
+                "admin": { "users": { "hello": "Hello" } },
+                "_message_format": "icu",
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_load_locales_with_layout() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-layout-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("de/admin")).unwrap();
+        let locales_path = dir.display().to_string();
+
+        std::fs::write(dir.join("de/common.yml"), "hello: Hallo").unwrap();
+        std::fs::write(dir.join("de/admin/users.json"), r#"{"title": "Benutzer"}"#).unwrap();
+        std::fs::write(dir.join("en.yml"), "hello: Hello").unwrap();
+
+        let trs = try_load_locales(&locales_path, Layout::LocaleDirectory, |_| false).unwrap();
+        assert_eq!(trs["de"]["common.hello"], "Hallo");
+        assert_eq!(trs["de"]["admin.users.title"], "Benutzer");
+        assert_eq!(trs["en"]["hello"], "Hello");
+
+        let trs = try_load_locales(&locales_path, Layout::default(), |_| false).unwrap();
+        assert_eq!(trs["common"]["hello"], "Hallo");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("admin.en.yml"), "title: Admin").unwrap();
+        std::fs::write(dir.join("en.yml"), "hello: Hello").unwrap();
+
+        let trs = try_load_locales(&locales_path, Layout::NamespaceLocale, |_| false).unwrap();
+        assert_eq!(trs["en"]["admin.title"], "Admin");
+        assert_eq!(trs["en"]["hello"], "Hello");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.yml"), "en:\n  hello: Hello\nde:\n  hello: Hallo").unwrap();
+        std::fs::write(dir.join("TODO.fr.yml"), "bye: Au revoir").unwrap();
+
+        let trs = try_load_locales(&locales_path, Layout::Rails, |_| false).unwrap();
+        assert_eq!(trs["en"]["hello"], "Hello");
+        assert_eq!(trs["de"]["hello"], "Hallo");
+        assert_eq!(trs["fr"]["bye"], "Au revoir");
+
+        std::fs::write(
+            dir.join("app.yml"),
+            "hello:\n  en: Hello\n  de: Hallo\nmessages:\n  hi:\n    en: Hi",
+        )
+        .unwrap();
+        let trs = try_load_locales(&locales_path, Layout::Leaf, |_| false).unwrap();
+        assert_eq!(trs["en"]["hello"], "Hello");
+        assert_eq!(trs["en"]["messages.hi"], "Hi");
+        assert_eq!(trs["de"]["hello"], "Hallo");
+        assert_eq!(trs["fr"]["bye"], "Au revoir");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
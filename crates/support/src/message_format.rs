@@ -0,0 +1,623 @@
+use crate::fluent;
+use crate::plural::{ordinal_category, plural_category, PluralCategory, PluralOperands};
+use std::borrow::Cow;
+
+/// A translation in [ICU MessageFormat](https://unicode-org.github.io/icu/userguide/format_parse/messages/) syntax,
+/// parsed once and formatted with the named arguments of `t!`.
+///
+/// Supports the simple arguments `{name}`, nested `plural`, `select` and `selectordinal` arguments,
+/// `#` for the number of the innermost plural, and apostrophe quoting like `'{'` and `''`.
+///
+/// ```ignore
+/// let message = Message::parse("{count, plural, one {# file} other {# files}}")?;
+/// message.format("en", &[("count", "2".to_string())]); // => "2 files"
+/// ```
+///
+/// A message of a `.ftl` file is parsed by [`Message::parse_fluent`] in Fluent syntax instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    source: String,
+    body: Body,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Body {
+    Icu(Vec<Part>),
+    Fluent(Vec<fluent::Part>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Argument(String),
+    /// `#` in a plural case
+    Number,
+    Plural {
+        name: String,
+        ordinal: bool,
+        offset: f64,
+        cases: Vec<(Selector, Vec<Part>)>,
+    },
+    Select {
+        name: String,
+        cases: Vec<(String, Vec<Part>)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    /// `=1`
+    Exact(f64),
+    /// `one`
+    Category(PluralCategory),
+}
+
+impl Message {
+    /// Parse a message, the error contains the byte position of the problem.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser { source, pos: 0 };
+        let parts = parser.parse_message(false, false)?;
+
+        Ok(Self {
+            source: source.to_string(),
+            body: Body::Icu(parts),
+        })
+    }
+
+    /// Parse a message in [Fluent](https://projectfluent.org/) syntax, like `{ $count -> [one] ... *[other] ... }`.
+    pub fn parse_fluent(source: &str) -> Result<Self, String> {
+        let parts = fluent::parse_pattern(source)?;
+
+        Ok(Self {
+            source: source.to_string(),
+            body: Body::Fluent(parts),
+        })
+    }
+
+    /// The message before parsing.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The keys of the messages and terms referenced by a Fluent message, like `-brand` of `{ -brand }`,
+    /// none for an ICU message.
+    pub fn references(&self) -> Vec<String> {
+        let mut keys = vec![];
+        if let Body::Fluent(parts) = &self.body {
+            fluent::references(parts, &mut keys);
+        }
+        keys
+    }
+
+    /// Format the message with the named arguments, the plural rules of `locale` select the plural cases.
+    ///
+    /// A missing argument is kept as `{name}`, and selects the `other` case.
+    pub fn format(&self, locale: &str, args: &[(&str, String)]) -> Cow<'_, str> {
+        self.format_with(locale, args, &|_, _| None)
+    }
+
+    /// Format the message like [`Message::format`], `resolve` looks up the messages and terms
+    /// referenced by a Fluent message, an unresolved reference is kept as `{id}`.
+    pub fn format_with(
+        &self,
+        locale: &str,
+        args: &[(&str, String)],
+        resolve: fluent::Resolve,
+    ) -> Cow<'_, str> {
+        match &self.body {
+            Body::Icu(parts) => match parts.as_slice() {
+                [] => Cow::Borrowed(""),
+                [Part::Text(text)] => Cow::Borrowed(text),
+                parts => {
+                    let mut output = String::new();
+                    format_parts(parts, locale, args, None, &mut output);
+                    Cow::Owned(output)
+                }
+            },
+            Body::Fluent(parts) => match parts.as_slice() {
+                [] => Cow::Borrowed(""),
+                [fluent::Part::Text(text)] => Cow::Borrowed(text),
+                parts => {
+                    let mut output = String::new();
+                    fluent::format_parts(parts, locale, args, resolve, &mut output);
+                    Cow::Owned(output)
+                }
+            },
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at position {}", message, self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected `{}`", expected)));
+        }
+
+        self.bump();
+        Ok(())
+    }
+
+    /// Parse an argument name, type or selector.
+    fn parse_word(&mut self) -> &str {
+        self.skip_whitespace();
+
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, '{' | '}' | ',' | '#' | '\''))
+        {
+            self.bump();
+        }
+
+        &self.source[start..self.pos]
+    }
+
+    /// Parse the parts until the end, or until the `}` closing a case when `nested`.
+    fn parse_message(&mut self, in_plural: bool, nested: bool) -> Result<Vec<Part>, String> {
+        let mut parts = vec![];
+        let mut text = String::new();
+
+        loop {
+            match self.peek() {
+                None if nested => return Err(self.error("unclosed `{`")),
+                None => break,
+                Some('}') if nested => break,
+                Some('}') => return Err(self.error("unmatched `}`")),
+                Some('{') => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    self.bump();
+                    parts.push(self.parse_argument(in_plural)?);
+                }
+                Some('#') if in_plural => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    self.bump();
+                    parts.push(Part::Number);
+                }
+                Some('\'') => {
+                    self.bump();
+                    self.parse_quoted(in_plural, &mut text);
+                }
+                Some(c) => {
+                    self.bump();
+                    text.push(c);
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(parts)
+    }
+
+    /// An apostrophe quotes the following syntax characters until the next single apostrophe,
+    /// `''` is an apostrophe, and an apostrophe before other characters is kept as is.
+    fn parse_quoted(&mut self, in_plural: bool, text: &mut String) {
+        match self.peek() {
+            Some('\'') => {
+                self.bump();
+                text.push('\'');
+                return;
+            }
+            Some('{' | '}' | '|') => {}
+            Some('#') if in_plural => {}
+            _ => {
+                text.push('\'');
+                return;
+            }
+        }
+
+        while let Some(c) = self.bump() {
+            if c != '\'' {
+                text.push(c);
+            } else if self.peek() == Some('\'') {
+                self.bump();
+                text.push('\'');
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Parse an argument after `{`, until its closing `}`.
+    fn parse_argument(&mut self, in_plural: bool) -> Result<Part, String> {
+        let name = self.parse_word().to_string();
+        if name.is_empty() {
+            return Err(self.error("expected argument name"));
+        }
+
+        self.skip_whitespace();
+        match self.bump() {
+            Some('}') => return Ok(Part::Argument(name)),
+            Some(',') => {}
+            _ => return Err(self.error(&format!("expected `,` or `}}` after `{}`", name))),
+        }
+
+        let kind = self.parse_word().to_string();
+        match kind.as_str() {
+            "plural" | "selectordinal" => {
+                self.expect(',')?;
+                self.parse_plural(name, kind == "selectordinal")
+            }
+            "select" => {
+                self.expect(',')?;
+                self.parse_select(name, in_plural)
+            }
+            "number" | "date" | "time" | "spellout" | "ordinal" | "duration" => {
+                // The style is not supported, the value is formatted as is
+                while let Some(c) = self.bump() {
+                    match c {
+                        '}' => return Ok(Part::Argument(name)),
+                        '{' => return Err(self.error("unexpected `{` in argument style")),
+                        _ => {}
+                    }
+                }
+                Err(self.error("unclosed `{`"))
+            }
+            "" => Err(self.error("expected argument type")),
+            _ => Err(self.error(&format!("unknown argument type `{}`", kind))),
+        }
+    }
+
+    fn parse_plural(&mut self, name: String, ordinal: bool) -> Result<Part, String> {
+        let mut offset = 0.0;
+
+        self.skip_whitespace();
+        if self.source[self.pos..].starts_with("offset:") {
+            self.pos += "offset:".len();
+            let value = self.parse_word().to_string();
+            offset = value
+                .parse::<f64>()
+                .map_err(|_| self.error(&format!("invalid offset `{}`", value)))?;
+        }
+
+        let mut cases: Vec<(Selector, Vec<Part>)> = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    break;
+                }
+                None => return Err(self.error("expected `}`")),
+                _ => {}
+            }
+
+            let word = self.parse_word().to_string();
+            let selector = match word.strip_prefix('=') {
+                Some(number) => number.parse::<f64>().ok().map(Selector::Exact),
+                None => category(&word).map(Selector::Category),
+            };
+            let Some(selector) = selector else {
+                return Err(self.error(&format!("invalid plural selector `{}`", word)));
+            };
+            if cases.iter().any(|(existing, _)| *existing == selector) {
+                return Err(self.error(&format!("duplicate selector `{}`", word)));
+            }
+
+            self.expect('{')?;
+            let parts = self.parse_message(true, true)?;
+            self.expect('}')?;
+
+            cases.push((selector, parts));
+        }
+
+        if !cases
+            .iter()
+            .any(|(selector, _)| *selector == Selector::Category(PluralCategory::Other))
+        {
+            return Err(self.error(&format!("missing `other` case of `{}`", name)));
+        }
+
+        Ok(Part::Plural {
+            name,
+            ordinal,
+            offset,
+            cases,
+        })
+    }
+
+    fn parse_select(&mut self, name: String, in_plural: bool) -> Result<Part, String> {
+        let mut cases: Vec<(String, Vec<Part>)> = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    break;
+                }
+                None => return Err(self.error("expected `}`")),
+                _ => {}
+            }
+
+            let word = self.parse_word().to_string();
+            if word.is_empty() {
+                return Err(self.error("expected select case"));
+            }
+            if cases.iter().any(|(existing, _)| *existing == word) {
+                return Err(self.error(&format!("duplicate selector `{}`", word)));
+            }
+
+            self.expect('{')?;
+            let parts = self.parse_message(in_plural, true)?;
+            self.expect('}')?;
+
+            cases.push((word, parts));
+        }
+
+        if !cases.iter().any(|(selector, _)| selector == "other") {
+            return Err(self.error(&format!("missing `other` case of `{}`", name)));
+        }
+
+        Ok(Part::Select { name, cases })
+    }
+}
+
+fn category(keyword: &str) -> Option<PluralCategory> {
+    match keyword {
+        "zero" => Some(PluralCategory::Zero),
+        "one" => Some(PluralCategory::One),
+        "two" => Some(PluralCategory::Two),
+        "few" => Some(PluralCategory::Few),
+        "many" => Some(PluralCategory::Many),
+        "other" => Some(PluralCategory::Other),
+        _ => None,
+    }
+}
+
+fn argument<'a>(args: &'a [(&str, String)], name: &str) -> Option<&'a str> {
+    args.iter()
+        .find(|(arg, _)| *arg == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn format_parts(
+    parts: &[Part],
+    locale: &str,
+    args: &[(&str, String)],
+    number: Option<&str>,
+    output: &mut String,
+) {
+    for part in parts {
+        match part {
+            Part::Text(text) => output.push_str(text),
+            Part::Argument(name) => match argument(args, name) {
+                Some(value) => output.push_str(value),
+                None => output.push_str(&format!("{{{}}}", name)),
+            },
+            Part::Number => output.push_str(number.unwrap_or("#")),
+            Part::Plural {
+                name,
+                ordinal,
+                offset,
+                cases,
+            } => {
+                let value = argument(args, name);
+                let number = value
+                    .and_then(|value| value.trim().parse::<f64>().ok())
+                    .map(|value| value - offset);
+
+                let exact = cases.iter().find(|(selector, _)| match selector {
+                    Selector::Exact(exact) => {
+                        number.is_some_and(|number| number + offset == *exact)
+                    }
+                    Selector::Category(_) => false,
+                });
+
+                // The plural category is of the number after the offset, which is also the `#`
+                let number = match (value, number) {
+                    (Some(value), Some(_)) if *offset == 0.0 => value.trim().to_string(),
+                    (_, Some(number)) => number.to_string(),
+                    (_, None) => format!("{{{}}}", name),
+                };
+                let category = match number.parse::<PluralOperands>() {
+                    Ok(operands) if *ordinal => ordinal_category(locale, &operands),
+                    Ok(operands) => plural_category(locale, &operands),
+                    Err(_) => PluralCategory::Other,
+                };
+
+                let case = exact
+                    .or_else(|| {
+                        cases
+                            .iter()
+                            .find(|(selector, _)| *selector == Selector::Category(category))
+                    })
+                    .or_else(|| {
+                        cases.iter().find(|(selector, _)| {
+                            *selector == Selector::Category(PluralCategory::Other)
+                        })
+                    });
+
+                if let Some((_, parts)) = case {
+                    format_parts(parts, locale, args, Some(&number), output);
+                }
+            }
+            Part::Select { name, cases } => {
+                let value = argument(args, name).unwrap_or_default();
+                let case = cases
+                    .iter()
+                    .find(|(selector, _)| selector == value)
+                    .or_else(|| cases.iter().find(|(selector, _)| selector == "other"));
+
+                if let Some((_, parts)) = case {
+                    format_parts(parts, locale, args, number, output);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str, locale: &str, args: &[(&str, &str)]) -> String {
+        let args = args
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect::<Vec<_>>();
+        Message::parse(source)
+            .unwrap()
+            .format(locale, &args)
+            .into_owned()
+    }
+
+    #[test]
+    fn test_format_plural() {
+        let source = "{count, plural, =0 {No files} one {# file} other {# files}}";
+        assert_eq!(format(source, "en", &[("count", "0")]), "No files");
+        assert_eq!(format(source, "en", &[("count", "1")]), "1 file");
+        assert_eq!(format(source, "en", &[("count", "5")]), "5 files");
+        assert_eq!(format(source, "en", &[("count", "1.5")]), "1.5 files");
+        assert_eq!(format(source, "ja", &[("count", "1")]), "1 files");
+        assert_eq!(format(source, "en", &[]), "{count} files");
+
+        let source = "{count, plural, one {# файл} few {# файла} many {# файлов} other {# файла}}";
+        assert_eq!(format(source, "ru", &[("count", "3")]), "3 файла");
+        assert_eq!(format(source, "ru", &[("count", "11")]), "11 файлов");
+        assert_eq!(format(source, "ru", &[("count", "21")]), "21 файл");
+
+        let source = "{count, plural, offset:1 =0 {Nobody} =1 {{name}} one {{name} and # other} other {{name} and # others}}";
+        assert_eq!(
+            format(source, "en", &[("count", "1"), ("name", "Ann")]),
+            "Ann"
+        );
+        assert_eq!(
+            format(source, "en", &[("count", "2"), ("name", "Ann")]),
+            "Ann and 1 other"
+        );
+        assert_eq!(
+            format(source, "en", &[("count", "4"), ("name", "Ann")]),
+            "Ann and 3 others"
+        );
+    }
+
+    #[test]
+    fn test_format_select() {
+        let source = "{gender, select, female {{name} added you to her {count, plural, one {circle} other {# circles}}} male {{name} added you to his circle} other {{name} added you}}";
+        assert_eq!(
+            format(
+                source,
+                "en",
+                &[("gender", "female"), ("name", "Ann"), ("count", "2")]
+            ),
+            "Ann added you to her 2 circles"
+        );
+        assert_eq!(
+            format(source, "en", &[("gender", "male"), ("name", "Bob")]),
+            "Bob added you to his circle"
+        );
+        assert_eq!(format(source, "en", &[("name", "Sam")]), "Sam added you");
+
+        // `#` in a select nested in a plural is the number of the plural
+        let source =
+            "{count, plural, other {{gender, select, female {She has #} other {They have #}}}}";
+        assert_eq!(
+            format(source, "en", &[("count", "3"), ("gender", "female")]),
+            "She has 3"
+        );
+    }
+
+    #[test]
+    fn test_format_selectordinal() {
+        let source = "{n, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}";
+        assert_eq!(format(source, "en", &[("n", "1")]), "1st");
+        assert_eq!(format(source, "en", &[("n", "22")]), "22nd");
+        assert_eq!(format(source, "en", &[("n", "13")]), "13th");
+        assert_eq!(format(source, "en", &[("n", "103")]), "103rd");
+    }
+
+    #[test]
+    fn test_format_quoting() {
+        assert_eq!(format("It''s {name}", "en", &[("name", "me")]), "It's me");
+        assert_eq!(
+            format("'{name}' is {name}", "en", &[("name", "x")]),
+            "{name} is x"
+        );
+        assert_eq!(format("Don't", "en", &[]), "Don't");
+        assert_eq!(
+            format("{n, plural, other {'#' is #}}", "en", &[("n", "2")]),
+            "# is 2"
+        );
+        assert_eq!(format("# is literal", "en", &[]), "# is literal");
+        assert_eq!(format("{n, number} items", "en", &[("n", "3")]), "3 items");
+    }
+
+    #[test]
+    fn test_format_borrowed() {
+        let message = Message::parse("Hello").unwrap();
+        assert!(matches!(message.format("en", &[]), Cow::Borrowed("Hello")));
+        assert_eq!(message.source(), "Hello");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |source: &str| Message::parse(source).unwrap_err();
+
+        assert_eq!(
+            error("Hello {name"),
+            "expected `,` or `}` after `name` at position 11"
+        );
+        assert_eq!(error("Hello }"), "unmatched `}` at position 6");
+        assert_eq!(error("{}"), "expected argument name at position 1");
+        assert_eq!(
+            error("{count, plural, one {# file}}"),
+            "missing `other` case of `count` at position 29"
+        );
+        assert_eq!(
+            error("{count, plural, single {x} other {y}}"),
+            "invalid plural selector `single` at position 22"
+        );
+        assert_eq!(
+            error("{count, plural, one {x} one {y} other {z}}"),
+            "duplicate selector `one` at position 27"
+        );
+        assert_eq!(
+            error("{gender, select, male {x}}"),
+            "missing `other` case of `gender` at position 26"
+        );
+        assert_eq!(
+            error("{count, plural, other {# files}"),
+            "expected `}` at position 31"
+        );
+        assert_eq!(
+            error("{count, plural, other {# files"),
+            "unclosed `{` at position 30"
+        );
+        assert_eq!(
+            error("{count, currency}"),
+            "unknown argument type `currency` at position 16"
+        );
+    }
+}
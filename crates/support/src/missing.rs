@@ -0,0 +1,74 @@
+use arc_swap::ArcSwapOption;
+use once_cell::sync::Lazy;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// A handler of missing translations, called with the locale, the key and the fallback chain
+/// that was tried, returns the text to use instead of the key.
+pub type MissingHandler = dyn Fn(&str, &str, &[String]) -> Option<String> + Send + Sync;
+
+static MISSING_HANDLER: Lazy<ArcSwapOption<Box<MissingHandler>>> =
+    Lazy::new(|| ArcSwapOption::from(None));
+
+/// Register a global handler of missing translations, it replaces the previous one.
+///
+/// The handler runs when a key is missing in all locales of the fallback chain, including the
+/// translations of the `backend` of `i18n!`. It can log, count or panic, and returns the text
+/// of `t!`, or `None` for the key itself.
+///
+/// ```ignore
+/// rust_i18n::set_missing_handler(|locale, key, _chain| {
+///     log::warn!("missing translation `{}` in {}", key, locale);
+///     Some(format!("[MISSING: {}]", key))
+/// });
+/// ```
+pub fn set_missing_handler<F>(handler: F)
+where
+    F: Fn(&str, &str, &[String]) -> Option<String> + Send + Sync + 'static,
+{
+    MISSING_HANDLER.store(Some(Arc::new(Box::new(handler))));
+}
+
+/// Remove the handler of missing translations, `t!` returns the key of a missing translation.
+pub fn clear_missing_handler() {
+    MISSING_HANDLER.store(None);
+}
+
+/// Get the text of a missing translation, from the handler or the key.
+#[doc(hidden)]
+pub fn handle_missing(locale: &str, key: &str, chain: &[String]) -> Cow<'static, str> {
+    let handler = MISSING_HANDLER.load();
+    match handler
+        .as_ref()
+        .and_then(|handler| handler(locale, key, chain))
+    {
+        Some(text) => Cow::Owned(text),
+        None => Cow::Owned(key.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_handler() {
+        let chain = ["x-missing".to_string(), "en".to_string()];
+        assert_eq!(handle_missing("x-missing", "foo", &chain), "foo");
+
+        set_missing_handler(|locale, key, chain| {
+            if locale != "x-missing" {
+                return None;
+            }
+            Some(format!("[MISSING: {} in {}]", key, chain.join(", ")))
+        });
+        assert_eq!(
+            handle_missing("x-missing", "foo", &chain),
+            "[MISSING: foo in x-missing, en]"
+        );
+        assert_eq!(handle_missing("en", "foo", &["en".to_string()]), "foo");
+
+        clear_missing_handler();
+        assert_eq!(handle_missing("x-missing", "foo", &chain), "foo");
+    }
+}
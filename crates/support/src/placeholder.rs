@@ -0,0 +1,421 @@
+use std::borrow::Cow;
+use std::fmt::{Binary, Debug, Display, LowerExp, LowerHex, Octal, UpperExp, UpperHex};
+
+/// An argument of `t!`, with the formatting traits it implements.
+///
+/// Created by `t!` for each named argument, so `%{name:spec}` placeholders can use the
+/// `Display`, `Debug` or numeric formatting of the argument.
+#[derive(Clone, Copy, Default)]
+pub struct FormatArg<'a> {
+    pub display: Option<&'a dyn Display>,
+    pub debug: Option<&'a dyn Debug>,
+    pub lower_hex: Option<&'a dyn LowerHex>,
+    pub upper_hex: Option<&'a dyn UpperHex>,
+    pub octal: Option<&'a dyn Octal>,
+    pub binary: Option<&'a dyn Binary>,
+    pub lower_exp: Option<&'a dyn LowerExp>,
+    pub upper_exp: Option<&'a dyn UpperExp>,
+}
+
+impl Display for FormatArg<'_> {
+    /// Format with `Display`, or `Debug` if the argument only implements it.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.display, self.debug) {
+            (Some(display), _) => Display::fmt(display, f),
+            (None, Some(debug)) => Debug::fmt(debug, f),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Detect the formatting traits of an argument by autoref specialization, used by `t!`.
+///
+/// `(&&FormatWrap(value)).display_arg()` picks the impl for `&FormatWrap<T>` if `T: Display`,
+/// otherwise the impl for `FormatWrap<T>` which returns `None`.
+#[doc(hidden)]
+pub mod autoref {
+    use super::*;
+
+    pub struct FormatWrap<'a, T>(pub &'a T);
+
+    macro_rules! impl_autoref {
+        ($($fmt:ident => $supported:ident, $unsupported:ident, $method:ident;)*) => {
+            $(
+                pub trait $supported<'a> {
+                    fn $method(&self) -> Option<&'a dyn $fmt>;
+                }
+
+                impl<'a, T: $fmt> $supported<'a> for &FormatWrap<'a, T> {
+                    fn $method(&self) -> Option<&'a dyn $fmt> {
+                        Some(self.0)
+                    }
+                }
+
+                pub trait $unsupported<'a> {
+                    fn $method(&self) -> Option<&'a dyn $fmt>;
+                }
+
+                impl<'a, T> $unsupported<'a> for FormatWrap<'a, T> {
+                    fn $method(&self) -> Option<&'a dyn $fmt> {
+                        None
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_autoref! {
+        Display => DisplayArg, NoDisplayArg, display_arg;
+        Debug => DebugArg, NoDebugArg, debug_arg;
+        LowerHex => LowerHexArg, NoLowerHexArg, lower_hex_arg;
+        UpperHex => UpperHexArg, NoUpperHexArg, upper_hex_arg;
+        Octal => OctalArg, NoOctalArg, octal_arg;
+        Binary => BinaryArg, NoBinaryArg, binary_arg;
+        LowerExp => LowerExpArg, NoLowerExpArg, lower_exp_arg;
+        UpperExp => UpperExpArg, NoUpperExpArg, upper_exp_arg;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Display,
+    Debug,
+    LowerHex,
+    UpperHex,
+    Octal,
+    Binary,
+    LowerExp,
+    UpperExp,
+}
+
+/// A Rust format spec `[[fill]align][sign]['#']['0'][width]['.' precision][type]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FormatSpec {
+    fill: char,
+    align: Option<Align>,
+    plus: bool,
+    alternate: bool,
+    zero: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+    kind: Kind,
+}
+
+impl FormatSpec {
+    fn parse(spec: &str) -> Option<Self> {
+        let mut result = Self {
+            fill: ' ',
+            align: None,
+            plus: false,
+            alternate: false,
+            zero: false,
+            width: None,
+            precision: None,
+            kind: Kind::Display,
+        };
+
+        let align = |c: char| match c {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        };
+
+        let mut chars = spec.chars().peekable();
+        let mut lookahead = spec.chars().skip(1);
+        match (chars.peek().copied(), lookahead.next().and_then(align)) {
+            (Some(fill), Some(align)) => {
+                result.fill = fill;
+                result.align = Some(align);
+                chars.next();
+                chars.next();
+            }
+            (Some(c), None) if align(c).is_some() => {
+                result.align = align(c);
+                chars.next();
+            }
+            _ => {}
+        }
+
+        // The `-` flag is unused by Rust, so it's an invalid spec here
+        if chars.next_if_eq(&'+').is_some() {
+            result.plus = true;
+        }
+        if chars.next_if_eq(&'#').is_some() {
+            result.alternate = true;
+        }
+        if chars.next_if_eq(&'0').is_some() {
+            result.zero = true;
+        }
+
+        let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut digits = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                digits.push(c);
+            }
+            digits.parse::<usize>().ok()
+        };
+        result.width = number(&mut chars);
+        if chars.next_if_eq(&'.').is_some() {
+            result.precision = Some(number(&mut chars)?);
+        }
+
+        result.kind = match chars.collect::<String>().as_str() {
+            "" => Kind::Display,
+            "?" => Kind::Debug,
+            "x" => Kind::LowerHex,
+            "X" => Kind::UpperHex,
+            "o" => Kind::Octal,
+            "b" => Kind::Binary,
+            "e" => Kind::LowerExp,
+            "E" => Kind::UpperExp,
+            _ => return None,
+        };
+
+        Some(result)
+    }
+}
+
+impl FormatArg<'_> {
+    /// Format with a spec, the kind of formatting falls back to `Display` if the argument doesn't implement it.
+    fn format(&self, spec: &FormatSpec) -> String {
+        // Only numbers implement `LowerExp`, they are right aligned and can have a sign
+        let numeric = self.lower_exp.is_some();
+
+        let mut body = self
+            .render(spec, spec.kind)
+            .or_else(|| self.render(spec, Kind::Display))
+            .or_else(|| self.render(spec, Kind::Debug))
+            .unwrap_or_default();
+
+        if spec.plus && numeric && !body.starts_with('-') {
+            body.insert(0, '+');
+        }
+
+        let len = body.chars().count();
+        let Some(width) = spec.width.filter(|width| *width > len) else {
+            return body;
+        };
+        let padding = width - len;
+
+        if spec.zero && numeric {
+            // Zeros go after the sign and the `0x` prefix
+            let mut prefix_len = body.starts_with(['+', '-']) as usize;
+            if spec.alternate && body[prefix_len..].starts_with('0') {
+                if let Some(c) = body[prefix_len..].chars().nth(1) {
+                    if matches!(c, 'x' | 'X' | 'o' | 'b') {
+                        prefix_len += 2;
+                    }
+                }
+            }
+            body.insert_str(prefix_len, &"0".repeat(padding));
+            return body;
+        }
+
+        let fill = |n: usize| spec.fill.to_string().repeat(n);
+        let align = spec
+            .align
+            .unwrap_or(if numeric { Align::Right } else { Align::Left });
+        match align {
+            Align::Left => body + &fill(padding),
+            Align::Right => fill(padding) + &body,
+            Align::Center => fill(padding / 2) + &body + &fill(padding - padding / 2),
+        }
+    }
+
+    fn render(&self, spec: &FormatSpec, kind: Kind) -> Option<String> {
+        let (alternate, precision) = (spec.alternate, spec.precision);
+
+        let result = match kind {
+            Kind::Display => {
+                let value = self.display?;
+                match precision {
+                    Some(precision) => format!("{:.*}", precision, value),
+                    None => format!("{}", value),
+                }
+            }
+            Kind::Debug => {
+                let value = self.debug?;
+                match (alternate, precision) {
+                    (true, Some(precision)) => format!("{:#.*?}", precision, value),
+                    (true, None) => format!("{:#?}", value),
+                    (false, Some(precision)) => format!("{:.*?}", precision, value),
+                    (false, None) => format!("{:?}", value),
+                }
+            }
+            Kind::LowerHex if alternate => format!("{:#x}", self.lower_hex?),
+            Kind::LowerHex => format!("{:x}", self.lower_hex?),
+            Kind::UpperHex if alternate => format!("{:#X}", self.upper_hex?),
+            Kind::UpperHex => format!("{:X}", self.upper_hex?),
+            Kind::Octal if alternate => format!("{:#o}", self.octal?),
+            Kind::Octal => format!("{:o}", self.octal?),
+            Kind::Binary if alternate => format!("{:#b}", self.binary?),
+            Kind::Binary => format!("{:b}", self.binary?),
+            Kind::LowerExp => {
+                let value = self.lower_exp?;
+                match precision {
+                    Some(precision) => format!("{:.*e}", precision, value),
+                    None => format!("{:e}", value),
+                }
+            }
+            Kind::UpperExp => {
+                let value = self.upper_exp?;
+                match precision {
+                    Some(precision) => format!("{:.*E}", precision, value),
+                    None => format!("{:E}", value),
+                }
+            }
+        };
+
+        Some(result)
+    }
+}
+
+/// Replace the `%{name}` and `%{name:spec}` placeholders of a message with the arguments, e.g. `%{price:.2}`.
+///
+/// `%%{` is a literal `%{`. Placeholders without an argument, or with an invalid spec, are kept as is.
+/// The message is returned without copying if it has no placeholders.
+pub fn format_placeholders<'a>(message: Cow<'a, str>, args: &[(&str, FormatArg)]) -> Cow<'a, str> {
+    if !message.contains("%{") {
+        return message;
+    }
+
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message.as_ref();
+
+    while let Some(pos) = rest.find("%{") {
+        // `%%{` is an escaped `%{`
+        if rest[..pos].ends_with('%') {
+            result.push_str(&rest[..pos - 1]);
+            result.push_str("%{");
+            rest = &rest[pos + 2..];
+            continue;
+        }
+
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        let placeholder = &rest[2..end];
+        let (name, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let arg = args.iter().find(|(arg, _)| *arg == name);
+
+        match (arg, FormatSpec::parse(spec)) {
+            (Some((_, arg)), Some(spec)) => result.push_str(&arg.format(&spec)),
+            _ => result.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    Cow::Owned(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::autoref::*;
+    use super::*;
+
+    macro_rules! arg {
+        ($value:expr) => {
+            FormatArg {
+                display: (&&FormatWrap($value)).display_arg(),
+                debug: (&&FormatWrap($value)).debug_arg(),
+                lower_hex: (&&FormatWrap($value)).lower_hex_arg(),
+                upper_hex: (&&FormatWrap($value)).upper_hex_arg(),
+                octal: (&&FormatWrap($value)).octal_arg(),
+                binary: (&&FormatWrap($value)).binary_arg(),
+                lower_exp: (&&FormatWrap($value)).lower_exp_arg(),
+                upper_exp: (&&FormatWrap($value)).upper_exp_arg(),
+            }
+        };
+    }
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct DebugOnly {
+        id: u32,
+    }
+
+    fn format(message: &str, args: &[(&str, FormatArg)]) -> String {
+        format_placeholders(Cow::Borrowed(message), args).into_owned()
+    }
+
+    #[test]
+    fn test_autoref() {
+        let arg = arg!(&42);
+        assert!(arg.display.is_some());
+        assert!(arg.lower_hex.is_some());
+
+        let arg = arg!(&"foo");
+        assert!(arg.display.is_some());
+        assert!(arg.lower_hex.is_none());
+        assert!(arg.lower_exp.is_none());
+
+        let value = DebugOnly { id: 1 };
+        let arg = arg!(&value);
+        assert!(arg.display.is_none());
+        assert!(arg.debug.is_some());
+        assert_eq!(arg.to_string(), "DebugOnly { id: 1 }");
+    }
+
+    #[test]
+    fn test_format_spec() {
+        let price = 12.3456;
+        let id = 255;
+        let name = "Jason";
+        let args = [("price", arg!(&price)), ("id", arg!(&id)), ("name", arg!(&name))];
+
+        assert_eq!(format("%{price:.2}", &args), "12.35");
+        assert_eq!(format("%{price:8.2}|", &args), "   12.35|");
+        assert_eq!(format("%{price:+.1}", &args), "+12.3");
+        assert_eq!(format("%{price:e}", &args), "1.23456e1");
+        assert_eq!(format("%{id:>8}|", &args), "     255|");
+        assert_eq!(format("%{id:<8}|", &args), "255     |");
+        assert_eq!(format("%{id:*^9}", &args), "***255***");
+        assert_eq!(format("%{id:08}", &args), "00000255");
+        assert_eq!(format("%{id:x} %{id:X} %{id:o} %{id:b}", &args), "ff FF 377 11111111");
+        assert_eq!(format("%{id:#06x}", &args), "0x00ff");
+        assert_eq!(format("%{name:?}", &args), "\"Jason\"");
+        assert_eq!(format("%{name:>7}", &args), "  Jason");
+        assert_eq!(format("%{name:.3}", &args), "Jas");
+        assert_eq!(format("%{name:8}|", &args), "Jason   |");
+
+        // Unsupported kinds fall back to `Display`
+        assert_eq!(format("%{name:x}", &args), "Jason");
+
+        let value = DebugOnly { id: 1 };
+        let args = [("value", arg!(&value))];
+        assert_eq!(format("%{value}", &args), "DebugOnly { id: 1 }");
+        assert_eq!(format("%{value:#?}", &args), "DebugOnly {\n    id: 1,\n}");
+    }
+
+    #[test]
+    fn test_format_placeholders() {
+        let args = [("name", arg!(&"Jason")), ("count", arg!(&2))];
+
+        assert_eq!(
+            format("Hello %{name}, %{count} messages", &args),
+            "Hello Jason, 2 messages"
+        );
+        assert_eq!(format("%%{name} is %{name}", &args), "%{name} is Jason");
+        assert_eq!(format("100%% %{count}", &args), "100%% 2");
+        assert_eq!(format("%{missing} %{name}", &args), "%{missing} Jason");
+        assert_eq!(format("%{name:!!} %{name}", &args), "%{name:!!} Jason");
+        assert_eq!(format("%{count:-5} %{name}", &args), "%{count:-5} Jason");
+        assert_eq!(format("%{name", &args), "%{name");
+
+        let message = format_placeholders(Cow::Borrowed("Hello"), &args);
+        assert!(matches!(message, Cow::Borrowed("Hello")));
+    }
+}
@@ -0,0 +1,689 @@
+use crate::fallback::truncate_locale;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// CLDR plural category
+///
+/// https://unicode.org/reports/tr35/tr35-numbers.html#Language_Plural_Rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// The sub-key name used in translation files, e.g. `one`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// Plural operands of a number, parsed from its decimal representation.
+///
+/// https://unicode.org/reports/tr35/tr35-numbers.html#Operands
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PluralOperands {
+    /// Absolute value of the source number.
+    pub n: f64,
+    /// Integer digits of n.
+    pub i: u64,
+    /// Number of visible fraction digits in n, with trailing zeros.
+    pub v: usize,
+    /// Number of visible fraction digits in n, without trailing zeros.
+    pub w: usize,
+    /// Visible fraction digits in n, with trailing zeros.
+    pub f: u64,
+    /// Visible fraction digits in n, without trailing zeros.
+    pub t: u64,
+}
+
+impl FromStr for PluralOperands {
+    type Err = String;
+
+    /// Parse operands from a plain decimal like `1`, `-3` or `1.50`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let abs = s.trim().trim_start_matches(['-', '+']);
+        let (int_part, frac_part) = abs.split_once('.').unwrap_or((abs, ""));
+
+        let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if int_part.is_empty() || !is_digits(int_part) || !is_digits(frac_part) {
+            return Err(format!("Invalid plural count: {}", s));
+        }
+
+        let n = abs
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid plural count: {}", s))?;
+        let i = int_part.parse::<u64>().unwrap_or(u64::MAX);
+        let frac_trimmed = frac_part.trim_end_matches('0');
+
+        Ok(Self {
+            n,
+            i,
+            v: frac_part.len(),
+            w: frac_trimmed.len(),
+            f: frac_part.parse::<u64>().unwrap_or(0),
+            t: frac_trimmed.parse::<u64>().unwrap_or(0),
+        })
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for PluralOperands {
+                fn from(n: $ty) -> Self {
+                    let i = n.unsigned_abs() as u64;
+                    Self { n: i as f64, i, ..Default::default() }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, isize);
+
+macro_rules! impl_from_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for PluralOperands {
+                fn from(n: $ty) -> Self {
+                    Self { n: n as f64, i: n as u64, ..Default::default() }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_unsigned!(u8, u16, u32, u64, usize);
+
+/// A plural rule, returns the category of the given operands.
+pub type PluralRule = fn(&PluralOperands) -> PluralCategory;
+
+static CUSTOM_RULES: Lazy<RwLock<HashMap<String, PluralRule>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Register a plural rule for a locale, this takes precedence over the built-in CLDR rules.
+///
+/// ```ignore
+/// rust_i18n::add_plural_rule("x-pirate", |ops| {
+///     if ops.i == 1 { PluralCategory::One } else { PluralCategory::Other }
+/// });
+/// ```
+pub fn add_plural_rule(locale: &str, rule: PluralRule) {
+    let mut rules = CUSTOM_RULES.write().unwrap();
+    rules.insert(locale.to_string(), rule);
+}
+
+/// Get the CLDR plural category of `operands` in `locale`.
+///
+/// Lookup the custom rules by the locale and its truncated tags (`zh-Hant-TW`, `zh-Hant`, `zh`),
+/// before using the built-in rules.
+pub fn plural_category(locale: &str, operands: &PluralOperands) -> PluralCategory {
+    {
+        let rules = CUSTOM_RULES.read().unwrap();
+        for tag in truncate_locale(locale) {
+            if let Some(rule) = rules.get(tag) {
+                return rule(operands);
+            }
+        }
+    }
+
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+    builtin_rule(locale, language)(operands)
+}
+
+/// Get the CLDR ordinal category of `operands` in `locale`, e.g. `one` for `1st` and `two` for `2nd` in English.
+pub fn ordinal_category(locale: &str, operands: &PluralOperands) -> PluralCategory {
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+    builtin_ordinal_rule(language)(operands)
+}
+
+/// Candidate keys to lookup for a pluralized `key`, in order of priority.
+///
+/// An explicit `zero` form always wins for `0`, then comes the locale's category and `other`,
+/// and finally the bare `key` for translations without plural forms.
+pub fn plural_keys(locale: &str, key: &str, operands: &PluralOperands) -> Vec<String> {
+    let category = plural_category(locale, operands);
+
+    let mut keys = Vec::with_capacity(4);
+    if operands.n == 0.0 {
+        keys.push(format!("{}.zero", key));
+    }
+
+    for category in [category, PluralCategory::Other] {
+        let candidate = format!("{}.{}", key, category.as_str());
+        if !keys.contains(&candidate) {
+            keys.push(candidate);
+        }
+    }
+    keys.push(key.to_string());
+
+    keys
+}
+
+fn builtin_rule(locale: &str, language: &str) -> PluralRule {
+    match language {
+        "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" | "lo" | "my" | "km" | "yue" | "jv"
+        | "bo" | "dz" | "ig" | "yo" | "wo" | "to" | "sg" => rule_other,
+        "en" | "de" | "nl" | "sv" | "it" | "fi" | "et" | "ca" | "gl" | "ur" | "sw" | "fy"
+        | "ia" | "io" | "ji" | "yi" | "sc" => rule_one_integer,
+        "tr" | "el" | "hu" | "bg" | "nb" | "no" | "nn" | "es" | "az" | "ka" | "kk" | "ky"
+        | "uz" | "sq" | "eu" | "ta" | "te" | "ml" | "mn" | "ne" | "ps" | "so" | "tk" | "ug"
+        | "af" | "lb" | "rm" | "or" | "ast" => rule_one,
+        "pt" if is_portugal(locale) => rule_one_integer,
+        "fr" | "pt" | "hy" | "kab" | "ff" => rule_zero_one,
+        "hi" | "bn" | "fa" | "gu" | "kn" | "mr" | "zu" | "am" | "as" => rule_hindi,
+        "da" => rule_danish,
+        "is" => rule_icelandic,
+        "mk" => rule_macedonian,
+        "fil" | "tl" => rule_filipino,
+        "ru" | "uk" => rule_russian,
+        "be" => rule_belarusian,
+        "pl" => rule_polish,
+        "cs" | "sk" => rule_czech,
+        "hr" | "sr" | "bs" | "sh" => rule_croatian,
+        "lt" => rule_lithuanian,
+        "lv" | "prg" => rule_latvian,
+        "ro" | "mo" => rule_romanian,
+        "sl" => rule_slovenian,
+        "he" | "iw" => rule_hebrew,
+        "ar" | "ars" => rule_arabic,
+        "ga" => rule_irish,
+        "cy" => rule_welsh,
+        _ => rule_one_integer,
+    }
+}
+
+fn builtin_ordinal_rule(language: &str) -> PluralRule {
+    match language {
+        "en" => ordinal_english,
+        "fr" | "fil" | "tl" | "ga" | "hy" | "lo" | "mo" | "ms" | "ro" | "vi" => rule_one,
+        "sv" => ordinal_swedish,
+        "it" | "sc" => ordinal_italian,
+        "ca" => ordinal_catalan,
+        "hu" => ordinal_hungarian,
+        "mk" => ordinal_macedonian,
+        "cy" => ordinal_welsh,
+        _ => rule_other,
+    }
+}
+
+fn is_portugal(locale: &str) -> bool {
+    locale
+        .split(['-', '_'])
+        .skip(1)
+        .any(|part| part.eq_ignore_ascii_case("pt"))
+}
+
+/// `n` is an integer in `start..=end`.
+fn in_range(n: f64, start: u64, end: u64) -> bool {
+    n.fract() == 0.0 && n >= start as f64 && n <= end as f64
+}
+
+fn rule_other(_: &PluralOperands) -> PluralCategory {
+    PluralCategory::Other
+}
+
+// one: i = 1 and v = 0
+fn rule_one_integer(o: &PluralOperands) -> PluralCategory {
+    if o.i == 1 && o.v == 0 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: n = 1
+fn rule_one(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: i = 0,1
+fn rule_zero_one(o: &PluralOperands) -> PluralCategory {
+    if o.i <= 1 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: i = 0 or n = 1
+fn rule_hindi(o: &PluralOperands) -> PluralCategory {
+    if o.i == 0 || o.n == 1.0 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: n = 1 or t != 0 and i = 0,1
+fn rule_danish(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 || (o.t != 0 && o.i <= 1) {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: t = 0 and i % 10 = 1 and i % 100 != 11 or t % 10 = 1 and t % 100 != 11
+fn rule_icelandic(o: &PluralOperands) -> PluralCategory {
+    if (o.t == 0 && o.i % 10 == 1 && o.i % 100 != 11) || (o.t % 10 == 1 && o.t % 100 != 11) {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: v = 0 and i % 10 = 1 and i % 100 != 11 or f % 10 = 1 and f % 100 != 11
+fn rule_macedonian(o: &PluralOperands) -> PluralCategory {
+    if (o.v == 0 && o.i % 10 == 1 && o.i % 100 != 11) || (o.f % 10 == 1 && o.f % 100 != 11) {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: v = 0 and i = 1,2,3 or v = 0 and i % 10 != 4,6,9 or v != 0 and f % 10 != 4,6,9
+fn rule_filipino(o: &PluralOperands) -> PluralCategory {
+    let not_469 = |x: u64| !matches!(x % 10, 4 | 6 | 9);
+    if (o.v == 0 && ((1..=3).contains(&o.i) || not_469(o.i))) || (o.v != 0 && not_469(o.f)) {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_russian(o: &PluralOperands) -> PluralCategory {
+    if o.v != 0 {
+        return PluralCategory::Other;
+    }
+
+    match (o.i % 10, o.i % 100) {
+        (1, i100) if i100 != 11 => PluralCategory::One,
+        (2..=4, i100) if !(12..=14).contains(&i100) => PluralCategory::Few,
+        _ => PluralCategory::Many,
+    }
+}
+
+fn rule_belarusian(o: &PluralOperands) -> PluralCategory {
+    let (n10, n100) = (o.n % 10.0, o.n % 100.0);
+    if n10 == 1.0 && n100 != 11.0 {
+        PluralCategory::One
+    } else if in_range(n10, 2, 4) && !in_range(n100, 12, 14) {
+        PluralCategory::Few
+    } else if n10 == 0.0 || in_range(n10, 5, 9) || in_range(n100, 11, 14) {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_polish(o: &PluralOperands) -> PluralCategory {
+    if o.v != 0 {
+        return PluralCategory::Other;
+    }
+
+    let (i10, i100) = (o.i % 10, o.i % 100);
+    if o.i == 1 {
+        PluralCategory::One
+    } else if (2..=4).contains(&i10) && !(12..=14).contains(&i100) {
+        PluralCategory::Few
+    } else {
+        PluralCategory::Many
+    }
+}
+
+fn rule_czech(o: &PluralOperands) -> PluralCategory {
+    if o.v != 0 {
+        PluralCategory::Many
+    } else if o.i == 1 {
+        PluralCategory::One
+    } else if (2..=4).contains(&o.i) {
+        PluralCategory::Few
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_croatian(o: &PluralOperands) -> PluralCategory {
+    let (i10, i100, f10, f100) = (o.i % 10, o.i % 100, o.f % 10, o.f % 100);
+    if (o.v == 0 && i10 == 1 && i100 != 11) || (f10 == 1 && f100 != 11) {
+        PluralCategory::One
+    } else if (o.v == 0 && (2..=4).contains(&i10) && !(12..=14).contains(&i100))
+        || ((2..=4).contains(&f10) && !(12..=14).contains(&f100))
+    {
+        PluralCategory::Few
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_lithuanian(o: &PluralOperands) -> PluralCategory {
+    let (n10, n100) = (o.n % 10.0, o.n % 100.0);
+    if n10 == 1.0 && !in_range(n100, 11, 19) {
+        PluralCategory::One
+    } else if in_range(n10, 2, 9) && !in_range(n100, 11, 19) {
+        PluralCategory::Few
+    } else if o.f != 0 {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_latvian(o: &PluralOperands) -> PluralCategory {
+    let (n10, n100, f10, f100) = (o.n % 10.0, o.n % 100.0, o.f % 10, o.f % 100);
+    if n10 == 0.0 || in_range(n100, 11, 19) || (o.v == 2 && (11..=19).contains(&f100)) {
+        PluralCategory::Zero
+    } else if (n10 == 1.0 && n100 != 11.0) || (f10 == 1 && (o.v != 2 || f100 != 11)) {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_romanian(o: &PluralOperands) -> PluralCategory {
+    if o.i == 1 && o.v == 0 {
+        PluralCategory::One
+    } else if o.v != 0 || o.n == 0.0 || (o.n != 1.0 && in_range(o.n % 100.0, 1, 19)) {
+        PluralCategory::Few
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_slovenian(o: &PluralOperands) -> PluralCategory {
+    if o.v != 0 {
+        return PluralCategory::Few;
+    }
+
+    match o.i % 100 {
+        1 => PluralCategory::One,
+        2 => PluralCategory::Two,
+        3 | 4 => PluralCategory::Few,
+        _ => PluralCategory::Other,
+    }
+}
+
+fn rule_hebrew(o: &PluralOperands) -> PluralCategory {
+    if (o.i == 1 && o.v == 0) || (o.i == 0 && o.v != 0) {
+        PluralCategory::One
+    } else if o.i == 2 && o.v == 0 {
+        PluralCategory::Two
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_arabic(o: &PluralOperands) -> PluralCategory {
+    let n100 = o.n % 100.0;
+    if o.n == 0.0 {
+        PluralCategory::Zero
+    } else if o.n == 1.0 {
+        PluralCategory::One
+    } else if o.n == 2.0 {
+        PluralCategory::Two
+    } else if in_range(n100, 3, 10) {
+        PluralCategory::Few
+    } else if in_range(n100, 11, 99) {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_irish(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 {
+        PluralCategory::One
+    } else if o.n == 2.0 {
+        PluralCategory::Two
+    } else if in_range(o.n, 3, 6) {
+        PluralCategory::Few
+    } else if in_range(o.n, 7, 10) {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn rule_welsh(o: &PluralOperands) -> PluralCategory {
+    if o.n == 0.0 {
+        PluralCategory::Zero
+    } else if o.n == 1.0 {
+        PluralCategory::One
+    } else if o.n == 2.0 {
+        PluralCategory::Two
+    } else if o.n == 3.0 {
+        PluralCategory::Few
+    } else if o.n == 6.0 {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn ordinal_english(o: &PluralOperands) -> PluralCategory {
+    let (n10, n100) = (o.n % 10.0, o.n % 100.0);
+    if n10 == 1.0 && n100 != 11.0 {
+        PluralCategory::One
+    } else if n10 == 2.0 && n100 != 12.0 {
+        PluralCategory::Two
+    } else if n10 == 3.0 && n100 != 13.0 {
+        PluralCategory::Few
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: n % 10 = 1,2 and n % 100 != 11,12
+fn ordinal_swedish(o: &PluralOperands) -> PluralCategory {
+    if in_range(o.n % 10.0, 1, 2) && !in_range(o.n % 100.0, 11, 12) {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// many: n = 11,8,80,800
+fn ordinal_italian(o: &PluralOperands) -> PluralCategory {
+    if [11.0, 8.0, 80.0, 800.0].contains(&o.n) {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn ordinal_catalan(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 || o.n == 3.0 {
+        PluralCategory::One
+    } else if o.n == 2.0 {
+        PluralCategory::Two
+    } else if o.n == 4.0 {
+        PluralCategory::Few
+    } else {
+        PluralCategory::Other
+    }
+}
+
+// one: n = 1,5
+fn ordinal_hungarian(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 || o.n == 5.0 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn ordinal_macedonian(o: &PluralOperands) -> PluralCategory {
+    let (i10, i100) = (o.i % 10, o.i % 100);
+    if i10 == 1 && i100 != 11 {
+        PluralCategory::One
+    } else if i10 == 2 && i100 != 12 {
+        PluralCategory::Two
+    } else if (i10 == 7 || i10 == 8) && i100 != 17 && i100 != 18 {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+fn ordinal_welsh(o: &PluralOperands) -> PluralCategory {
+    if [0.0, 7.0, 8.0, 9.0].contains(&o.n) {
+        PluralCategory::Zero
+    } else if o.n == 1.0 {
+        PluralCategory::One
+    } else if o.n == 2.0 {
+        PluralCategory::Two
+    } else if o.n == 3.0 || o.n == 4.0 {
+        PluralCategory::Few
+    } else if o.n == 5.0 || o.n == 6.0 {
+        PluralCategory::Many
+    } else {
+        PluralCategory::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(locale: &str, n: &str) -> PluralCategory {
+        plural_category(locale, &n.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse_operands() {
+        let ops: PluralOperands = "-12.340".parse().unwrap();
+        assert_eq!(ops.n, 12.34);
+        assert_eq!(ops.i, 12);
+        assert_eq!(ops.v, 3);
+        assert_eq!(ops.w, 2);
+        assert_eq!(ops.f, 340);
+        assert_eq!(ops.t, 34);
+
+        assert_eq!(
+            "5".parse::<PluralOperands>().unwrap(),
+            PluralOperands::from(5)
+        );
+        assert_eq!(PluralOperands::from(-3i32).i, 3);
+
+        "foo".parse::<PluralOperands>().expect_err("Should error");
+        "1.2.3".parse::<PluralOperands>().expect_err("Should error");
+        "".parse::<PluralOperands>().expect_err("Should error");
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        assert_eq!(category("en", "1"), PluralCategory::One);
+        assert_eq!(category("en-US", "1.0"), PluralCategory::Other);
+        assert_eq!(category("en", "0"), PluralCategory::Other);
+        assert_eq!(category("ja", "1"), PluralCategory::Other);
+
+        assert_eq!(category("tr-TR", "1"), PluralCategory::One);
+        assert_eq!(category("tr-TR", "2"), PluralCategory::Other);
+
+        assert_eq!(category("fr", "0"), PluralCategory::One);
+        assert_eq!(category("fr", "1.5"), PluralCategory::One);
+        assert_eq!(category("pt-BR", "0"), PluralCategory::One);
+        assert_eq!(category("pt-PT", "0"), PluralCategory::Other);
+
+        assert_eq!(category("ru", "1"), PluralCategory::One);
+        assert_eq!(category("ru", "21"), PluralCategory::One);
+        assert_eq!(category("ru", "11"), PluralCategory::Many);
+        assert_eq!(category("ru", "3"), PluralCategory::Few);
+        assert_eq!(category("ru", "14"), PluralCategory::Many);
+        assert_eq!(category("ru", "25"), PluralCategory::Many);
+        assert_eq!(category("ru", "1.5"), PluralCategory::Other);
+
+        assert_eq!(category("pl", "1"), PluralCategory::One);
+        assert_eq!(category("pl", "22"), PluralCategory::Few);
+        assert_eq!(category("pl", "12"), PluralCategory::Many);
+        assert_eq!(category("pl", "21"), PluralCategory::Many);
+        assert_eq!(category("pl", "0.5"), PluralCategory::Other);
+
+        assert_eq!(category("ar", "0"), PluralCategory::Zero);
+        assert_eq!(category("ar", "1"), PluralCategory::One);
+        assert_eq!(category("ar", "2"), PluralCategory::Two);
+        assert_eq!(category("ar", "103"), PluralCategory::Few);
+        assert_eq!(category("ar", "11"), PluralCategory::Many);
+        assert_eq!(category("ar", "100"), PluralCategory::Other);
+
+        assert_eq!(category("ro", "1"), PluralCategory::One);
+        assert_eq!(category("ro", "0"), PluralCategory::Few);
+        assert_eq!(category("ro", "19"), PluralCategory::Few);
+        assert_eq!(category("ro", "20"), PluralCategory::Other);
+        assert_eq!(category("ro", "101"), PluralCategory::Few);
+        assert_eq!(category("ro", "201"), PluralCategory::Few);
+        assert_eq!(category("ro", "1.5"), PluralCategory::Few);
+
+        assert_eq!(category("cy", "6"), PluralCategory::Many);
+        assert_eq!(category("lt", "0.1"), PluralCategory::Many);
+    }
+
+    #[test]
+    fn test_ordinal_rules() {
+        let ordinal = |locale: &str, n: &str| ordinal_category(locale, &n.parse().unwrap());
+
+        assert_eq!(ordinal("en", "1"), PluralCategory::One);
+        assert_eq!(ordinal("en-US", "22"), PluralCategory::Two);
+        assert_eq!(ordinal("en", "103"), PluralCategory::Few);
+        assert_eq!(ordinal("en", "11"), PluralCategory::Other);
+        assert_eq!(ordinal("en", "13"), PluralCategory::Other);
+        assert_eq!(ordinal("fr", "1"), PluralCategory::One);
+        assert_eq!(ordinal("fr", "2"), PluralCategory::Other);
+        assert_eq!(ordinal("it", "80"), PluralCategory::Many);
+        assert_eq!(ordinal("sv", "12"), PluralCategory::Other);
+        assert_eq!(ordinal("de", "1"), PluralCategory::Other);
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        add_plural_rule("x-test", |ops| {
+            if ops.i == 2 {
+                PluralCategory::Two
+            } else {
+                PluralCategory::Other
+            }
+        });
+
+        assert_eq!(category("x-test", "2"), PluralCategory::Two);
+        assert_eq!(category("x-test", "1"), PluralCategory::Other);
+        assert_eq!(category("x-test-AA", "2"), PluralCategory::Two);
+    }
+
+    #[test]
+    fn test_plural_keys() {
+        let ops = PluralOperands::from(1);
+        assert_eq!(
+            plural_keys("en", "inbox", &ops),
+            vec!["inbox.one", "inbox.other", "inbox"]
+        );
+
+        let ops = PluralOperands::from(0);
+        assert_eq!(
+            plural_keys("ar", "inbox", &ops),
+            vec!["inbox.zero", "inbox.other", "inbox"]
+        );
+        assert_eq!(
+            plural_keys("en", "inbox", &ops),
+            vec!["inbox.zero", "inbox.other", "inbox"]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+/// A subtree of the translations returned by `t_map!`, a text or a map of the child keys.
+///
+/// The items of an array in the locale files are the child keys `0`, `1`, ...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextTree {
+    Text(String),
+    Map(BTreeMap<String, TextTree>),
+}
+
+impl TextTree {
+    /// Get the text, or `None` if it's a map.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            Self::Map(_) => None,
+        }
+    }
+
+    /// Get the child keys, or `None` if it's a text.
+    pub fn as_map(&self) -> Option<&BTreeMap<String, TextTree>> {
+        match self {
+            Self::Text(_) => None,
+            Self::Map(map) => Some(map),
+        }
+    }
+
+    /// Get the subtree of a dotted key, e.g. `file.label`.
+    pub fn get(&self, key: &str) -> Option<&TextTree> {
+        key.split('.')
+            .try_fold(self, |tree, part| tree.as_map()?.get(part))
+    }
+}
+
+/// Build the nested map from the dotted keys relative to a prefix and their texts.
+///
+/// A key which is both a text and the parent of other keys keeps the child keys.
+pub fn build_tree<K, V, I>(items: I) -> BTreeMap<String, TextTree>
+where
+    K: AsRef<str>,
+    V: Into<String>,
+    I: IntoIterator<Item = (K, V)>,
+{
+    let mut root = BTreeMap::new();
+    for (key, text) in items {
+        let mut parts = key.as_ref().split('.').collect::<Vec<_>>();
+        let Some(last) = parts.pop() else {
+            continue;
+        };
+
+        let mut map = &mut root;
+        for part in parts {
+            let node = map
+                .entry(part.to_string())
+                .or_insert_with(|| TextTree::Map(BTreeMap::new()));
+            if let TextTree::Text(_) = node {
+                *node = TextTree::Map(BTreeMap::new());
+            }
+            map = match node {
+                TextTree::Map(map) => map,
+                TextTree::Text(_) => unreachable!(),
+            };
+        }
+
+        if let Some(TextTree::Map(_)) = map.get(last) {
+            continue;
+        }
+        map.insert(last.to_string(), TextTree::Text(text.into()));
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_tree() {
+        let tree = build_tree([
+            ("title", "Menu"),
+            ("items.0.label", "File"),
+            ("items.1.label", "Edit"),
+            ("items.1", "Ignored"),
+            ("help", "Help"),
+            ("help.about", "About"),
+        ]);
+
+        assert_eq!(tree["title"], TextTree::Text("Menu".into()));
+        assert_eq!(
+            tree["items"].get("0.label").and_then(|t| t.as_text()),
+            Some("File")
+        );
+        assert_eq!(
+            tree["items"].get("1.label").and_then(|t| t.as_text()),
+            Some("Edit")
+        );
+        assert_eq!(tree["items"].as_map().map(|m| m.len()), Some(2));
+        assert_eq!(
+            tree["help"].get("about").and_then(|t| t.as_text()),
+            Some("About")
+        );
+        assert_eq!(tree["title"].get("missing"), None);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::backend::Backend;
+use crate::{
+    load_file, load_files, merge_translations, FileFormat, Layout, LoadError, Translations,
+};
+use arc_swap::{ArcSwap, ArcSwapOption};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+type Data = HashMap<String, HashMap<String, String>>;
+
+/// A handler of the locale files failed to reload, see [`WatchingBackend::on_reload_error`].
+pub type ReloadErrorHandler = dyn Fn(&LoadError) + Send + Sync;
+
+/// A backend loads the locale files at runtime and reloads them when they changed, for development.
+///
+/// Only the changed files are parsed again, then all files are merged like `i18n!` does, and the
+/// new translations are swapped in atomically. A file failed to parse keeps its last translations.
+///
+/// The reloaded translations are plain strings, the ICU MessageFormat and Fluent translations are only
+/// formatted as embedded by `i18n!`, so their changes are returned as written until the next build.
+///
+/// ```ignore
+/// #[cfg(debug_assertions)]
+/// rust_i18n::i18n!("locales", backend = rust_i18n::WatchingBackend::new("locales").unwrap());
+/// #[cfg(not(debug_assertions))]
+/// rust_i18n::i18n!("locales");
+/// ```
+pub struct WatchingBackend {
+    data: Arc<ArcSwap<Data>>,
+    on_reload_error: Arc<ArcSwapOption<Box<ReloadErrorHandler>>>,
+    _watcher: Mutex<RecommendedWatcher>,
+}
+
+impl WatchingBackend {
+    /// Load the locale files of the path, and watch it for changes until the backend is dropped.
+    ///
+    /// Returns an error if a locale file failed to load, or the path can't be watched.
+    pub fn new<P: AsRef<Path>>(locales_path: P) -> notify::Result<Self> {
+        Self::with_layout(locales_path, Layout::default())
+    }
+
+    /// Load and watch the locale files of the path, with the locales and namespaces of the layout.
+    pub fn with_layout<P: AsRef<Path>>(locales_path: P, layout: Layout) -> notify::Result<Self> {
+        // The files are keyed by the absolute paths, like the paths of the events
+        let locales_path = locales_path
+            .as_ref()
+            .canonicalize()
+            .map_err(|err| notify::Error::io(err).add_path(locales_path.as_ref().to_path_buf()))?;
+        let locales_path = locales_path.as_path();
+
+        let files: BTreeMap<PathBuf, Translations> =
+            load_files(&locales_path.display().to_string(), layout, |_| false)
+                .map_err(|err| notify::Error::generic(&err.to_string()).add_path(err.path))?
+                .into_iter()
+                .collect();
+        let data = Arc::new(ArcSwap::from_pointee(merge_translations(
+            files.values().cloned(),
+        )));
+
+        let files = Mutex::new(files);
+        let watched_data = data.clone();
+        let on_reload_error = Arc::new(ArcSwapOption::<Box<ReloadErrorHandler>>::from(None));
+        let watched_on_reload_error = on_reload_error.clone();
+        let watched_path = locales_path.to_path_buf();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+
+                let mut files = files.lock().unwrap();
+                let mut changed = false;
+                for path in event.paths.iter().filter(|path| is_locale_file(path)) {
+                    match reload_file(&mut files, &watched_path, path, layout) {
+                        Ok(reloaded) => changed |= reloaded,
+                        Err(err) => match watched_on_reload_error.load().as_ref() {
+                            Some(handler) => handler(&err),
+                            None => eprintln!("rust-i18n: failed to reload, {}", err),
+                        },
+                    }
+                }
+
+                if changed {
+                    watched_data.store(Arc::new(merge_translations(files.values().cloned())));
+                }
+            })?;
+        watcher.watch(locales_path, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            data,
+            on_reload_error,
+            _watcher: Mutex::new(watcher),
+        })
+    }
+
+    /// Handle the errors of the locale files failed to reload, which are printed to stderr by default,
+    /// e.g. to log them. The file keeps its last translations.
+    ///
+    /// ```ignore
+    /// let backend = rust_i18n::WatchingBackend::new("locales")
+    ///     .unwrap()
+    ///     .on_reload_error(|err| log::warn!("{}", err));
+    /// ```
+    pub fn on_reload_error<F>(self, handler: F) -> Self
+    where
+        F: Fn(&LoadError) + Send + Sync + 'static,
+    {
+        self.on_reload_error
+            .store(Some(Arc::new(Box::new(handler))));
+        self
+    }
+}
+
+fn is_locale_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(FileFormat::from_extension)
+        .is_some()
+}
+
+/// Parse a changed file again, or remove it if it's deleted, returns whether the files changed.
+fn reload_file(
+    files: &mut BTreeMap<PathBuf, Translations>,
+    locales_path: &Path,
+    path: &Path,
+    layout: Layout,
+) -> Result<bool, LoadError> {
+    if !path.exists() {
+        return Ok(files.remove(path).is_some());
+    }
+
+    let trs = load_file(locales_path, path, layout)?;
+    files.insert(path.to_path_buf(), trs);
+    Ok(true)
+}
+
+impl Backend for WatchingBackend {
+    fn available_locales(&self) -> Vec<String> {
+        self.data.load().keys().cloned().collect()
+    }
+
+    fn translate(&self, locale: &str, key: &str) -> Option<Cow<'_, str>> {
+        self.data
+            .load()
+            .get(locale)?
+            .get(key)
+            .map(|value| Cow::Owned(value.clone()))
+    }
+
+    fn keys(&self, locale: &str) -> Vec<String> {
+        self.data
+            .load()
+            .get(locale)
+            .map(|trs| trs.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The added translations are kept until the next reload.
+    fn add(&mut self, locale: &str, key: &str, value: &str) {
+        self.data.rcu(|data| {
+            let mut data = Data::clone(data);
+            data.entry(locale.to_string())
+                .or_default()
+                .insert(key.to_string(), value.to_string());
+            data
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for<F: Fn() -> bool>(f: F) -> bool {
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_secs(10) {
+            if f() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn test_watching_backend() {
+        let dir = std::env::temp_dir().join(format!("rust-i18n-watching-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("en.yml"), "hello: Hello\nmessages:\n  hi: Hi").unwrap();
+        std::fs::write(dir.join("nested/app.en.yml"), "bye: Bye").unwrap();
+
+        let errors = Arc::new(Mutex::new(vec![]));
+        let reload_errors = errors.clone();
+        let backend = WatchingBackend::new(&dir)
+            .unwrap()
+            .on_reload_error(move |err| reload_errors.lock().unwrap().push(err.to_string()));
+        assert_eq!(backend.available_locales(), vec!["en"]);
+        assert_eq!(backend.translate("en", "hello").as_deref(), Some("Hello"));
+        assert_eq!(
+            backend.translate("en", "messages.hi").as_deref(),
+            Some("Hi")
+        );
+        assert_eq!(backend.translate("en", "bye").as_deref(), Some("Bye"));
+
+        std::fs::write(
+            dir.join("en.yml"),
+            "hello: Hello again\nmessages:\n  hi: Hi",
+        )
+        .unwrap();
+        assert!(wait_for(
+            || backend.translate("en", "hello").as_deref() == Some("Hello again")
+        ));
+
+        // Invalid files keep the last translations
+        std::fs::write(dir.join("nested/app.tmp"), "bye: [").unwrap();
+        std::fs::rename(dir.join("nested/app.tmp"), dir.join("nested/app.en.yml")).unwrap();
+        std::fs::write(dir.join("zh.yml"), "hello: 你好").unwrap();
+        assert!(wait_for(|| backend.translate("zh", "hello").is_some()));
+        assert_eq!(backend.translate("en", "bye").as_deref(), Some("Bye"));
+        let is_reported =
+            |err: &String| err.contains("app.en.yml") && err.contains("invalid YAML format");
+        assert!(wait_for(|| errors.lock().unwrap().iter().any(is_reported)));
+
+        std::fs::remove_file(dir.join("nested/app.en.yml")).unwrap();
+        assert!(wait_for(|| backend.translate("en", "bye").is_none()));
+        assert_eq!(
+            backend.translate("en", "hello").as_deref(),
+            Some("Hello again")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_watching_backend_relative_path() {
+        let dir = std::env::temp_dir().join(format!(
+            "rust-i18n-watching-relative-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("en.yml"), "hello: Hello").unwrap();
+
+        // The same directory relative to the current directory, like `WatchingBackend::new("locales")`
+        let cwd = std::env::current_dir().unwrap();
+        let relative = cwd
+            .components()
+            .skip(1)
+            .map(|_| "..")
+            .collect::<PathBuf>()
+            .join(dir.strip_prefix(dir.ancestors().last().unwrap()).unwrap());
+        assert!(relative.is_relative());
+
+        let backend = WatchingBackend::new(&relative).unwrap();
+        assert_eq!(backend.translate("en", "hello").as_deref(), Some("Hello"));
+
+        std::fs::write(dir.join("en.yml"), "hello: Hello again").unwrap();
+        assert!(wait_for(
+            || backend.translate("en", "hello").as_deref() == Some("Hello again")
+        ));
+
+        std::fs::remove_file(dir.join("en.yml")).unwrap();
+        assert!(wait_for(|| backend.translate("en", "hello").is_none()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,10 +4,11 @@
 //! See `Manifest::from_slice`.
 
 use itertools::Itertools;
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -18,6 +19,15 @@ pub struct I18nConfig {
     pub available_locales: Vec<String>,
     #[serde(default = "load_path")]
     pub load_path: String,
+    /// The layout of the locale files, like `{locale}/{namespace}.yml`.
+    #[serde(default)]
+    pub layout: Option<String>,
+    /// The macros to extract the keys from besides `t!`, with the index of the key argument.
+    #[serde(default)]
+    pub macros: BTreeMap<String, isize>,
+    /// The keys never unused for `cargo i18n check` and `prune`, like `errors.*` of the dynamic keys.
+    #[serde(default)]
+    pub keep_keys: Vec<String>,
 }
 
 fn default_locale() -> String {
@@ -32,51 +42,172 @@ fn load_path() -> String {
     I18nConfig::default().load_path
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "kebab-case")]
-pub struct MainConfig {
-    pub i18n: I18nConfig,
-}
-
 impl Default for I18nConfig {
     fn default() -> Self {
         I18nConfig {
             default_locale: "en".to_string(),
             available_locales: vec!["en".to_string()],
             load_path: "./locales".to_string(),
+            layout: None,
+            macros: BTreeMap::new(),
+            keep_keys: vec![],
         }
     }
 }
 
 pub fn load(cargo_root: &Path) -> io::Result<I18nConfig> {
-    let cargo_file = cargo_root.join("Cargo.toml");
-    let mut file = fs::File::open(&cargo_file)
-        .unwrap_or_else(|e| panic!("Fail to open {}, {}", cargo_file.display(), e));
-
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
+    let contents = read_manifest(cargo_root)?;
 
-    parse(&contents)
+    match find_workspace(cargo_root)?.and_then(|workspace| workspace.metadata) {
+        Some(inherited) => parse_inherited(&contents, &inherited),
+        None => parse(&contents),
+    }
 }
 
 pub fn parse(contents: &str) -> io::Result<I18nConfig> {
-    if !contents.contains("[i18n]") && !contents.contains("[package.metadata.i18n]") {
-        return Ok(I18nConfig::default());
+    parse_inherited(contents, &toml::Table::new())
+}
+
+/// Parse the `[i18n]` or `[package.metadata.i18n]` of a Cargo.toml, over the `[workspace.metadata.i18n]`.
+fn parse_inherited(contents: &str, inherited: &toml::Table) -> io::Result<I18nConfig> {
+    let manifest: toml::Table = toml::from_str(contents).map_err(invalid_data)?;
+    let own = manifest
+        .get("i18n")
+        .or_else(|| manifest.get("package")?.get("metadata")?.get("i18n"));
+
+    let mut table = inherited.clone();
+    if let Some(toml::Value::Table(own)) = own {
+        merge(&mut table, own);
     }
-    let contents = contents.replace("[package.metadata.i18n]", "[i18n]");
-    let mut config: MainConfig = toml::from_str(&contents)
-        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{}", err)))?;
+    let mut config: I18nConfig = toml::Value::Table(table).try_into().map_err(invalid_data)?;
 
     // Push default_locale
     config
-        .i18n
         .available_locales
-        .insert(0, config.i18n.default_locale.clone());
+        .insert(0, config.default_locale.clone());
 
     // unqiue
-    config.i18n.available_locales = config.i18n.available_locales.into_iter().unique().collect();
+    config.available_locales = config.available_locales.into_iter().unique().collect();
 
-    Ok(config.i18n)
+    Ok(config)
+}
+
+/// Override the inherited keys with the keys of the crate, the tables like `macros` key by key.
+fn merge(table: &mut toml::Table, other: &toml::Table) {
+    for (key, value) in other {
+        match (table.get_mut(key), value) {
+            (Some(toml::Value::Table(table)), toml::Value::Table(other)) => merge(table, other),
+            _ => {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// The `[workspace]` of a Cargo.toml.
+pub struct Workspace {
+    /// The roots of the member crates, and the workspace root if it's also a package.
+    pub members: Vec<PathBuf>,
+    /// The `[workspace.metadata.i18n]` inherited by the members.
+    metadata: Option<toml::Table>,
+}
+
+/// Load the `[workspace]` of the Cargo.toml in `root`, or `None` if it's not a workspace.
+///
+/// The `members` are glob patterns like Cargo, and the crates in the `exclude` paths are skipped.
+pub fn load_workspace(root: &Path) -> io::Result<Option<Workspace>> {
+    let manifest: toml::Table = toml::from_str(&read_manifest(root)?).map_err(invalid_data)?;
+    let Some(workspace) = manifest.get("workspace") else {
+        return Ok(None);
+    };
+
+    let paths = |name: &str| -> Vec<String> {
+        workspace
+            .get(name)
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect()
+    };
+    // Compared without the `.` parts, which the glob doesn't keep, like `./crates/a`
+    let without_cur_dir = |path: &Path| {
+        path.components()
+            .filter(|component| *component != Component::CurDir)
+            .collect::<PathBuf>()
+    };
+    let exclude = paths("exclude")
+        .iter()
+        .map(|path| without_cur_dir(&root.join(path)))
+        .collect::<Vec<_>>();
+
+    let mut members = vec![];
+    if manifest.contains_key("package") {
+        members.push(root.to_path_buf());
+    }
+    for pattern in paths("members") {
+        for member in expand_member(root, &pattern)? {
+            let is_excluded = exclude
+                .iter()
+                .any(|path| without_cur_dir(&member).starts_with(path));
+            if !members.contains(&member) && !is_excluded {
+                members.push(member);
+            }
+        }
+    }
+
+    let metadata = workspace
+        .get("metadata")
+        .and_then(|metadata| metadata.get("i18n"))
+        .and_then(|i18n| i18n.as_table())
+        .cloned();
+
+    Ok(Some(Workspace { members, metadata }))
+}
+
+/// Find the workspace of the crate, the closest Cargo.toml with `[workspace]` in the crate root
+/// or its parents, if the crate is one of its members.
+fn find_workspace(cargo_root: &Path) -> io::Result<Option<Workspace>> {
+    let cargo_root = cargo_root.canonicalize()?;
+    for dir in cargo_root.ancestors() {
+        if !dir.join("Cargo.toml").is_file() {
+            continue;
+        }
+        if let Some(workspace) = load_workspace(dir)? {
+            let is_member = workspace
+                .members
+                .iter()
+                .any(|member| member.canonicalize().is_ok_and(|m| m == cargo_root));
+            return Ok(is_member.then_some(workspace));
+        }
+    }
+    Ok(None)
+}
+
+/// The crate roots matched by a `members` path of the workspace, a glob pattern like Cargo.
+fn expand_member(root: &Path, pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let pattern = Path::new(&glob::Pattern::escape(&root.to_string_lossy())).join(pattern);
+    let mut dirs = glob::glob(&pattern.to_string_lossy())
+        .map_err(invalid_data)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io::Error::from)?;
+
+    dirs.retain(|dir| dir.join("Cargo.toml").is_file());
+    Ok(dirs)
+}
+
+fn read_manifest(cargo_root: &Path) -> io::Result<String> {
+    let cargo_file = cargo_root.join("Cargo.toml");
+    let mut file = fs::File::open(&cargo_file)
+        .unwrap_or_else(|e| panic!("Fail to open {}, {}", cargo_file.display(), e));
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn invalid_data(err: impl std::fmt::Display) -> io::Error {
+    io::Error::other(err.to_string())
 }
 
 #[test]
@@ -117,12 +248,29 @@ fn test_parse_with_metadata() {
         default-locale = "en"
         available-locales = ["zh-CN"]
         load-path = "./my-locales"
+        layout = "{locale}/{namespace}.yml"
+        keep-keys = ["errors.*"]
+
+        [package.metadata.i18n.macros]
+        tr = 0
+        "ui::t_html" = 0
+        t_add = -2
     "#;
 
     let cfg = parse(contents).unwrap();
     assert_eq!(cfg.default_locale, "en");
     assert_eq!(cfg.available_locales, vec!["en", "zh-CN"]);
     assert_eq!(cfg.load_path, "./my-locales");
+    assert_eq!(cfg.layout.as_deref(), Some("{locale}/{namespace}.yml"));
+    assert_eq!(cfg.keep_keys, vec!["errors.*"]);
+    assert_eq!(
+        cfg.macros,
+        BTreeMap::from([
+            ("t_add".to_string(), -2),
+            ("tr".to_string(), 0),
+            ("ui::t_html".to_string(), 0)
+        ])
+    );
 }
 
 #[test]
@@ -144,3 +292,89 @@ fn test_load() {
     assert_eq!(cfg.default_locale, "en");
     assert_eq!(cfg.available_locales, vec!["en", "zh-CN"]);
 }
+
+#[test]
+fn test_load_workspace() {
+    let dir = std::env::temp_dir().join(format!("rust-i18n-workspace-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for member in [
+        "crates/a",
+        "crates/b",
+        "crates/skip",
+        "app",
+        "other",
+        "tools/xtask",
+    ] {
+        fs::create_dir_all(dir.join(member)).unwrap();
+        fs::write(
+            dir.join(member).join("Cargo.toml"),
+            format!("[package]\nname = \"{}\"\n", member.replace('/', "-")),
+        )
+        .unwrap();
+    }
+    fs::write(
+        dir.join("Cargo.toml"),
+        r#"
+        [workspace]
+        members = ["crates/*", "app", "missing", "tools/[wx]*"]
+        exclude = ["crates/skip"]
+
+        [workspace.metadata.i18n]
+        available-locales = ["zh-CN", "de"]
+        load-path = "i18n"
+
+        [workspace.metadata.i18n.macros]
+        tr = 0
+        "#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("app/Cargo.toml"),
+        r#"
+        [package]
+        name = "app"
+
+        [package.metadata.i18n]
+        default-locale = "de"
+        load-path = "locales"
+
+        [package.metadata.i18n.macros]
+        t_html = 1
+        "#,
+    )
+    .unwrap();
+
+    let workspace = load_workspace(&dir).unwrap().unwrap();
+    assert_eq!(
+        workspace.members,
+        vec![
+            dir.join("crates/a"),
+            dir.join("crates/b"),
+            dir.join("app"),
+            dir.join("tools/xtask")
+        ]
+    );
+    assert!(load_workspace(&dir.join("app")).unwrap().is_none());
+
+    let cfg = load(&dir.join("crates/a")).unwrap();
+    assert_eq!(cfg.default_locale, "en");
+    assert_eq!(cfg.available_locales, vec!["en", "zh-CN", "de"]);
+    assert_eq!(cfg.load_path, "i18n");
+    assert_eq!(cfg.macros, BTreeMap::from([("tr".to_string(), 0)]));
+
+    let cfg = load(&dir.join("app")).unwrap();
+    assert_eq!(cfg.default_locale, "de");
+    assert_eq!(cfg.available_locales, vec!["de", "zh-CN"]);
+    assert_eq!(cfg.load_path, "locales");
+    assert_eq!(
+        cfg.macros,
+        BTreeMap::from([("t_html".to_string(), 1), ("tr".to_string(), 0)])
+    );
+
+    // Not a member of the workspace
+    let cfg = load(&dir.join("other")).unwrap();
+    assert_eq!(cfg.available_locales, vec!["en"]);
+    assert_eq!(cfg.load_path, "./locales");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
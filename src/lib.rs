@@ -1,32 +1,47 @@
 #![allow(rustdoc::invalid_rust_codeblocks)]
 #![doc = include_str!("../README.md")]
 
+use arc_swap::ArcSwap;
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::sync::Arc;
 
 pub mod error;
+mod scope;
 
 #[doc(hidden)]
 pub use once_cell;
 pub use rust_i18n_macros::*;
 pub use rust_i18n_support::*;
 pub use rust_i18n_support::backend::*;
+pub use rust_i18n_support::fallback::*;
+pub use rust_i18n_support::message_format::*;
+pub use rust_i18n_support::missing::*;
+pub use rust_i18n_support::placeholder::*;
+pub use rust_i18n_support::plural::*;
+pub use rust_i18n_support::tree::*;
+#[cfg(feature = "watch")]
+pub use rust_i18n_support::watching::*;
+#[cfg(feature = "tokio")]
+pub use scope::with_task_locale;
+pub use scope::{with_locale, LocaleGuard};
 
-static CURRENT_LOCALE: Lazy<Mutex<String>> = Lazy::new(|| {
-    get_locale()
-        .map(|locale| Mutex::new(locale.to_string()))
-        .unwrap_or_else(|| Mutex::new("en-US".to_string()))
+/// The global locale, swapped atomically for `t!` reads it without lock
+static CURRENT_LOCALE: Lazy<ArcSwap<String>> = Lazy::new(|| {
+    ArcSwap::from_pointee(get_locale().unwrap_or_else(|| "en-US".to_string()))
 });
 
-/// Set current locale
+/// Set the global locale, the scoped locales of [`with_locale`] and [`LocaleGuard`] take precedence over it
 pub fn set_locale(locale: &str) {
-    let mut current_locale = CURRENT_LOCALE.lock().unwrap();
-    *current_locale = locale.to_string();
+    CURRENT_LOCALE.store(Arc::new(locale.to_string()));
 }
 
-/// Get current locale
+/// Get current locale, the innermost thread or task scoped locale, or the global locale
 pub fn locale() -> String {
-    CURRENT_LOCALE.lock().unwrap().to_string()
+    if let Some(locale) = scope::scoped_locale() {
+        return locale;
+    }
+
+    CURRENT_LOCALE.load().to_string()
 }
 
 pub fn fmt<I, S>(s: &str, vals: I) -> String
@@ -43,7 +58,10 @@ where
     res
 }
 
-/// Get I18n text
+/// Get I18n text, as a `Cow<'static, str>`
+///
+/// The text is borrowed from the translations embedded at compile time, it's only allocated for
+/// the interpolated variables, the translations added by `t_add!` and the missing keys.
 ///
 /// ```ignore
 /// // Simple get text with current locale
@@ -57,48 +75,62 @@ where
 ///
 /// // With locale and variables
 /// t!("messages.hello", locale = "de", "Jason"); // messages.hello: "Hallo, {}" => "Hallo, Jason"
+///
+/// // With format specs, like `format!`
+/// t!("price", amount = 12.3456); // price: "Price: %{amount:.2}" => "Price: 12.35"
+///
+/// // With plural forms, `count` picks the CLDR plural category of the locale
+/// t!("inbox", count = 2); // inbox: { one: "%{count} message", other: "%{count} messages" } => "2 messages"
+///
+/// // With ICU MessageFormat translations, see `message_format` of `i18n!`
+/// t!("files", count = 2); // files: "{count, plural, one {# file} other {# files}}" => "2 files"
 /// ```
 #[macro_export]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! t {
     // t!("foo")
     ($key:expr) => {
-        _rust_i18n_translate(rust_i18n::locale().as_str(), $key)
+        rust_i18n::format_placeholders(
+            _rust_i18n_translate(rust_i18n::locale().as_str(), _rust_i18n_translate::check_key!($key)),
+            &[],
+        )
+    };
+
+    // t!("foo", locale = "en", a = 1, b = "Foo")
+    ($key:expr, locale = $locale:expr, $($var_name:tt = $var_val:expr),+ $(,)?) => {
+        rust_i18n::_rust_i18n_format_args!(@bind args [] $($var_name = $var_val),+ => {
+            // A `count` variable selects the plural form, e.g. `inbox.one` or `inbox.other`,
+            // and ICU MessageFormat translations are formatted with all variables
+            let vars = args
+                .iter()
+                .map(|(var_name, var_val)| (*var_name, var_val.to_string()))
+                .collect::<Vec<_>>();
+            let message = _rust_i18n_translate::args($locale, _rust_i18n_translate::check_key!($key), &vars);
+
+            // Replace the `%{var_name}` and `%{var_name:spec}` placeholders
+            rust_i18n::format_placeholders(message, &args)
+        })
     };
 
     // t!("foo", locale = "en", vec!["bar", "baz"])
     ($key:expr, locale = $locale:expr, $vals:expr) => {{
-        let mut message = _rust_i18n_translate($locale, $key);
-        rust_i18n::fmt(&message, $vals)
+        let message = _rust_i18n_translate($locale, _rust_i18n_translate::check_key!($key));
+        // Unescape `%%{` like the named arguments, before the values are inserted
+        let message = rust_i18n::format_placeholders(message, &[]);
+        std::borrow::Cow::<'static, str>::Owned(rust_i18n::fmt(&message, $vals))
     }};
 
     // t!("foo", locale = "en", "bar", "baz")
     ($key:expr, locale = $locale:expr, $( $x:expr ),*) => {{
-        let mut message = _rust_i18n_translate($locale, $key);
+        let message = _rust_i18n_translate($locale, _rust_i18n_translate::check_key!($key));
+        let message = rust_i18n::format_placeholders(message, &[]);
         let vals: Vec<&str> = vec![$($x),*];
-        rust_i18n::fmt(&message, &vals)
+        std::borrow::Cow::<'static, str>::Owned(rust_i18n::fmt(&message, &vals))
     }};
 
     // t!("foo", locale = "en")
     ($key:expr, locale = $locale:expr) => {
-        _rust_i18n_translate($locale, $key)
-    };
-
-    // t!("foo", locale = "en", a = 1, b = "Foo")
-    ($key:expr, locale = $locale:expr, $($var_name:tt = $var_val:expr),+ $(,)?) => {
-        {
-            let mut message = _rust_i18n_translate($locale, $key);
-
-            $(
-                // Get the variable name as a string, and remove quotes surrounding the variable name
-                let var_name = stringify!($var_name).trim_matches('"');
-                // Make a holder string to replace the variable name with: %{var_name}
-                let holder = format!("%{{{var_name}}}");
-
-                message = message.replace(&holder, &format!("{}", $var_val));
-            )+
-            message
-        }
+        rust_i18n::format_placeholders(_rust_i18n_translate($locale, _rust_i18n_translate::check_key!($key)), &[])
     };
 
     // t!("foo %{a} %{b}", a = "bar", b = "baz")
@@ -141,6 +173,41 @@ macro_rules! t {
     }};
 }
 
+/// Bind each named argument of `t!` once, and convert them to `(name, FormatArg)` pairs in `$args`.
+///
+/// The values are bound by nested `match`, which keeps the temporaries alive until `$body` is evaluated.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _rust_i18n_format_args {
+    (@bind $args:ident [$(($name:tt, $value:ident))*] => $body:block) => {{
+        #[allow(unused_imports)]
+        use rust_i18n::placeholder::autoref::*;
+
+        let $args = [$((
+            stringify!($name).trim_matches('"'),
+            rust_i18n::FormatArg {
+                display: (&&FormatWrap($value)).display_arg(),
+                debug: (&&FormatWrap($value)).debug_arg(),
+                lower_hex: (&&FormatWrap($value)).lower_hex_arg(),
+                upper_hex: (&&FormatWrap($value)).upper_hex_arg(),
+                octal: (&&FormatWrap($value)).octal_arg(),
+                binary: (&&FormatWrap($value)).binary_arg(),
+                lower_exp: (&&FormatWrap($value)).lower_exp_arg(),
+                upper_exp: (&&FormatWrap($value)).upper_exp_arg(),
+            },
+        )),*];
+        $body
+    }};
+
+    (@bind $args:ident [$($bound:tt)*] $name:tt = $val:expr $(, $rest_name:tt = $rest_val:expr)* => $body:block) => {
+        match &$val {
+            value => rust_i18n::_rust_i18n_format_args!(
+                @bind $args [$($bound)* ($name, value)] $($rest_name = $rest_val),* => $body
+            ),
+        }
+    };
+}
+
 /// Get available locales
 ///
 /// ```ignore
@@ -156,6 +223,57 @@ macro_rules! available_locales {
 }
 
 
+/// Get the texts of an array in the locale files, as a `Vec<String>`
+///
+/// The array is taken from the first locale of the fallback chain which has it, and it's empty if
+/// no locale has it.
+///
+/// ```ignore
+/// // days: ["Sunday", "Monday", "Tuesday"]
+/// t_list!("days"); // => ["Sunday", "Monday", "Tuesday"]
+/// t_list!("days", locale = "de"); // => ["Sonntag", "Montag", "Dienstag"]
+/// t_list!("days")[1]; // => "Monday", the same as t!("days.1")
+/// ```
+#[macro_export]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! t_list {
+    // t_list!("days")
+    ($key:expr) => {
+        _rust_i18n_translate::list(rust_i18n::locale().as_str(), $key)
+    };
+
+    // t_list!("days", locale = "de")
+    ($key:expr, locale = $locale:expr) => {
+        _rust_i18n_translate::list($locale, $key)
+    };
+}
+
+/// Get the texts of the keys under a prefix, as a nested `BTreeMap<String, TextTree>`
+///
+/// The keys are collected from the locale and its fallback locales, and each text is looked up
+/// with the fallback locales like `t!`, so a partly translated subtree is completed by the fallback.
+///
+/// ```ignore
+/// // menu: { title: "Menu", items: [{ label: "File" }, { label: "Edit" }] }
+/// let menu = t_map!("menu");
+/// menu["title"].as_text(); // => Some("Menu")
+/// menu["items"].get("0.label").and_then(|item| item.as_text()); // => Some("File")
+/// t_map!("menu", locale = "de");
+/// ```
+#[macro_export]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! t_map {
+    // t_map!("menu")
+    ($key:expr) => {
+        _rust_i18n_translate::map(rust_i18n::locale().as_str(), $key)
+    };
+
+    // t_map!("menu", locale = "de")
+    ($key:expr, locale = $locale:expr) => {
+        _rust_i18n_translate::map($locale, $key)
+    };
+}
+
 #[macro_export]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! t_add {
@@ -1,9 +1,16 @@
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use clap::{Arg, Command};
 
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
 
-use rust_i18n_extract::{extractor, generator, iter};
+use rust_i18n_extract::checker::{self, IssueKind};
+use rust_i18n_extract::extractor::{self, MacroName, Message};
+use rust_i18n_extract::pruner::{self, KeyPatterns};
+use rust_i18n_extract::{generator, iter, mobile, spreadsheet, updater, xliff};
+use rust_i18n_support::Layout;
 mod config;
 
 #[macro_use]
@@ -26,6 +33,106 @@ fn main() -> Result<(), Error> {
             Arg::new("source")
                 .help("Path of your Rust crate root and Cargo.toml")
                 .default_value("./"),
+        )
+        .arg(
+            Arg::new("pot")
+                .long("pot")
+                .value_name("FILE")
+                .help("Write a gettext .pot template to FILE, instead of the TODO files"),
+        )
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("export")
+                .about("Export the translations for translation tools, or as Android and iOS resources")
+                .arg(
+                    Arg::new("path")
+                        .help("Path of your Rust crate root and Cargo.toml")
+                        .default_value("./"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["xliff", "xliff2", "android", "ios", "csv", "tsv"])
+                        .default_value("xliff")
+                        .help("Format of the export, xliff for XLIFF 1.2, xliff2 for XLIFF 2.0, android for strings.xml, ios for .strings and .stringsdict, csv and tsv for a sheet of all locales"),
+                )
+                .arg(
+                    Arg::new("source")
+                        .long("source")
+                        .value_name("LOCALE")
+                        .help("Locale of the source texts, default is the default-locale"),
+                )
+                .arg(
+                    Arg::new("target")
+                        .long("target")
+                        .value_name("LOCALE")
+                        .help("Locale to translate into, required by XLIFF, default is all locales for android and ios"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("PATH")
+                        .help("Write XLIFF or the sheet to the file instead of stdout, or the directory of the android and ios resources"),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check the missing, unused and untranslated keys of all locales without writing any file, exits with 3 if any key is missing or untranslated, or 4 if any key is unused")
+                .arg(
+                    Arg::new("path")
+                        .help("Path of your Rust crate root and Cargo.toml")
+                        .default_value("./"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["text", "json", "sarif"])
+                        .default_value("text")
+                        .help("Format of the report, text for humans, json or sarif for CI"),
+                ),
+        )
+        .subcommand(
+            Command::new("prune")
+                .about("Remove the keys not used in the source code from the YAML, JSON and TOML locale files, except the keep-keys of Cargo.toml and --keep")
+                .arg(
+                    Arg::new("path")
+                        .help("Path of your Rust crate root and Cargo.toml")
+                        .default_value("./"),
+                )
+                .arg(
+                    Arg::new("keep")
+                        .long("keep")
+                        .value_name("PATTERN")
+                        .action(clap::ArgAction::Append)
+                        .help("Keep the keys matched by the pattern, like errors.* for the dynamic keys, can be repeated"),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Print the diff of the locale files instead of writing them"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Import the translated units of a XLIFF file, or the changed cells of a CSV/TSV sheet into the locale files")
+                .arg(
+                    Arg::new("file")
+                        .help("The XLIFF 1.2 or 2.0 file, or the .csv or .tsv sheet")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("path")
+                        .help("Path of your Rust crate root and Cargo.toml")
+                        .default_value("./"),
+                )
+                .arg(
+                    Arg::new("locale")
+                        .long("locale")
+                        .value_name("LOCALE")
+                        .help("Locale of the translations, default is the target language of the file, or all locale columns of the sheet"),
+                ),
         );
 
     let app = Command::new(APP_NAME)
@@ -34,31 +141,74 @@ fn main() -> Result<(), Error> {
         .subcommand(extract_command)
         .get_matches();
 
-    let mut results = HashMap::new();
-
     #[allow(clippy::single_match)]
     match app.subcommand() {
         Some(("i18n", sub_m)) => {
+            match sub_m.subcommand() {
+                Some(("export", export_m)) => return export(export_m),
+                Some(("import", import_m)) => return import(import_m),
+                Some(("check", check_m)) => return check(check_m),
+                Some(("prune", prune_m)) => return prune(prune_m),
+                _ => {}
+            }
+
             let source_path = sub_m.get_one::<String>("source").expect("Missing source path");
 
-            let cfg = config::load(std::path::Path::new(source_path))?;
+            let source_root = Path::new(source_path);
+            let crates = match config::load_workspace(source_root)? {
+                Some(workspace) => workspace.members,
+                None => vec![source_root.to_path_buf()],
+            };
 
-            iter::iter_crate(source_path, |path, source| {
-                extractor::extract(&mut results, path, source)
-            })?;
+            let pot_file = sub_m.get_one::<String>("pot");
+            let mut pot_messages = vec![];
+            let mut has_error = false;
 
-            let mut messages: Vec<_> = results.values().collect();
-            messages.sort_by_key(|m| m.index);
+            for crate_root in &crates {
+                let crate_path = crate_root.display().to_string();
+                let cfg = config::load(crate_root)?;
 
-            let mut has_error = false;
+                let macros = macros(&cfg)?;
+                let mut results = HashMap::new();
+                iter::iter_crate(&crate_path, |path, source| {
+                    extractor::extract(&mut results, path, source, &macros)
+                })?;
 
-            let output_path = Path::new(source_path).join(&cfg.load_path);
+                let mut messages: Vec<_> = results.into_values().collect();
+                messages.sort_by_key(|m| m.index);
 
-            for available_locale in cfg.available_locales.into_iter() {
-                let result = generator::generate(&output_path, &available_locale, messages.clone());
-                if result.is_err() {
-                    has_error = true;
+                if pot_file.is_some() {
+                    pot_messages.extend(messages);
+                    continue;
                 }
+                let output_path = locales_path(&crate_path, &cfg);
+                if crates.len() > 1 {
+                    // Skip the members without locales, the others are checked even without texts to clear their TODO files
+                    if !output_path.is_dir() {
+                        continue;
+                    }
+                    println!("Extracting {}", crate_path);
+                }
+
+                let layout = layout(&cfg)?;
+
+                for available_locale in cfg.available_locales.into_iter() {
+                    let result = generator::generate(
+                        &output_path,
+                        source_root,
+                        &available_locale,
+                        layout,
+                        &messages,
+                    );
+                    if result.is_err() {
+                        has_error = true;
+                    }
+                }
+            }
+
+            if let Some(pot_file) = pot_file {
+                generator::generate_pot(pot_file, source_root, &pot_messages)?;
+                return Ok(());
             }
 
             if has_error {
@@ -71,4 +221,354 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+/// The locales path of the crate, without the `.` parts like `././locales`, which the glob doesn't match.
+fn locales_path(source_path: &str, cfg: &config::I18nConfig) -> PathBuf {
+    let path = Path::new(source_path)
+        .join(&cfg.load_path)
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect::<PathBuf>();
+
+    if path.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        path
+    }
+}
+
+/// The layout of the locale files, `layout` in Cargo.toml.
+fn layout(cfg: &config::I18nConfig) -> Result<Layout, Error> {
+    match &cfg.layout {
+        Some(layout) => layout
+            .parse()
+            .map_err(|err: String| anyhow!("{} in Cargo.toml", err)),
+        None => Ok(Layout::default()),
+    }
+}
+
+/// The macros to extract the keys from, the `macros` in Cargo.toml and `t!`.
+fn macros(cfg: &config::I18nConfig) -> Result<Vec<MacroName>, Error> {
+    let mut macros = cfg
+        .macros
+        .iter()
+        .map(|(name, key_index)| {
+            MacroName::new(name, *key_index).map_err(|err| anyhow!("{} in Cargo.toml", err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    macros.extend(MacroName::builtin());
+    Ok(macros)
+}
+
+/// The exit code of `check` for the missing or untranslated keys.
+const EXIT_MISSING: i32 = 3;
+/// The exit code of `check` for the unused keys, if no key is missing.
+const EXIT_UNUSED: i32 = 4;
+
+/// Report the missing, unused and untranslated keys of all locales to stdout, without writing any file.
+fn check(sub_m: &clap::ArgMatches) -> Result<(), Error> {
+    let source_path = sub_m
+        .get_one::<String>("path")
+        .expect("Missing source path");
+    let cfg = config::load(Path::new(source_path))?;
+    let source_root = Path::new(source_path);
+
+    let macros = macros(&cfg)?;
+    let mut results = HashMap::new();
+    iter::iter_crate(source_path, |path, source| {
+        extractor::extract(&mut results, path, source, &macros)
+    })?;
+
+    let report = checker::check(
+        &locales_path(source_path, &cfg),
+        layout(&cfg)?,
+        &cfg.available_locales,
+        &cfg.default_locale,
+        &KeyPatterns::new(&cfg.keep_keys),
+        results.values(),
+    )?;
+    let content = match sub_m.get_one::<String>("format").map(|s| s.as_str()) {
+        Some("json") => report.to_json(source_root)?,
+        Some("sarif") => report.to_sarif(source_root)?,
+        _ => report.to_text(source_root),
+    };
+    print!("{}", content);
+
+    if report.has(IssueKind::Missing) || report.has(IssueKind::Untranslated) {
+        std::process::exit(EXIT_MISSING);
+    }
+    if report.has(IssueKind::Unused) {
+        std::process::exit(EXIT_UNUSED);
+    }
+    Ok(())
+}
+
+/// Remove the unused keys from the locale files, or print the diff with `--dry-run`.
+fn prune(sub_m: &clap::ArgMatches) -> Result<(), Error> {
+    let source_path = sub_m
+        .get_one::<String>("path")
+        .expect("Missing source path");
+    let cfg = config::load(Path::new(source_path))?;
+    let keep = KeyPatterns::new(
+        cfg.keep_keys
+            .iter()
+            .chain(sub_m.get_many::<String>("keep").into_iter().flatten()),
+    );
+    let dry_run = sub_m.get_flag("dry-run");
+
+    let macros = macros(&cfg)?;
+    let mut results = HashMap::new();
+    iter::iter_crate(source_path, |path, source| {
+        extractor::extract(&mut results, path, source, &macros)
+    })?;
+    let used = results.keys().map(|key| key.as_str()).collect();
+
+    let pruned = pruner::prune_locales(
+        &locales_path(source_path, &cfg),
+        layout(&cfg)?,
+        &used,
+        &keep,
+    )?;
+    for file in &pruned {
+        if file.rewritten {
+            note_rewritten(std::slice::from_ref(&file.path));
+        }
+        if dry_run {
+            print!(
+                "{}",
+                pruner::diff(&file.path.display().to_string(), &file.old, &file.new)
+            );
+        } else {
+            std::fs::write(&file.path, &file.new)?;
+            eprintln!(
+                "Removed {} keys from {}",
+                file.keys.len(),
+                file.path.display()
+            );
+        }
+    }
+
+    if pruned.is_empty() {
+        eprintln!("No unused keys found.");
+    }
+    Ok(())
+}
+
+/// Export the translations of the target locale, with the source texts and locations of all keys,
+/// the translations of all locales as the Android and iOS resources, or as a CSV/TSV sheet.
+fn export(sub_m: &clap::ArgMatches) -> Result<(), Error> {
+    let source_path = sub_m
+        .get_one::<String>("path")
+        .expect("Missing source path");
+    let cfg = config::load(Path::new(source_path))?;
+    let format = sub_m
+        .get_one::<String>("format")
+        .map(|s| s.as_str())
+        .unwrap_or("xliff");
+    let target_locale = sub_m.get_one::<String>("target");
+
+    let output_path = locales_path(source_path, &cfg);
+    let mut data = rust_i18n_support::try_load_locales(
+        &output_path.display().to_string(),
+        layout(&cfg)?,
+        generator::is_todo_file,
+    )?;
+
+    if matches!(format, "android" | "ios") {
+        let output = sub_m
+            .get_one::<String>("output")
+            .ok_or_else(|| anyhow!("--output <PATH> is required by the {} format", format))?;
+        if let Some(target_locale) = target_locale {
+            data.retain(|locale, _| locale == target_locale);
+        }
+
+        let paths = if format == "android" {
+            mobile::export_android(Path::new(output), &data, &cfg.default_locale)?
+        } else {
+            mobile::export_apple(Path::new(output), &data)?
+        };
+        for path in paths {
+            eprintln!("Exported {}", path.display());
+        }
+        return Ok(());
+    }
 
+    let macros = macros(&cfg)?;
+    let mut results = HashMap::new();
+    iter::iter_crate(source_path, |path, source| {
+        extractor::extract(&mut results, path, source, &macros)
+    })?;
+    let mut messages: Vec<&Message> = results.values().collect();
+    messages.sort_by_key(|m| m.index);
+
+    if matches!(format, "csv" | "tsv") {
+        let delimiter = if format == "csv" { b',' } else { b'\t' };
+        let content = spreadsheet::generate(
+            delimiter,
+            &cfg.available_locales,
+            &data,
+            Path::new(source_path),
+            messages,
+        )?;
+
+        match sub_m.get_one::<String>("output") {
+            Some(output) => {
+                std::fs::write(output, content)?;
+                eprintln!("Exported {}", output);
+            }
+            None => print!("{}", content),
+        }
+        return Ok(());
+    }
+
+    let source_locale = sub_m
+        .get_one::<String>("source")
+        .unwrap_or(&cfg.default_locale);
+    let target_locale =
+        target_locale.ok_or_else(|| anyhow!("--target <LOCALE> is required by XLIFF"))?;
+    let version = match format {
+        "xliff2" => xliff::Version::V2_0,
+        _ => xliff::Version::V1_2,
+    };
+
+    let units = xliff::units(
+        &data,
+        source_locale,
+        target_locale,
+        Path::new(source_path),
+        messages,
+    );
+    let content = xliff::generate(version, source_locale, target_locale, &units);
+
+    match sub_m.get_one::<String>("output") {
+        Some(output) => {
+            std::fs::write(output, content)?;
+            eprintln!(
+                "Exported {} units of {} to {}",
+                units.len(),
+                target_locale,
+                output
+            );
+        }
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
+
+/// Import the translated units of a XLIFF file, or the changed cells of a sheet into the locale files.
+fn import(sub_m: &clap::ArgMatches) -> Result<(), Error> {
+    let file = sub_m.get_one::<String>("file").expect("Missing file");
+    let source_path = sub_m
+        .get_one::<String>("path")
+        .expect("Missing source path");
+    let cfg = config::load(Path::new(source_path))?;
+
+    let content = std::fs::read_to_string(file)?;
+    if let Some(delimiter) = spreadsheet::delimiter(Path::new(file)) {
+        return import_sheet(sub_m, &cfg, source_path, file, &content, delimiter);
+    }
+
+    let document = xliff::parse(&content)?;
+    let locale = sub_m
+        .get_one::<String>("locale")
+        .cloned()
+        .or(document.target_locale)
+        .ok_or_else(|| anyhow!("{} has no target language, use --locale", file))?;
+
+    let count = document.units.len();
+    let output_path = locales_path(source_path, &cfg);
+    let update = updater::update_locales(&output_path, layout(&cfg)?, &locale, document.units)?;
+
+    println!(
+        "Imported {} translations of {} from {}",
+        count, locale, file
+    );
+    for path in update.paths {
+        println!("Updated {}", path.display());
+    }
+    note_rewritten(&update.rewritten);
+    report_conflicts(&locale, &update.conflicts);
+
+    Ok(())
+}
+
+/// Import the non-empty cells of a sheet that differ from the locale files, then report the conflicts
+/// and exit with an error if any.
+fn import_sheet(
+    sub_m: &clap::ArgMatches,
+    cfg: &config::I18nConfig,
+    source_path: &str,
+    file: &str,
+    content: &str,
+    delimiter: u8,
+) -> Result<(), Error> {
+    let mut sheet = spreadsheet::parse(content, delimiter)?;
+    if let Some(locale) = sub_m.get_one::<String>("locale") {
+        sheet.translations.retain(|l, _| l == locale);
+    }
+
+    let output_path = locales_path(source_path, cfg);
+    let layout = layout(cfg)?;
+    let data = rust_i18n_support::try_load_locales(
+        &output_path.display().to_string(),
+        layout,
+        generator::is_todo_file,
+    )?;
+
+    let mut has_conflict = !sheet.conflicts.is_empty();
+    for conflict in &sheet.conflicts {
+        eprintln!("Conflict: {}", conflict);
+    }
+
+    for (locale, translations) in sheet.translations {
+        let current = data.get(&locale);
+        let changes = translations
+            .into_iter()
+            .filter(|(key, text)| current.and_then(|trs| trs.get(key)) != Some(text))
+            .collect::<Vec<_>>();
+        if changes.is_empty() {
+            continue;
+        }
+
+        let count = changes.len();
+        let update = updater::update_locales(&output_path, layout, &locale, changes)?;
+        println!(
+            "Imported {} changed translations of {} from {}",
+            count - update.conflicts.len(),
+            locale,
+            file
+        );
+        for path in update.paths {
+            println!("Updated {}", path.display());
+        }
+        note_rewritten(&update.rewritten);
+        has_conflict |= report_conflicts(&locale, &update.conflicts);
+    }
+
+    if has_conflict {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Note the files written as a whole, which lost their comments and formatting.
+fn note_rewritten(paths: &[PathBuf]) {
+    for path in paths {
+        eprintln!(
+            "Note: {} is written as a whole, its comments and formatting are not kept",
+            path.display()
+        );
+    }
+}
+
+/// Report the keys not imported as they have nested keys, returns whether any.
+fn report_conflicts(locale: &str, keys: &[String]) -> bool {
+    for key in keys {
+        eprintln!(
+            "Conflict: `{}` has nested keys in {}, the translation is not imported",
+            key, locale
+        );
+    }
+    !keys.is_empty()
+}
@@ -0,0 +1,183 @@
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+
+thread_local! {
+    static THREAD_LOCALE: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// The number of the thread scopes entered.
+    static THREAD_DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// The number of the thread scopes entered when the innermost task scope is polled.
+    #[cfg(feature = "tokio")]
+    static TASK_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_LOCALE: String;
+}
+
+/// Override the locale of the current thread until the guard is dropped.
+///
+/// The previous locale of the thread is restored on drop, so guards can be nested.
+/// The guard is `!Send`, because an async task may resume on another thread after `.await`,
+/// use [`with_task_locale`] (feature `tokio`) for that.
+///
+/// ```ignore
+/// let _guard = rust_i18n::LocaleGuard::new("de");
+/// assert_eq!(rust_i18n::locale(), "de");
+/// ```
+#[must_use = "the locale is restored when the guard is dropped"]
+pub struct LocaleGuard {
+    previous: Option<String>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl LocaleGuard {
+    pub fn new(locale: &str) -> Self {
+        let previous = THREAD_LOCALE.with(|current| current.replace(Some(locale.to_string())));
+        THREAD_DEPTH.with(|depth| depth.set(depth.get() + 1));
+
+        Self {
+            previous,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for LocaleGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        THREAD_LOCALE.with(|current| *current.borrow_mut() = previous);
+        THREAD_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Run `f` with the locale overridden for the current thread only.
+///
+/// ```ignore
+/// let text = rust_i18n::with_locale("de", || t!("hello"));
+/// ```
+pub fn with_locale<R>(locale: &str, f: impl FnOnce() -> R) -> R {
+    let _guard = LocaleGuard::new(locale);
+    f()
+}
+
+/// Run `future` with the locale overridden for the current tokio task, across `.await` points.
+///
+/// ```ignore
+/// rust_i18n::with_task_locale("de", async {
+///     handle_request().await
+/// })
+/// .await;
+/// ```
+#[cfg(feature = "tokio")]
+pub fn with_task_locale<F: std::future::Future>(
+    locale: &str,
+    future: F,
+) -> impl std::future::Future<Output = F::Output> {
+    TASK_LOCALE.scope(
+        locale.to_string(),
+        TaskScope {
+            future: Box::pin(future),
+        },
+    )
+}
+
+/// A future of a task scope, which records the thread scopes it is polled in.
+#[cfg(feature = "tokio")]
+struct TaskScope<F> {
+    future: std::pin::Pin<Box<F>>,
+}
+
+#[cfg(feature = "tokio")]
+impl<F: std::future::Future> std::future::Future for TaskScope<F> {
+    type Output = F::Output;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let depth = THREAD_DEPTH.with(Cell::get);
+        let previous = TASK_DEPTH.with(|task_depth| task_depth.replace(depth));
+        let poll = self.future.as_mut().poll(cx);
+        TASK_DEPTH.with(|task_depth| task_depth.set(previous));
+        poll
+    }
+}
+
+/// The innermost scoped locale, the thread scope entered while the task is polled is inside of it,
+/// and the task polled in a thread scope is inside of that.
+pub(crate) fn scoped_locale() -> Option<String> {
+    #[cfg(feature = "tokio")]
+    if let Ok(locale) = TASK_LOCALE.try_with(|locale| locale.clone()) {
+        if THREAD_DEPTH.with(Cell::get) <= TASK_DEPTH.with(Cell::get) {
+            return Some(locale);
+        }
+    }
+
+    THREAD_LOCALE.with(|current| current.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_locale() {
+        assert_eq!(scoped_locale(), None);
+
+        let locale = with_locale("de", || {
+            let inner = with_locale("fr", scoped_locale);
+            assert_eq!(inner.as_deref(), Some("fr"));
+
+            scoped_locale()
+        });
+        assert_eq!(locale.as_deref(), Some("de"));
+        assert_eq!(scoped_locale(), None);
+    }
+
+    #[test]
+    fn test_locale_guard() {
+        let guard = LocaleGuard::new("de");
+        assert_eq!(scoped_locale().as_deref(), Some("de"));
+
+        let other = std::thread::spawn(scoped_locale).join().unwrap();
+        assert_eq!(other, None);
+
+        drop(guard);
+        assert_eq!(scoped_locale(), None);
+    }
+
+    #[test]
+    fn test_locale_guard_restored_on_panic() {
+        let result = std::panic::catch_unwind(|| with_locale("de", || panic!("boom")));
+        assert!(result.is_err());
+        assert_eq!(scoped_locale(), None);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_with_task_locale() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let locale = runtime.block_on(with_task_locale("de", async {
+            tokio::task::yield_now().await;
+            let inner = with_locale("fr", scoped_locale);
+            assert_eq!(inner.as_deref(), Some("fr"));
+
+            scoped_locale()
+        }));
+        assert_eq!(locale.as_deref(), Some("de"));
+        assert_eq!(scoped_locale(), None);
+
+        // The task scope is inside of the thread scope it's polled in
+        let locale = with_locale("fr", || {
+            runtime.block_on(with_task_locale("de", async {
+                tokio::task::yield_now().await;
+                scoped_locale()
+            }))
+        });
+        assert_eq!(locale.as_deref(), Some("de"));
+    }
+}
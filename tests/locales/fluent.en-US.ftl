@@ -0,0 +1,17 @@
+# Fluent messages, evaluated with the arguments of `t!`
+-brand-name = Rust I18n
+
+welcome = Welcome to { -brand-name }, { $user }!
+    .title = About { -brand-name }
+
+unread-emails = { $count ->
+    [0] You have no unread emails
+    [one] You have one unread email
+   *[other] You have { $count } unread emails
+}
+
+profile-updated = { $gender ->
+    [female] { $user } updated her profile
+    [male] { $user } updated his profile
+   *[other] { $user } updated their profile
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::{Mutex, Arc}};
+use std::{borrow::Cow, collections::HashMap, sync::{Mutex, Arc}};
 
 use rust_i18n::backend::*;
 
@@ -26,8 +26,8 @@ impl Backend for I18n {
         self.trs.lock().unwrap().keys().cloned().collect()
     }
 
-    fn translate(&self, locale: &str, key: &str) -> Option<String> {
-        return self.trs.lock().unwrap().get(locale)?.get(key).cloned();
+    fn translate(&self, locale: &str, key: &str) -> Option<Cow<'_, str>> {
+        return self.trs.lock().unwrap().get(locale)?.get(key).cloned().map(Cow::Owned);
     }
 
     fn add(&mut self, locale: &str, key: &str, value: &str) {
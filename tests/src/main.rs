@@ -79,4 +79,265 @@ mod tests {
         assert_eq!(t, "Merhaba k");
     }
 
+    #[test]
+    fn lists_and_maps() {
+        let days = vec!["Sunday", "Monday", "Tuesday"];
+        assert_eq!(t_list!("days", locale = "en-US"), days);
+        assert_eq!(t_list!("days", locale = "tr-TR"), days);
+        assert_eq!(
+            t_list!("days", locale = "zh-Hant-TW"),
+            vec!["星期日", "星期一", "星期二"]
+        );
+        assert_eq!(t!("days.1", locale = "en-US"), "Monday");
+        assert!(t_list!("unknown", locale = "en-US").is_empty());
+
+        let menu = TextTree::Map(t_map!("menu", locale = "zh"));
+        let text = |key: &str| menu.get(key).and_then(TextTree::as_text);
+        assert_eq!(text("title"), Some("菜单"));
+        assert_eq!(text("items.0.label"), Some("文件"));
+        // The missing texts fallback to en-US per key
+        assert_eq!(text("items.0.shortcut"), Some("Ctrl+F"));
+        assert_eq!(text("items.1.label"), Some("Edit"));
+
+        assert!(t_map!("unknown", locale = "en-US").is_empty());
+    }
+
+    #[test]
+    fn fallback_chain() {
+        assert_eq!(t!("greetings", locale = "zh-Hant-TW"), "你好！");
+        assert_eq!(t!("greetings", locale = "zh"), "你好！");
+        assert_eq!(t!("hello", locale = "tr-TR-x-formal"), "Merhaba");
+        assert_eq!(t!("hello", locale = "zh-Hant-TW"), "Hello");
+        assert_eq!(t!("inbox", locale = "tr-TR-x-formal", count = 2), "2 mesaj");
+    }
+
+    mod fallback_list {
+        use rust_i18n::*;
+
+        i18n!(
+            "tests/locales",
+            fallback = ["zh", "en-US"],
+            fallback_map = { "tr-CY" => ["tr-TR"], "az" => "tr-TR" },
+            check_keys = "strict",
+        );
+
+        #[test]
+        fn fallback_list() {
+            assert_eq!(t!("greetings", locale = "de"), "你好！");
+            assert_eq!(t!("hello", locale = "de"), "Hello");
+            assert_eq!(t!("greetings", locale = "tr-CY"), "Merhabalar!");
+            assert_eq!(t!("hello", locale = "az-Latn"), "Merhaba");
+            assert_eq!(t!("messages.hello", locale = "az"), "Merhaba");
+        }
+    }
+
+    mod imported_translate {
+        use super::_rust_i18n_translate;
+        use rust_i18n::{t, t_list};
+
+        #[test]
+        fn imported_translate() {
+            assert_eq!(t!("hello", locale = "en-US"), "Hello");
+            assert_eq!(t!("inbox", locale = "en-US", count = 2), "2 messages");
+            assert_eq!(t_list!("days", locale = "en-US")[0], "Sunday");
+        }
+    }
+
+    #[test]
+    fn scoped_locale() {
+        let _guard = rust_i18n::LocaleGuard::new("tr-TR");
+        assert_eq!(t!("hello"), "Merhaba");
+
+        let text = rust_i18n::with_locale("en-US", || t!("hello"));
+        assert_eq!(text, "Hello");
+
+        let text = std::thread::spawn(|| rust_i18n::with_locale("en-US", || t!("greetings")))
+            .join()
+            .unwrap();
+        assert_eq!(text, "Greetings!");
+
+        assert_eq!(rust_i18n::locale(), "tr-TR");
+    }
+
+    #[test]
+    fn plural() {
+        assert_eq!(t!("inbox", locale = "en-US", count = 0), "No messages");
+        assert_eq!(t!("inbox", locale = "en-US", count = 1), "1 message");
+        assert_eq!(t!("inbox", locale = "en-US", count = 2), "2 messages");
+        assert_eq!(t!("inbox", locale = "en-US", count = 1.5), "1.5 messages");
+
+        assert_eq!(t!("inbox", locale = "tr-TR", count = 1), "1 mesaj");
+        assert_eq!(t!("inbox", locale = "tr-TR", count = 5), "5 mesaj");
+
+        assert_eq!(t!("inbox", locale = "ru", count = 1), "1 сообщение");
+        assert_eq!(t!("inbox", locale = "ru", count = 3), "3 сообщения");
+        assert_eq!(t!("inbox", locale = "ru", count = 11), "11 сообщений");
+        assert_eq!(t!("inbox", locale = "ru", count = 21), "21 сообщение");
+
+        assert_eq!(t!("inbox", locale = "pl", count = 22), "22 wiadomości");
+        assert_eq!(t!("inbox", locale = "pl", count = 25), "25 wiadomości");
+
+        assert_eq!(t!("inbox", locale = "ar", count = 0), "لا توجد رسائل");
+        assert_eq!(t!("inbox", locale = "ar", count = 2), "رسالتان");
+        assert_eq!(t!("inbox", locale = "ar", count = 4), "4 رسائل");
+        assert_eq!(t!("inbox", locale = "ar", count = 11), "11 رسالة");
+
+        // Fallback locale uses its own plural rules
+        assert_eq!(t!("inbox", locale = "ja", count = 1), "1 message");
+
+        // Plain keys still work with `count`
+        assert_eq!(t!("hello", locale = "en-US", count = 2), "Hello");
+    }
+
+    #[test]
+    fn borrowed() {
+        use std::borrow::Cow;
+
+        assert!(matches!(t!("hello", locale = "en-US"), Cow::Borrowed("Hello")));
+        assert!(matches!(t!("greetings", locale = "en-GB"), Cow::Borrowed("Greetings!")));
+        assert!(matches!(t!("hello", locale = "en-US", name = "Jason"), Cow::Borrowed("Hello")));
+        assert!(matches!(t!("inbox", locale = "en-US", count = 2), Cow::Owned(_)));
+        assert!(matches!(t!("missing.key", locale = "en-US"), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn gettext() {
+        assert_eq!(t!("hello", locale = "fr"), "Bonjour le monde");
+        assert_eq!(t!("messages.hello", locale = "fr", name = "Jason"), "Bonjour, Jason");
+        assert_eq!(t!("apple", locale = "fr", count = 1), "1 pomme");
+        assert_eq!(t!("apple", locale = "fr", count = 3), "3 pommes");
+    }
+
+    #[test]
+    fn fluent() {
+        assert_eq!(
+            t!("welcome", locale = "en-US", user = "Jason"),
+            "Welcome to Rust I18n, Jason!"
+        );
+        assert_eq!(t!("welcome", locale = "en-US"), "Welcome to Rust I18n, {$user}!");
+        assert_eq!(t!("welcome.title", locale = "en-US"), "About Rust I18n");
+        assert_eq!(t!("-brand-name", locale = "en-GB"), "Rust I18n");
+
+        assert_eq!(
+            t!("unread-emails", locale = "en-US", count = 0),
+            "You have no unread emails"
+        );
+        assert_eq!(
+            t!("unread-emails", locale = "en-US", count = 1),
+            "You have one unread email"
+        );
+        assert_eq!(
+            t!("unread-emails", locale = "en-US", count = 5),
+            "You have 5 unread emails"
+        );
+        assert_eq!(
+            t!("profile-updated", locale = "en-US", user = "Ann", gender = "female"),
+            "Ann updated her profile"
+        );
+        assert_eq!(
+            t!("profile-updated", locale = "en-US", user = "Sam"),
+            "Sam updated their profile"
+        );
+    }
+
+    #[test]
+    fn format_specs() {
+        assert_eq!(t!("price", locale = "en-US", amount = 12.3456), "Price: 12.35");
+        assert_eq!(
+            t!("padded", locale = "en-US", id = 255, name = "Jason"),
+            "[   255] [0x00ff] [\"Jason\"]"
+        );
+        assert_eq!(t!("escaped", locale = "en-US", name = "Jason"), "Use %{name} for Jason");
+        assert_eq!(t!("escaped", locale = "en-US"), "Use %{name} for %{name}");
+        assert_eq!(t!("escaped", locale = "en-US", "a", "b"), "Use %{name} for %{name}");
+        assert_eq!(t!("escaped", locale = "en-US", vec!["Jason"]), "Use %{name} for %{name}");
+
+        // Arguments are evaluated once, and temporaries live until the text is formatted
+        let mut calls = 0;
+        let mut next = || {
+            calls += 1;
+            calls.to_string()
+        };
+        assert_eq!(t!("price", locale = "en-US", amount = next()), "Price: 1");
+        assert_eq!(calls, 1);
+
+        #[derive(Debug)]
+        struct Point(i32, i32);
+        assert_eq!(
+            t!("messages.hello", locale = "en-US", point = Point(1, 2)),
+            "Hello"
+        );
+    }
+
+    #[test]
+    fn message_format() {
+        assert_eq!(t!("files", locale = "en-US", count = 0), "No files");
+        assert_eq!(t!("files", locale = "en-US", count = 1), "1 file");
+        assert_eq!(t!("files", locale = "en-GB", count = 3), "3 files");
+        assert_eq!(t!("files", locale = "ru", count = 3), "3 файла");
+        assert_eq!(t!("files", locale = "ru", count = 5), "5 файлов");
+
+        assert_eq!(
+            t!("invitation", locale = "en-US", gender = "female", name = "Ann"),
+            "Ann invited you to her party"
+        );
+        assert_eq!(
+            t!("invitation", locale = "en-US", name = "Sam"),
+            "Sam invited you to their party"
+        );
+        assert_eq!(t!("place", locale = "en-US", place = 22), "You finished 22nd");
+        assert_eq!(t!("place", locale = "en-US", place = 13), "You finished 13th");
+        assert_eq!(t!("quoted", locale = "en-US", name = "Ann"), "It's {literal}, Ann");
+
+        // Other files keep the `%{name}` syntax
+        assert_eq!(t!("inbox", locale = "en-US", count = 2), "2 messages");
+    }
+
+    mod missing_handler {
+        use crate::backend::*;
+        use rust_i18n::*;
+
+        i18n!("tests/locales", fallback = "en-US", backend = {
+            let mut backend = I18n::new();
+            backend.add("en-US", "missing_handler.extended", "From the extended backend");
+            backend
+        });
+
+        #[test]
+        fn missing_handler() {
+            set_missing_handler(|locale, key, chain| {
+                if !key.starts_with("missing_handler.") {
+                    return None;
+                }
+                Some(format!("[MISSING: {} in {}, tried {}]", key, locale, chain.join(", ")))
+            });
+
+            assert_eq!(
+                t!("missing_handler.unknown", locale = "zh"),
+                "[MISSING: missing_handler.unknown in zh, tried zh, en-US, en]"
+            );
+            assert_eq!(
+                t!("missing_handler.unknown", locale = "en-US", count = 2),
+                "[MISSING: missing_handler.unknown in en-US, tried en-US, en]"
+            );
+            assert_eq!(
+                t!("missing_handler.extended", locale = "zh"),
+                "From the extended backend"
+            );
+            assert_eq!(t!("hello", locale = "en-US"), "Hello");
+        }
+    }
+
+    mod message_format_project {
+        use rust_i18n::*;
+
+        i18n!("tests/locales", fallback = "en-US", message_format = "icu");
+
+        #[test]
+        fn message_format_project() {
+            assert_eq!(t!("files", count = 1), "1 file");
+            assert_eq!(t!("test_of", locale = "en-US", "0" => "a", "1" => "b"), "Test of a and b");
+        }
+    }
+
 }
\ No newline at end of file